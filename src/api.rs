@@ -0,0 +1,334 @@
+//! Library interface of the solver: build a game from a [`GameConfig`], solve it with
+//! [`Trainer::solve`] and query strategies, ranges, EVs and equities at a line.
+//!
+//! Lines are written like in UPI, `r:0` is the root action node followed by actions and dealt
+//! cards, e.g. `r:0:c:b50:c:Ks`. Per hand results are in [`hand_order`], 1326 entries.
+
+use crate::hand_range::*;
+use crate::range::*;
+use crate::trainer::*;
+use crate::postfloptree::*;
+use crate::best_response::*;
+use crate::equity::*;
+use crate::resolve::hand_order_index;
+use std::collections::HashMap;
+use serde::Serialize;
+use rust_poker::hand_range::{get_card_mask};
+
+/// Everything needed to build a game tree, the same settings as the UPI set_* and add_line commands
+#[derive(Debug, Clone)]
+pub struct GameConfig {
+    /// flop, turn or river, e.g. "Ks7d2c"
+    pub board: String,
+    pub oop_range: HandRange,
+    pub ip_range: HandRange,
+    /// pot at the start of the tree
+    pub pot: u32,
+    pub eff_stack: u32,
+    /// bet sequences, every entry is the total invested on the street after an action, see add_line
+    pub lines: Vec<Vec<u32>>,
+    /// board length the tree stops at with the equity realization of OOP and IP, see set_depth_limit
+    pub depth_limit: Option<(usize, f64, f64)>,
+}
+
+impl GameConfig {
+    pub fn new(board: &str, oop_range: HandRange, ip_range: HandRange, pot: u32, eff_stack: u32) -> GameConfig {
+        GameConfig { board: board.to_string(), oop_range, ip_range, pot, eff_stack, lines: vec![], depth_limit: None }
+    }
+
+    pub fn add_line(&mut self, line: Vec<u32>) {
+        self.lines.push(line);
+    }
+
+    /// Builds the tree, the trainer starts unsolved
+    pub fn build(&self) -> Result<Trainer, String> {
+        if !is_valid_board(&self.board) {
+            return Err(format!("invalid board {}", self.board));
+        }
+        if self.lines.is_empty() {
+            return Err("no lines".to_string());
+        }
+        if self.oop_range.hands.is_empty() || self.ip_range.hands.is_empty() {
+            return Err("empty range".to_string());
+        }
+        if let Some((limit, _, _)) = self.depth_limit {
+            if limit < self.board.len() {
+                return Err("depth limit before the starting street".to_string());
+            }
+        }
+
+        let mut range_manager = RangeManager::new(self.oop_range.clone(), self.ip_range.clone(), self.board.clone());
+        range_manager.depth_limit = self.depth_limit;
+        Ok(Trainer::new(range_manager, self.lines.clone(), self.eff_stack, self.pot))
+    }
+}
+
+pub fn is_valid_board(board: &str) -> bool {
+    board.len() >= 6 && board.len() <= 10 && board.len().is_multiple_of(2) && board.is_ascii()
+        && get_card_mask(board).count_ones() as usize == board.len() / 2
+}
+
+/// Hole cards in the order of all per hand results, e.g. 2d2c, 2h2c, ..., AsAh
+pub fn hand_order() -> Vec<String> {
+    get_hand_order()
+}
+
+pub fn hand_order_mapping() -> HashMap<String, usize> {
+    hand_order().into_iter().enumerate().map(|(i, hand)| (hand, i)).collect()
+}
+
+/// Total invested of a bet or raise action like "b50", none for other actions
+pub fn bet_size(action: &str) -> Option<u32> {
+    action.strip_prefix('b').and_then(|x| x.parse().ok())
+}
+
+/// Index of the bet or raise in actions whose size is nearest to size
+pub fn nearest_bet(size: u32, actions: &[String]) -> Option<usize> {
+    (0..actions.len()).filter(|&i| bet_size(&actions[i]).is_some())
+        .min_by_key(|&i| (bet_size(&actions[i]).unwrap() as i64 - size as i64).abs())
+}
+
+/// Average strategy of the player to act
+#[derive(Debug, Clone, Serialize)]
+pub struct NodeStrategy {
+    pub oop: bool,
+    /// last part of every child's line, e.g. "c", "b50" or "f"
+    pub actions: Vec<String>,
+    /// per action the frequency of every hand in hand order, 0 for hands not in the range
+    pub frequencies: Vec<Vec<f64>>,
+}
+
+/// Per hand values at a node in hand order
+#[derive(Debug, Clone, Serialize)]
+pub struct HandValues {
+    pub values: Vec<f64>,
+    /// villain reach not blocked by the hand, the weight of the hand's value
+    pub matchups: Vec<f64>,
+    /// average over the range reaching the node
+    pub total: f64,
+}
+
+/// Errors when the line doesn't exist in the built tree
+pub fn check_line(trainer: &Trainer, line: &str) -> Result<(), String> {
+    let actions: Vec<&str> = line.split(':').collect();
+    if actions[0] != "r" || (actions.len() > 1 && actions[1] != "0") {
+        return Err(format!("invalid line {}", line));
+    }
+
+    let mut current_line = "r:0".to_string();
+    for action in actions.iter().skip(2) {
+        let next_line = format!("{}:{}", current_line, action);
+        let children = trainer.root.get_children(current_line, &trainer.range_manager);
+        if !children.iter().any(|child| child.line == next_line) {
+            return Err(format!("couldn't find line {}", line));
+        }
+        current_line = next_line;
+    }
+    Ok(())
+}
+
+fn find_node<'a>(trainer: &'a Trainer, line: &str) -> Result<(String, &'a Node), String> {
+    check_line(trainer, line)?;
+    let (board, node, _, _, _, _) = trainer.root.find_node(&line.to_string(), &trainer.range_manager);
+    Ok((board, node))
+}
+
+pub fn get_strategy(trainer: &Trainer, line: &str) -> Result<NodeStrategy, String> {
+    let (_, node) = find_node(trainer, line)?;
+    let oop = match &node.node_type {
+        NodeType::ActionNode(node_info) => node_info.oop,
+        _ => return Err(format!("{} is not an action node", line)),
+    };
+    let actions = trainer.root.get_children(line.to_string(), &trainer.range_manager).iter()
+                        .map(|child| child.line.rsplit(':').next().unwrap().to_string())
+                        .collect();
+    let frequencies = trainer.root.get_strategy(line.to_string(), &trainer.range_manager, &hand_order_mapping());
+    Ok(NodeStrategy { oop, actions, frequencies })
+}
+
+/// Probability of every hand of the player to reach the line, including the starting weight
+pub fn get_range(trainer: &Trainer, oop: bool, line: &str) -> Result<Vec<f64>, String> {
+    check_line(trainer, line)?;
+    Ok(trainer.root.get_range(oop, line.to_string(), &trainer.range_manager, &hand_order_mapping()))
+}
+
+/// Frequency the line is played with, see calc_line_freq
+pub fn get_line_freq(trainer: &Trainer, line: &str) -> Result<f64, String> {
+    check_line(trainer, line)?;
+    Ok(trainer.root.get_line_freq(line.to_string(), &trainer.range_manager, &hand_order_mapping()))
+}
+
+// reach of both players at the node in the order of the range manager's ranges
+pub(crate) fn get_node_reach(trainer: &Trainer, line: &str, board_masks: (u64, Option<u64>), hand_order_mapping: &HashMap<String, usize>) -> (Vec<f64>, Vec<f64>) {
+    let range_manager = &trainer.range_manager;
+    let reach = |oop: bool| -> Vec<f64> {
+        let reach = trainer.root.get_range(oop, line.to_string(), range_manager, hand_order_mapping);
+        range_manager.get_range(oop, board_masks.0, board_masks.1).hands.iter().map(|c| reach[hand_order_index(c, hand_order_mapping)]).collect()
+    };
+    (reach(true), reach(false))
+}
+
+// spreads per combo values to hand order and averages them over the hero reach
+fn to_hand_values(hero_range: &[Combo], hero_reach: &[f64], values: Vec<f64>, matchups: Vec<f64>, hand_order_mapping: &HashMap<String, usize>) -> HandValues {
+    let mut hand_values = HandValues { values: vec![0.0; hand_order_mapping.len()], matchups: vec![0.0; hand_order_mapping.len()], total: 0.0 };
+    let mut weight_sum = 0.0;
+    for (i, combo) in hero_range.iter().enumerate() {
+        let idx = hand_order_index(combo, hand_order_mapping);
+        hand_values.values[idx] = values[i];
+        hand_values.matchups[idx] = matchups[i];
+        hand_values.total += values[i] * matchups[i] * hero_reach[i];
+        weight_sum += matchups[i] * hero_reach[i];
+    }
+    if weight_sum > 0.0 {
+        hand_values.total /= weight_sum;
+    }
+    hand_values
+}
+
+pub(crate) fn get_matchups(hero_range: &[Combo], villain_range: &[Combo], villain_reach: &[f64]) -> Vec<f64> {
+    hero_range.iter().map(|hero_combo| {
+        villain_range.iter().zip(villain_reach.iter())
+            .filter(|(villain_combo, _)| villain_combo.0 != hero_combo.0 && villain_combo.0 != hero_combo.1 && villain_combo.1 != hero_combo.0 && villain_combo.1 != hero_combo.1)
+            .map(|(_, reach)| reach)
+            .sum()
+    }).collect()
+}
+
+// counterfactual value of a hand as the part of the pot at the node it wins on average, 0 without matchups
+pub(crate) fn value_to_ev(value: f64, matchups: f64, pot: u32) -> f64 {
+    if matchups > 0.0 {
+        value / matchups / 2.0 + pot as f64 / 2.0
+    } else {
+        0.0
+    }
+}
+
+/// EV of every hand when both players play the average strategy from the line on. Like the MES
+/// it's the part of the pot at the node the hand wins on average
+pub fn get_ev(trainer: &Trainer, oop: bool, line: &str) -> Result<HandValues, String> {
+    let (board, node) = find_node(trainer, line)?;
+    let range_manager = &trainer.range_manager;
+    let hand_order_mapping = hand_order_mapping();
+    let board_masks = range_manager.get_board_masks(&board);
+    let (oop_reach, ip_reach) = get_node_reach(trainer, line, board_masks, &hand_order_mapping);
+    let (hero_reach, villain_reach) = if oop {
+        (oop_reach, ip_reach)
+    } else {
+        (ip_reach, oop_reach)
+    };
+    let hero_range = &range_manager.get_range(oop, board_masks.0, board_masks.1).hands;
+    let villain_range = &range_manager.get_range(!oop, board_masks.0, board_masks.1).hands;
+
+    let matchups = get_matchups(hero_range, villain_range, &villain_reach);
+    let values = get_counterfactual_values(range_manager, node, oop, &villain_reach, board_masks, false).iter().zip(matchups.iter())
+        .map(|(value, matchups)| value_to_ev(*value, *matchups, node.pot_size))
+        .collect();
+
+    Ok(to_hand_values(hero_range, &hero_reach, values, matchups, &hand_order_mapping))
+}
+
+/// EV of every hand of the player to act for each action, as part of the pot at the line like get_ev. A hand's
+/// EV at the line is the average of its action EVs weighted by its frequencies
+pub fn get_action_evs(trainer: &Trainer, line: &str) -> Result<Vec<HandValues>, String> {
+    let (board, node) = find_node(trainer, line)?;
+    let oop = match &node.node_type {
+        NodeType::ActionNode(node_info) => node_info.oop,
+        _ => return Err(format!("{} is not an action node", line)),
+    };
+    let range_manager = &trainer.range_manager;
+    let hand_order_mapping = hand_order_mapping();
+    let board_masks = range_manager.get_board_masks(&board);
+    let (oop_reach, ip_reach) = get_node_reach(trainer, line, board_masks, &hand_order_mapping);
+    let (hero_reach, villain_reach) = if oop {
+        (oop_reach, ip_reach)
+    } else {
+        (ip_reach, oop_reach)
+    };
+    let hero_range = &range_manager.get_range(oop, board_masks.0, board_masks.1).hands;
+    let villain_range = &range_manager.get_range(!oop, board_masks.0, board_masks.1).hands;
+
+    let matchups = get_matchups(hero_range, villain_range, &villain_reach);
+    // the villain reach doesn't change with hero's action
+    Ok(node.children.iter().map(|child| {
+        let values = get_counterfactual_values(range_manager, child, oop, &villain_reach, board_masks, false).iter().zip(matchups.iter())
+            .map(|(value, matchups)| value_to_ev(*value, *matchups, node.pot_size))
+            .collect();
+        to_hand_values(hero_range, &hero_reach, values, matchups.clone(), &hand_order_mapping)
+    }).collect())
+}
+
+/// All-in equity of every hand against the villain range reaching the line
+pub fn get_equity(trainer: &Trainer, oop: bool, line: &str) -> Result<HandValues, String> {
+    let (board, _) = find_node(trainer, line)?;
+    let range_manager = &trainer.range_manager;
+    let hand_order_mapping = hand_order_mapping();
+    let board_masks = range_manager.get_board_masks(&board);
+    let (oop_reach, ip_reach) = get_node_reach(trainer, line, board_masks, &hand_order_mapping);
+    let oop_range = &range_manager.get_range(true, board_masks.0, board_masks.1).hands;
+    let ip_range = &range_manager.get_range(false, board_masks.0, board_masks.1).hands;
+    let equity_matrix = calc_equity_matrix(oop_range, ip_range, board_masks.0);
+
+    let (hero_range, hero_reach, villain_range, villain_reach) = if oop {
+        (oop_range, &oop_reach, ip_range, &ip_reach)
+    } else {
+        (ip_range, &ip_reach, oop_range, &oop_reach)
+    };
+    let mut values = vec![0.0; hero_range.len()];
+    let mut matchups = vec![0.0; hero_range.len()];
+    for i in 0..hero_range.len() {
+        for j in 0..villain_range.len() {
+            let equity = if oop {
+                equity_matrix[i*ip_range.len() + j]
+            } else {
+                equity_matrix[j*ip_range.len() + i].map(|x| 1.0 - x)
+            };
+            if let Some(equity) = equity {
+                values[i] += equity * villain_reach[j];
+                matchups[i] += villain_reach[j];
+            }
+        }
+        if matchups[i] > 0.0 {
+            values[i] /= matchups[i];
+        }
+    }
+
+    Ok(to_hand_values(hero_range, hero_reach, values, matchups, &hand_order_mapping))
+}
+
+/// How much each player gains by best responding from a node on, per street starting with the
+/// node's street, in chips per matchup reaching the node
+#[derive(Debug, Clone, Serialize)]
+pub struct NodeExploitability {
+    pub board: String,
+    pub pot: u32,
+    pub oop_gains: Vec<f64>,
+    pub ip_gains: Vec<f64>,
+}
+
+impl NodeExploitability {
+    /// same as the solver's exploitability at the root
+    pub fn exploitability(&self) -> f64 {
+        (self.oop_gains.iter().sum::<f64>() + self.ip_gains.iter().sum::<f64>()) / 2.0
+    }
+}
+
+pub fn get_node_exploitability(trainer: &Trainer, line: &str) -> Result<NodeExploitability, String> {
+    let (board, node) = find_node(trainer, line)?;
+    let range_manager = &trainer.range_manager;
+    let hand_order_mapping = hand_order_mapping();
+    let board_masks = range_manager.get_board_masks(&board);
+    let (oop_reach, ip_reach) = get_node_reach(trainer, line, board_masks, &hand_order_mapping);
+    let oop_gains = get_street_gains(range_manager, node, true, &oop_reach, &ip_reach, board_masks);
+    let ip_gains = get_street_gains(range_manager, node, false, &ip_reach, &oop_reach, board_masks);
+    Ok(NodeExploitability { board, pot: node.pot_size, oop_gains, ip_gains })
+}
+
+// spot the module tests share, on a river board it solves in a few milliseconds
+#[cfg(test)]
+pub(crate) fn test_config(board: &str) -> GameConfig {
+    let mut config = GameConfig::new(board, HandRange::from_string("AA,KK,QQ,AK".to_string()), HandRange::from_string("AA,KK,JJ,AQ".to_string()), 50, 100);
+    config.add_line(vec![0, 0]);
+    config.add_line(vec![25, 25]);
+    config.add_line(vec![0, 25, 25]);
+    config
+}
@@ -0,0 +1,166 @@
+use crate::postfloptree::*;
+use crate::range::*;
+use crate::hand_range::*;
+use crate::trainer::*;
+use crate::best_response::*;
+use crate::cfr::*;
+use crate::isomorphism::*;
+use std::fs::File;
+use std::io::Write;
+use std::sync::atomic::{AtomicBool, Ordering};
+use rust_poker::hand_range::{get_card_mask};
+
+// everything needed to build and solve a tree, except the board
+#[derive(Debug, Clone)]
+pub struct TreeTemplate {
+    pub oop_range: HandRange,
+    pub ip_range: HandRange,
+    pub eff_stack: u32,
+    pub pot: u32,
+    pub lines: Vec<Vec<u32>>,
+    pub depth_limit: Option<(usize, f64, f64)>,
+    pub algorithm: CfrAlgorithm,
+    // chance sampling and the exploitability % of pot to switch back to full traversal
+    pub sampling: (ChanceSampling, f64),
+    pub pruning_interval: Option<u64>,
+    pub info_freq: u64,
+}
+
+#[derive(Debug)]
+pub struct FlopResult {
+    pub flop: String,
+    pub weight: f64,
+    pub actions: Vec<ActionType>,
+    pub frequencies: Vec<f64>,
+    pub oop_ev: f64,
+    pub ip_ev: f64,
+    pub exploitability: f64,
+    pub pot: u32,
+}
+
+impl FlopResult {
+    pub fn exploitability_pct(&self) -> f64 {
+        self.exploitability / self.pot as f64 * 100.0
+    }
+}
+
+// range weighted frequency of each root action for OOP
+fn get_root_frequencies(trainer: &Trainer) -> (Vec<ActionType>, Vec<f64>) {
+    let root_node = &trainer.root.children[0].children[0];
+    match &root_node.node_type {
+        NodeType::ActionNode(node_info) => {
+            let reach_probs = trainer.range_manager.get_initial_reach_probs(node_info.oop);
+            let average_strategy = node_info.get_average_strategy();
+            let mut frequencies = vec![0.0; node_info.actions_num];
+            let reach_sum: f64 = reach_probs.iter().sum();
+
+            for (j, action_strategy) in average_strategy.chunks_exact(reach_probs.len().max(1)).enumerate() {
+                for (action_freq, reach_prob) in action_strategy.iter().zip(reach_probs.iter()) {
+                    frequencies[j] += action_freq * reach_prob / reach_sum;
+                }
+            }
+
+            (node_info.actions.clone(), frequencies)
+        },
+        _ => panic!("Root should be an action node"),
+    }
+}
+
+pub fn solve_flop(template: &TreeTemplate, flop: &str, weight: f64, accuracy: &Accuracy, train_finish: TrainFinish, cancel: Option<&AtomicBool>, on_progress: &mut dyn FnMut(&Progress)) -> FlopResult {
+    let mut range_manager = RangeManager::new(template.oop_range.clone(), template.ip_range.clone(), flop.to_string());
+    range_manager.depth_limit = template.depth_limit;
+    let mut trainer = Trainer::new(range_manager, template.lines.clone(), template.eff_stack, template.pot);
+    trainer.algorithm = template.algorithm;
+    trainer.sampling = template.sampling.0;
+    trainer.sampling_polish = template.sampling.1;
+    trainer.pruning_interval = template.pruning_interval;
+    trainer.info_freq = template.info_freq;
+    let exploitability = trainer.solve(accuracy, train_finish, cancel, on_progress);
+
+    let (actions, frequencies) = get_root_frequencies(&trainer);
+    let mut best_response = BestResponse::new(&trainer.range_manager);
+    best_response.set_relative_probablities(true);
+    best_response.set_relative_probablities(false);
+    let oop_ev = best_response.get_ev(true, &trainer.root)/2.0 + template.pot as f64 / 2.0;
+    let ip_ev = best_response.get_ev(false, &trainer.root)/2.0 + template.pot as f64 / 2.0;
+
+    FlopResult { flop: flop.to_string(), weight, actions, frequencies, oop_ev, ip_ev, exploitability, pot: template.pot }
+}
+
+// on_progress gets the index of the flop with every exploitability check, cancel stops after the current flop
+pub fn solve_batch(template: &TreeTemplate, flops: &[(String, f64)], accuracy: &Accuracy, train_finish: TrainFinish, cancel: Option<&AtomicBool>, on_progress: &mut dyn FnMut(usize, &Progress)) -> Vec<FlopResult> {
+    let mut results = vec![];
+
+    for (i, (flop, weight)) in flops.iter().enumerate() {
+        results.push(solve_flop(template, flop, *weight, accuracy, train_finish, cancel, &mut |progress| on_progress(i, progress)));
+        if cancel.is_some_and(|cancel| cancel.load(Ordering::Relaxed)) {
+            break;
+        }
+    }
+
+    results
+}
+
+// a named subset of flops with their weights or comma separated flops weighted 1
+pub fn parse_flops(flops: &str) -> Result<Vec<(String, f64)>, String> {
+    if let Some(subset) = get_named_flop_subset(flops) {
+        return Ok(subset.into_iter().map(|(flop, weight)| (flop, weight as f64)).collect());
+    }
+    let mut parsed = vec![];
+    for flop in flops.split(',') {
+        if !is_valid_flop(flop) {
+            return Err(format!("invalid flop {}", flop));
+        }
+        parsed.push((flop.to_string(), 1.0));
+    }
+    Ok(parsed)
+}
+
+pub fn is_valid_flop(flop: &str) -> bool {
+    flop.len() == 6 && get_card_mask(flop).count_ones() == 3
+}
+
+// csv report with one row per flop and a weighted aggregate row at the end
+pub fn write_batch_report(results: &Vec<FlopResult>, path: &str) -> std::io::Result<()> {
+    let mut file = File::create(path)?;
+
+    let actions = match results.first() {
+        Some(result) => result.actions.clone(),
+        None => vec![],
+    };
+
+    write!(file, "flop,weight,oop_ev,ip_ev,exploitability,exploitability_pct")?;
+    for action in &actions {
+        write!(file, ",{}", action)?;
+    }
+    writeln!(file)?;
+
+    let mut total_weight = 0.0;
+    let mut aggregate = vec![0.0; 4 + actions.len()];
+    for result in results {
+        write!(file, "{},{},{},{},{},{}", result.flop, result.weight, result.oop_ev, result.ip_ev, result.exploitability, result.exploitability_pct())?;
+        for freq in &result.frequencies {
+            write!(file, ",{}", freq)?;
+        }
+        writeln!(file)?;
+
+        total_weight += result.weight;
+        aggregate[0] += result.weight * result.oop_ev;
+        aggregate[1] += result.weight * result.ip_ev;
+        aggregate[2] += result.weight * result.exploitability;
+        aggregate[3] += result.weight * result.exploitability_pct();
+        for (j, freq) in result.frequencies.iter().enumerate() {
+            aggregate[4+j] += result.weight * freq;
+        }
+    }
+
+    if total_weight > 0.0 {
+        write!(file, "total,{}", total_weight)?;
+        for value in &aggregate {
+            write!(file, ",{}", value / total_weight)?;
+        }
+        writeln!(file)?;
+    }
+
+    Ok(())
+}
@@ -25,6 +25,15 @@ impl<'a> BestResponse<'a> {
     }
     
     pub fn get_best_response_ev(&mut self, pos: bool, root: &Node) -> f64 {
+        self.get_root_ev(pos, root, true)
+    }
+    
+    // ev of pos when both players play their average strategy
+    pub fn get_ev(&mut self, pos: bool, root: &Node) -> f64 {
+        self.get_root_ev(pos, root, false)
+    }
+    
+    fn get_root_ev(&mut self, pos: bool, root: &Node, best_response: bool) -> f64 {
         let mut total_ev = 0.0;
         
         let villain_pos = pos ^ true;
@@ -41,7 +50,7 @@ impl<'a> BestResponse<'a> {
         let villain_reach_probs = self.range_manager.get_initial_reach_probs(villain_pos);
         
         let mut ev_results = vec![];
-        let mut new_br = BestResponseState::new(self.range_manager, &mut ev_results, root, pos, &villain_reach_probs, (board_mask, None));
+        let mut new_br = BestResponseState::new(self.range_manager, &mut ev_results, root, pos, &villain_reach_probs, (board_mask, None), best_response_from(best_response), true);
         new_br.run();
         
         for i in 0..hero_hands {
@@ -88,18 +97,13 @@ impl<'a> BestResponse<'a> {
         
     }
     
-    pub fn print_exploitability(&mut self, root: &Node, time_elapsed: f64) -> f64 {
+    // OOP's MES, IP's MES and exploitability
+    pub fn get_exploitability(&mut self, root: &Node) -> (f64, f64, f64) {
         let oop_ev = self.get_best_response_ev(true, root);
         let ip_ev = self.get_best_response_ev(false, root);
         
         let exploitability = (oop_ev/2.0 + ip_ev/2.0) / 2.0;
-        println!("SOLVER:");
-        println!("running time: {}", time_elapsed);
-        println!("OOP's MES: {}", oop_ev/2.0 + (root.pot_size as f64 / 2.0) );
-        println!("IP's MES: {}", ip_ev/2.0 + (root.pot_size as f64 / 2.0));
-        println!("Exploitable for: {} ({}%)", exploitability, exploitability / (root.pot_size as f64) * 100.0);
-        println!("END \n");
-        exploitability
+        (oop_ev/2.0 + (root.pot_size as f64 / 2.0), ip_ev/2.0 + (root.pot_size as f64 / 2.0), exploitability)
     }
 }
 
@@ -131,22 +135,69 @@ struct BestResponseState<'a> {
     oop: bool,
     villain_reach_probs: &'a Vec<f64>,
     board_masks: (u64, Option<u64>),
+    // hero best responds at action nodes with at least this many board cards, the average strategy is played before
+    best_response_from: u32,
+    // no parallel level above, the action children of every node without a card split below run in parallel
+    parallel_actions: bool,
+}
+
+fn best_response_from(best_response: bool) -> u32 {
+    if best_response {
+        0
+    } else {
+        u32::MAX
+    }
+}
+
+// raw counterfactual values of pos at node against the given villain reach, best_response false plays the average strategy
+pub fn get_counterfactual_values(range_manager: &RangeManager, node: &Node, pos: bool, villain_reach_probs: &Vec<f64>, board_masks: (u64, Option<u64>), best_response: bool) -> Vec<f64> {
+    let mut results = vec![];
+    recursive_br(range_manager, &mut results, node, pos, villain_reach_probs, board_masks, best_response_from(best_response), true);
+    results
+}
+
+// best response gain of pos at node split by street, in chips per matchup reaching the node. The gain of a street
+// is what hero wins on top by also best responding there when already best responding on all later streets,
+// so the gains add up to the total
+pub fn get_street_gains(range_manager: &RangeManager, node: &Node, pos: bool, hero_reach_probs: &[f64], villain_reach_probs: &Vec<f64>, board_masks: (u64, Option<u64>)) -> Vec<f64> {
+    let hero_range = &range_manager.get_range(pos, board_masks.0, board_masks.1).hands;
+    let villain_range = &range_manager.get_range(pos ^ true, board_masks.0, board_masks.1).hands;
+    let mut matchups = 0.0;
+    for (hero_combo, hero_reach) in hero_range.iter().zip(hero_reach_probs.iter()) {
+        for (villain_combo, villain_reach) in villain_range.iter().zip(villain_reach_probs.iter()) {
+            if !overlap_combos(*hero_combo, *villain_combo) {
+                matchups += hero_reach * villain_reach;
+            }
+        }
+    }
+    if matchups == 0.0 {
+        return vec![0.0; 6 - board_masks.0.count_ones() as usize];
+    }
+    
+    let mut values = vec![];
+    for best_response_from in (board_masks.0.count_ones()..6).chain(std::iter::once(u32::MAX)) {
+        let mut results = vec![];
+        recursive_br(range_manager, &mut results, node, pos, villain_reach_probs, board_masks, best_response_from, true);
+        values.push(results.iter().zip(hero_reach_probs.iter()).map(|(value, reach)| value * reach).sum::<f64>());
+    }
+    
+    values.windows(2).map(|x| (x[0] - x[1]) / matchups / 2.0).collect()
 }
 
-fn recursive_br(range_manager: &RangeManager, results: &mut Vec<f64>, child: &Node, oop: bool, villain_reach_probs: &Vec<f64>, board_masks: (u64, Option<u64>)) {
-    let mut new_br = BestResponseState::new(range_manager, results, child, oop, villain_reach_probs, board_masks);
+fn recursive_br(range_manager: &RangeManager, results: &mut Vec<f64>, child: &Node, oop: bool, villain_reach_probs: &Vec<f64>, board_masks: (u64, Option<u64>), best_response_from: u32, parallel_actions: bool) {
+    let mut new_br = BestResponseState::new(range_manager, results, child, oop, villain_reach_probs, board_masks, best_response_from, parallel_actions);
     new_br.run();
 }
 
 impl<'a> BestResponseState<'a> {
-    fn new(range_manager: &'a RangeManager, result: &'a mut Vec<f64>, node: &'a Node, oop: bool, villain_reach_probs: &'a Vec<f64>, board_masks: (u64, Option<u64>) ) -> BestResponseState<'a> {
-        BestResponseState { range_manager, result, node, oop, villain_reach_probs, board_masks }
+    fn new(range_manager: &'a RangeManager, result: &'a mut Vec<f64>, node: &'a Node, oop: bool, villain_reach_probs: &'a Vec<f64>, board_masks: (u64, Option<u64>), best_response_from: u32, parallel_actions: bool) -> BestResponseState<'a> {
+        BestResponseState { range_manager, result, node, oop, villain_reach_probs, board_masks, best_response_from, parallel_actions }
     }
     
     pub fn run(&mut self) {       
         match self.node.node_type {
             NodeType::TerminalNode(terminal_type) => {
-                *self.result = get_payoffs(self.oop, self.range_manager, self.board_masks, self.node, self.villain_reach_probs, &terminal_type);
+                get_payoffs(self.result, self.oop, self.range_manager, self.board_masks, self.node, self.villain_reach_probs, &terminal_type);
             },
             NodeType::ChanceNode(deck_left) => { 
                 let hero_hands = if self.oop == true {
@@ -155,7 +206,9 @@ impl<'a> BestResponseState<'a> {
                     self.node.ip_num_hands
                 };
                 
-                *self.result = vec![0.0; hero_hands];
+                reset_buffer(self.result, hero_hands);
+                // cards are the parallel level
+                let parallel_actions = self.parallel_actions && self.node.children.len() == 1;
                 let results: Vec<_> = self.node.children.par_iter()
                                                         .map(|val| {
                                                             let new_masks = match val.node_type {
@@ -163,12 +216,12 @@ impl<'a> BestResponseState<'a> {
                                                                     _ => panic!("panicando!"),
                                                                 };
                                                             
-                                                            let mut results = vec![0.0; hero_hands];
+                                                            let mut results = get_buffer(0);
                                                             if deck_left == 0 {
-                                                                recursive_br(self.range_manager, &mut results, val, self.oop, self.villain_reach_probs, new_masks);
+                                                                recursive_br(self.range_manager, &mut results, val, self.oop, self.villain_reach_probs, new_masks, self.best_response_from, parallel_actions);
                                                             } else {
                                                                 let new_villain_reach_prob = self.range_manager.get_villain_reach(self.oop, new_masks.0, new_masks.1, self.villain_reach_probs);
-                                                                recursive_br(self.range_manager, &mut results, val, self.oop, &new_villain_reach_prob, new_masks);
+                                                                recursive_br(self.range_manager, &mut results, val, self.oop, &new_villain_reach_prob, new_masks, self.best_response_from, parallel_actions);
                                                             }
                                                             results
                                                         })
@@ -193,13 +246,21 @@ impl<'a> BestResponseState<'a> {
                         }
                     }
                 }
+                
+                for child_results in results {
+                    return_buffer(child_results);
+                }
             }, 
             NodeType::ChanceNodeCard(_) => { 
-                let mut new_br = BestResponseState::new(self.range_manager, self.result,  &self.node.children[0], self.oop, self.villain_reach_probs, self.board_masks);
+                let mut new_br = BestResponseState::new(self.range_manager, self.result,  &self.node.children[0], self.oop, self.villain_reach_probs, self.board_masks, self.best_response_from, self.parallel_actions);
                 new_br.run();
             }, 
             NodeType::ActionNode(ref node_info) => {
-                let n_actions = node_info.actions_num;                
+                let n_actions = node_info.actions_num;
+                // action children are the parallel level when no card is dealt below
+                let parallel = self.parallel_actions && !self.node.card_split;
+                let parallel_actions = self.parallel_actions;
+                let (range_manager, oop, villain_reach_probs, board_masks, best_response_from) = (self.range_manager, self.oop, self.villain_reach_probs, self.board_masks, self.best_response_from);
                 if node_info.oop == self.oop {
                     let hero_hands = if self.oop == true {
                         self.node.oop_num_hands
@@ -207,27 +268,41 @@ impl<'a> BestResponseState<'a> {
                         self.node.ip_num_hands
                     };
                 
-                    *self.result = vec![f64::MIN; hero_hands];
-   
-                    let results: Vec<_> = self.node.children.par_iter()
-                                                            .map(|val| {
-                                                                let mut results = vec![0.0; hero_hands];
-                                                                recursive_br(self.range_manager, &mut results, val, self.oop, self.villain_reach_probs, self.board_masks);
-                                                                results
-                                                            })
-                                                            .collect();
+                    let mut results: Vec<_> = (0..n_actions).map(|_| get_buffer(hero_hands)).collect();
+                    if parallel {
+                        self.node.children.par_iter().zip(results.par_iter_mut()).for_each(|(child, result)| {
+                            recursive_br(range_manager, result, child, oop, villain_reach_probs, board_masks, best_response_from, parallel_actions);
+                        });
+                    } else {
+                        for (count, child) in self.node.children.iter().enumerate() {
+                            recursive_br(range_manager, &mut results[count], child, oop, villain_reach_probs, board_masks, best_response_from, parallel_actions);
+                        }
+                    }
                     
-                    for (i,result) in self.result.iter_mut().enumerate() {
-                        for results_j in results.iter() {
-                            if results_j[i] > *result {
-                                *result = results_j[i];
+                    if self.board_masks.0.count_ones() >= self.best_response_from {
+                        reset_buffer(self.result, hero_hands);
+                        for (i,result) in self.result.iter_mut().enumerate() {
+                            *result = f64::MIN;
+                            for results_j in results.iter() {
+                                if results_j[i] > *result {
+                                    *result = results_j[i];
+                                }
+                            }
+                        }
+                    } else {
+                        let average_strategy = node_info.get_average_strategy();
+                        reset_buffer(self.result, hero_hands);
+                        for (action_strategy, results_j) in average_strategy.chunks_exact(hero_hands).zip(results.iter()) {
+                            for ((result, action_freq), result_j) in self.result.iter_mut().zip(action_strategy.iter()).zip(results_j.iter()) {
+                                *result += action_freq * result_j;
                             }
                         }
                     }
                     
-                    
+                    for results_j in results {
+                        return_buffer(results_j);
+                    }
                 } else {
-                    let villain_pos = self.oop ^ true;
                     let average_strategy = node_info.get_average_strategy();
                     let hero_hands = if self.oop == true {
                         self.node.oop_num_hands
@@ -239,30 +314,41 @@ impl<'a> BestResponseState<'a> {
                     } else {
                         self.node.oop_num_hands
                     };               
-                    *self.result = vec![0.0; hero_hands];
+                    reset_buffer(self.result, hero_hands);
+                    let mut results = get_buffer(hero_hands);
+                    let mut new_villain_reach_prob = get_buffer(villain_hands);
        
-                    let results: Vec<_> = self.node.children.par_iter()
-                                                            .enumerate()
-                                                            .map(|(count, val)| {
-                                                                let mut results = vec![0.0; hero_hands];
-                                                                let mut offset = 0;
-                                                                let mut new_villain_reach_prob = vec![0.0; villain_hands];
-                                                                for (i, reach_prob) in new_villain_reach_prob.iter_mut().enumerate() {
-                                                                    *reach_prob = average_strategy[offset+count] * self.villain_reach_probs[i];
-                                                                    
-                                                                    offset += n_actions;
-                                                                }
-                                                                recursive_br(self.range_manager, &mut results, val, self.oop, &new_villain_reach_prob, self.board_masks);
-                                                                results
-                                                            })
-                                                            .collect();
-                    
-                    for (i, result) in self.result.iter_mut().enumerate() {
-                        for results_j in results.iter() {
-                            *result += results_j[i];
+                    if parallel {
+                        let child_results: Vec<_> = self.node.children.par_iter().zip(average_strategy.par_chunks_exact(villain_hands)).map(|(child, action_strategy)| {
+                            let mut new_villain_reach_prob = get_buffer(villain_hands);
+                            for ((reach_prob, action_freq), villain_reach_prob) in new_villain_reach_prob.iter_mut().zip(action_strategy.iter()).zip(villain_reach_probs.iter()) {
+                                *reach_prob = action_freq * villain_reach_prob;
+                            }
+                            let mut results = get_buffer(hero_hands);
+                            recursive_br(range_manager, &mut results, child, oop, &new_villain_reach_prob, board_masks, best_response_from, parallel_actions);
+                            return_buffer(new_villain_reach_prob);
+                            results
+                        }).collect();
+                        for child_result in child_results {
+                            for (result, child_result) in self.result.iter_mut().zip(child_result.iter()) {
+                                *result += child_result;
+                            }
+                            return_buffer(child_result);
+                        }
+                    } else {
+                        for (child, action_strategy) in self.node.children.iter().zip(average_strategy.chunks_exact(villain_hands)) {
+                            for ((reach_prob, action_freq), villain_reach_prob) in new_villain_reach_prob.iter_mut().zip(action_strategy.iter()).zip(villain_reach_probs.iter()) {
+                                *reach_prob = action_freq * villain_reach_prob;
+                            }
+                            recursive_br(range_manager, &mut results, child, oop, &new_villain_reach_prob, board_masks, best_response_from, parallel_actions);
+                            for (result, child_result) in self.result.iter_mut().zip(results.iter()) {
+                                *result += child_result;
+                            }
                         }
                     }
-                                        
+                    
+                    return_buffer(results);
+                    return_buffer(new_villain_reach_prob);
                 }
                 
             },
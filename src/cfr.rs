@@ -3,6 +3,48 @@ use crate::postfloptree::*;
 use rust_poker::constants::RANK_TO_CHAR;
 use rust_poker::constants::SUIT_TO_CHAR;
 use rayon::prelude::*;
+use rand::seq::index::sample;
+use std::cell::RefCell;
+
+#[derive(Debug,Clone,Copy)]
+pub enum ChanceSampling {
+    Full,
+    // traverse only this many randomly sampled cards at every turn/river chance node
+    Sampled(usize),
+}
+
+// villain subtrees reached with less than this probability by every villain hand are skipped when pruning
+const PRUNE_REACH_THRESHOLD: f64 = 1e-9;
+
+#[derive(Debug,Clone,Copy)]
+pub struct CfrSettings {
+    pub algorithm: CfrAlgorithm,
+    pub sampling: ChanceSampling,
+    pub pruning: bool,
+    // no parallel level above, the action children of every node without a card split below run in parallel
+    pub parallel_actions: bool,
+}
+
+thread_local! {
+    // scratch vectors for the sequential part of the traversal, reused instead of allocated at every node
+    static BUFFER_POOL: RefCell<Vec<Vec<f64>>> = RefCell::new(vec![]);
+}
+
+// zeroed vector of length len from the pool of the current thread
+pub fn get_buffer(len: usize) -> Vec<f64> {
+    let mut buffer = BUFFER_POOL.with(|pool| pool.borrow_mut().pop()).unwrap_or_default();
+    reset_buffer(&mut buffer, len);
+    buffer
+}
+
+pub fn return_buffer(buffer: Vec<f64>) {
+    BUFFER_POOL.with(|pool| pool.borrow_mut().push(buffer));
+}
+
+pub fn reset_buffer(buffer: &mut Vec<f64>, len: usize) {
+    buffer.clear();
+    buffer.resize(len, 0.0);
+}
 
 pub struct CfrState<'a> {
     range_manager: &'a RangeManager,
@@ -12,21 +54,22 @@ pub struct CfrState<'a> {
     villain_reach_probs: &'a Vec<f64>,
     board_masks: (u64, Option<u64>),
     n_iterations: u64,
+    settings: CfrSettings,
 }
 
-fn recursive_cfr(range_manager: &RangeManager, results: &mut Vec<f64>, child: &mut Node, oop: bool, villain_reach_probs: &Vec<f64>, board_masks: (u64, Option<u64>), n_iterations: u64) {
-    let mut new_cfr = CfrState::new(range_manager, results, child, oop, villain_reach_probs, board_masks, n_iterations);
+fn recursive_cfr(range_manager: &RangeManager, results: &mut Vec<f64>, child: &mut Node, oop: bool, villain_reach_probs: &Vec<f64>, board_masks: (u64, Option<u64>), n_iterations: u64, settings: CfrSettings) {
+    let mut new_cfr = CfrState::new(range_manager, results, child, oop, villain_reach_probs, board_masks, n_iterations, settings);
     new_cfr.run();
 }
 
 impl<'a> CfrState<'a> {
-    pub fn new(range_manager: &'a RangeManager, result: &'a mut Vec<f64>, node: &'a mut Node, oop: bool, villain_reach_probs: &'a Vec<f64>, board_masks: (u64, Option<u64>), n_iterations: u64) -> CfrState<'a> {
-        CfrState { range_manager, result, node, oop, villain_reach_probs, board_masks, n_iterations }
+    pub fn new(range_manager: &'a RangeManager, result: &'a mut Vec<f64>, node: &'a mut Node, oop: bool, villain_reach_probs: &'a Vec<f64>, board_masks: (u64, Option<u64>), n_iterations: u64, settings: CfrSettings) -> CfrState<'a> {
+        CfrState { range_manager, result, node, oop, villain_reach_probs, board_masks, n_iterations, settings }
     }
     pub fn run(&mut self) {       
         match self.node.node_type {
             NodeType::TerminalNode(terminal_type) => {
-                *self.result = get_payoffs(self.oop, self.range_manager, self.board_masks, self.node, self.villain_reach_probs, &terminal_type);
+                get_payoffs(self.result, self.oop, self.range_manager, self.board_masks, self.node, self.villain_reach_probs, &terminal_type);
             },
             NodeType::ChanceNode(deck_left) => {
                 let hero_hands = if self.oop == true {
@@ -35,28 +78,52 @@ impl<'a> CfrState<'a> {
                     self.node.ip_num_hands
                 };
 
-                *self.result = vec![0.0; hero_hands];
+                reset_buffer(self.result, hero_hands);
                 
+                // with sampling the sum over all cards is estimated from the sampled ones
+                let n_children = self.node.children.len();
+                let mut selected = vec![true; n_children];
+                let mut sample_scale = 1.0;
+                if let ChanceSampling::Sampled(n_cards) = self.settings.sampling {
+                    if deck_left != 0 && n_cards < n_children {
+                        selected = vec![false; n_children];
+                        for idx in sample(&mut rand::thread_rng(), n_children, n_cards).iter() {
+                            selected[idx] = true;
+                        }
+                        sample_scale = n_children as f64 / n_cards as f64;
+                    }
+                }
+                
+                // cards are the parallel level, every card is traversed sequentially by one worker
+                let settings = CfrSettings { parallel_actions: self.settings.parallel_actions && n_children == 1, ..self.settings };
                 let results: Vec<_> = self.node.children.par_iter_mut()
-                                                            .map(|val| {
+                                                            .enumerate()
+                                                            .map(|(count, val)| {
+                                                                if selected[count] == false {
+                                                                    return None;
+                                                                }
                                                                 let new_masks = match val.node_type {
                                                                     NodeType::ChanceNodeCard((new,old)) => (new,old),
                                                                     _ => panic!("panicando!"),
                                                                 };
                                                                 
-                                                                let mut results = vec![0.0; hero_hands];
+                                                                let mut results = get_buffer(0);
                                                                 if deck_left == 0 {
-                                                                    recursive_cfr(self.range_manager, &mut results, val, self.oop, self.villain_reach_probs, new_masks, self.n_iterations);
+                                                                    recursive_cfr(self.range_manager, &mut results, val, self.oop, self.villain_reach_probs, new_masks, self.n_iterations, settings);
                                                                 } else {
                                                                     let new_villain_reach_prob = self.range_manager.get_villain_reach(self.oop, new_masks.0, new_masks.1, self.villain_reach_probs);
-                                                                    recursive_cfr(self.range_manager, &mut results, val, self.oop, &new_villain_reach_prob, new_masks, self.n_iterations);
+                                                                    recursive_cfr(self.range_manager, &mut results, val, self.oop, &new_villain_reach_prob, new_masks, self.n_iterations, settings);
                                                                 }
-                                                                results
+                                                                Some(results)
                                                             })
                                                             .collect();
                 
                 if deck_left != 0 {
                     for (count,child) in self.node.children.iter_mut().enumerate() {
+                        let child_results = match &results[count] {
+                            Some(x) => x,
+                            None => continue,
+                        };
                         let new_masks = match child.node_type {
                             NodeType::ChanceNodeCard((new,old)) => (new,old),
                             _ => panic!("panicando!"),
@@ -64,23 +131,29 @@ impl<'a> CfrState<'a> {
                         let reach_mapping = self.range_manager.get_reach_mapping(self.oop, new_masks.0, new_masks.1);
                         
                         for (i, mapping) in reach_mapping.iter().enumerate() {
-                            self.result[*mapping as usize] += results[count][i] * (1.0/deck_left as f64);
+                            self.result[*mapping as usize] += child_results[i] * (sample_scale/deck_left as f64);
                         }
                     }
                 } else {
                     for i in 0..hero_hands {
-                        for (count,_) in self.node.children.iter_mut().enumerate() {
-                            self.result[i] += results[count][i];
+                        for child_results in results.iter() {
+                            self.result[i] += child_results.as_ref().unwrap()[i];
                         }
                     }
                 }
+                
+                for child_results in results.into_iter().flatten() {
+                    return_buffer(child_results);
+                }
             },
             NodeType::ChanceNodeCard(_) => { 
-                let mut new_cfr = CfrState::new(self.range_manager, self.result, &mut self.node.children[0], self.oop, self.villain_reach_probs, self.board_masks, self.n_iterations);
+                let mut new_cfr = CfrState::new(self.range_manager, self.result, &mut self.node.children[0], self.oop, self.villain_reach_probs, self.board_masks, self.n_iterations, self.settings);
                 new_cfr.run();
             },            
             NodeType::ActionNode(ref mut node_info) => {
                 let n_actions = node_info.actions_num;
+                // action children are the parallel level when no card is dealt below
+                let parallel = self.settings.parallel_actions && !self.node.card_split;
                 
                 if node_info.oop == self.oop {
                     let hero_hands = if self.oop == true {
@@ -89,39 +162,54 @@ impl<'a> CfrState<'a> {
                         self.node.ip_num_hands
                     };
 					if n_actions == 1 {
-						recursive_cfr(self.range_manager, self.result, &mut self.node.children[0], self.oop, self.villain_reach_probs, self.board_masks, self.n_iterations);
+						recursive_cfr(self.range_manager, self.result, &mut self.node.children[0], self.oop, self.villain_reach_probs, self.board_masks, self.n_iterations, self.settings);
 					} else {
 						let current_strategy = node_info.get_current_strategy();
-						*self.result = vec![0.0; hero_hands];
+						let pruned = if self.settings.pruning {
+							node_info.get_prunable_actions(&current_strategy)
+						} else {
+							vec![false; n_actions]
+						};
 		   
-						let results: Vec<_> = self.node.children.par_iter_mut()
-																.map(|val| {
-																	let mut results = vec![0.0; hero_hands];
-																	recursive_cfr(self.range_manager, &mut results, val, self.oop, self.villain_reach_probs, self.board_masks, self.n_iterations);
-																	results
-																})
-																.collect();
-						
+						let mut results: Vec<_> = (0..n_actions).map(|_| get_buffer(hero_hands)).collect();
+						let (range_manager, oop, villain_reach_probs, board_masks, n_iterations) = (self.range_manager, self.oop, self.villain_reach_probs, self.board_masks, self.n_iterations);
+						let settings = self.settings;
+						if parallel {
+							self.node.children.par_iter_mut().zip(results.par_iter_mut()).enumerate().for_each(|(count, (child, result))| {
+								if pruned[count] == false {
+									recursive_cfr(range_manager, result, child, oop, villain_reach_probs, board_masks, n_iterations, settings);
+								}
+							});
+						} else {
+							for (count, child) in self.node.children.iter_mut().enumerate() {
+								if pruned[count] == false {
+									recursive_cfr(range_manager, &mut results[count], child, oop, villain_reach_probs, board_masks, n_iterations, settings);
+								}
+							}
+						}
 						
 						for (i, results_i) in results.iter().enumerate() {
-							node_info.update_regret_sum_1(results_i, i)
+							if pruned[i] == false {
+								node_info.update_regret_sum_1(results_i, i)
+							}
 						}
 						
-						let mut offset = 0;
+						reset_buffer(self.result, hero_hands);
 						
-						for (i,result) in self.result.iter_mut().enumerate() {
-							for j in 0..n_actions {
-								*result += current_strategy[offset+j] * results[j][i];
+						for (action_strategy, results_j) in current_strategy.chunks_exact(hero_hands).zip(results.iter()) {
+							for ((result, action_freq), result_j) in self.result.iter_mut().zip(action_strategy.iter()).zip(results_j.iter()) {
+								*result += action_freq * result_j;
 							}
-							offset += n_actions;
 						}
 						
-						node_info.update_regret_sum_2(self.result, self.n_iterations);
-				
+						node_info.update_regret_sum_2(self.result, self.n_iterations, &self.settings.algorithm, &pruned);
+						
+						for results_i in results {
+							return_buffer(results_i);
+						}
 					}
                     
                 } else {
-                    let villain_pos = self.oop ^ true;
                     let hero_hands = if self.oop == true {
                         self.node.oop_num_hands
                     } else {
@@ -133,35 +221,55 @@ impl<'a> CfrState<'a> {
                         self.node.oop_num_hands
                     };
 					if n_actions == 1 {
-						recursive_cfr(self.range_manager, self.result, &mut self.node.children[0], self.oop, self.villain_reach_probs, self.board_masks, self.n_iterations);
+						recursive_cfr(self.range_manager, self.result, &mut self.node.children[0], self.oop, self.villain_reach_probs, self.board_masks, self.n_iterations, self.settings);
 					} else {
-						*self.result = vec![0.0; hero_hands];
+						reset_buffer(self.result, hero_hands);
 						let current_strategy = node_info.get_current_strategy();
+						let mut results = get_buffer(hero_hands);
+						let mut new_villain_reach_prob = get_buffer(villain_hands);
 						
-						let results: Vec<_> = self.node.children.par_iter_mut()
-																.enumerate()
-																.map(|(count, val)| {
-																	let mut results = vec![0.0; hero_hands];
-																	let mut offset = 0;
-																	let mut new_villain_reach_prob = vec![0.0; villain_hands];
-																	for (i, reach_prob) in new_villain_reach_prob.iter_mut().enumerate() {
-																		*reach_prob = current_strategy[offset+count] * self.villain_reach_probs[i];
-																		
-																		offset += n_actions;
-																	}
-																	recursive_cfr(self.range_manager, &mut results, val, self.oop, &new_villain_reach_prob, self.board_masks, self.n_iterations);
-																	results
-																})
-																.collect();
-						
-						for (i, result) in self.result.iter_mut().enumerate() {
-							for results_j in results.iter() {
-								*result += results_j[i];
+						let (range_manager, oop, villain_reach_probs, board_masks, n_iterations) = (self.range_manager, self.oop, self.villain_reach_probs, self.board_masks, self.n_iterations);
+						let settings = self.settings;
+						if parallel {
+							let child_results: Vec<_> = self.node.children.par_iter_mut().zip(current_strategy.par_chunks_exact(villain_hands)).map(|(child, action_strategy)| {
+								let mut new_villain_reach_prob = get_buffer(villain_hands);
+								for ((reach_prob, action_freq), villain_reach_prob) in new_villain_reach_prob.iter_mut().zip(action_strategy.iter()).zip(villain_reach_probs.iter()) {
+									*reach_prob = action_freq * villain_reach_prob;
+								}
+								if settings.pruning && new_villain_reach_prob.iter().all(|x| *x < PRUNE_REACH_THRESHOLD) {
+									return_buffer(new_villain_reach_prob);
+									return None;
+								}
+								let mut results = get_buffer(hero_hands);
+								recursive_cfr(range_manager, &mut results, child, oop, &new_villain_reach_prob, board_masks, n_iterations, settings);
+								return_buffer(new_villain_reach_prob);
+								Some(results)
+							}).collect();
+							for child_result in child_results.into_iter().flatten() {
+								for (result, child_result) in self.result.iter_mut().zip(child_result.iter()) {
+									*result += child_result;
+								}
+								return_buffer(child_result);
+							}
+						} else {
+							for (child, action_strategy) in self.node.children.iter_mut().zip(current_strategy.chunks_exact(villain_hands)) {
+								for ((reach_prob, action_freq), villain_reach_prob) in new_villain_reach_prob.iter_mut().zip(action_strategy.iter()).zip(villain_reach_probs.iter()) {
+									*reach_prob = action_freq * villain_reach_prob;
+								}
+								if settings.pruning && new_villain_reach_prob.iter().all(|x| *x < PRUNE_REACH_THRESHOLD) {
+									continue;
+								}
+								recursive_cfr(range_manager, &mut results, child, oop, &new_villain_reach_prob, board_masks, n_iterations, settings);
+								for (result, child_result) in self.result.iter_mut().zip(results.iter()) {
+									*result += child_result;
+								}
 							}
 						}
 						
-						node_info.update_strategy_sum(&current_strategy, self.villain_reach_probs, self.n_iterations);
+						node_info.update_strategy_sum(&current_strategy, self.villain_reach_probs, self.n_iterations, &self.settings.algorithm);
 						
+						return_buffer(results);
+						return_buffer(new_villain_reach_prob);
 					}
                     
                 }
@@ -171,7 +279,7 @@ impl<'a> CfrState<'a> {
     }
 }
 
-pub fn get_payoffs(oop: bool, range_manager: &RangeManager, board_masks: (u64, Option<u64>), node: &Node, villain_reach_probs: &[f64], terminal_type: &TerminalType) -> Vec<f64> {
+pub fn get_payoffs(results_new: &mut Vec<f64>, oop: bool, range_manager: &RangeManager, board_masks: (u64, Option<u64>), node: &Node, villain_reach_probs: &[f64], terminal_type: &TerminalType) {
     let villain_pos = oop ^ true;
     let hero_hands = if oop == true {
         node.oop_num_hands
@@ -186,46 +294,101 @@ pub fn get_payoffs(oop: bool, range_manager: &RangeManager, board_masks: (u64, O
     let hero_range = &range_manager.get_range(oop, board_masks.0, board_masks.1).hands;
     let villain_range = &range_manager.get_range(villain_pos, board_masks.0, board_masks.1).hands;
     
-    let mut results_new = vec![0.0; hero_hands];
+    reset_buffer(results_new, hero_hands);
     
     
     match terminal_type {
         TerminalType::TerminalShowdown => {
             let value = node.pot_size as f64;
-            let mut card_sum_win = vec![0.0; 52];
-            let mut sum_win = 0.0;
-            let mut j = 0;
+            let table = range_manager.get_showdown_table(oop, board_masks.0, board_masks.1);
             
-            for (i,hero_combo) in hero_range.iter().enumerate() {
-                while j < villain_hands && villain_range[j].3 < hero_combo.3  {
-                    let villain_combo = villain_range[j];
-                    sum_win += villain_reach_probs[j];
-                    card_sum_win[villain_combo.0 as usize] += villain_reach_probs[j];
-                    card_sum_win[villain_combo.1 as usize] += villain_reach_probs[j];
-                    j += 1;
+            let mut reach_sum = get_buffer(villain_hands + 1);
+            let mut card_reach_sum = get_buffer(2*villain_hands + 52);
+            for (j, (reach_prob, villain_cards)) in villain_reach_probs.iter().zip(table.villain_cards.iter()).enumerate() {
+                reach_sum[j+1] = reach_sum[j] + reach_prob;
+                for slot in villain_cards.iter().map(|x| *x as usize) {
+                    card_reach_sum[slot] = card_reach_sum[slot-1] + reach_prob;
                 }
-                results_new[i] = (sum_win - card_sum_win[hero_combo.0 as usize] - card_sum_win[hero_combo.1 as usize]) * value;
             }
-            let mut card_sum_lose = vec![0.0; 52];
-            let mut sum_lose = 0.0;
-            let mut j = villain_hands;
             
-            for i in (0..hero_hands).rev() {
-                let hero_combo = hero_range[i];
+            let total = reach_sum[villain_hands];
+            for (result, hand) in results_new.iter_mut().zip(table.hands.iter()) {
+                let win = reach_sum[hand.win_end as usize] - card_reach_sum[hand.win_cards[0] as usize] - card_reach_sum[hand.win_cards[1] as usize];
+                let lose = total - reach_sum[hand.lose_start as usize]
+                            - (card_reach_sum[hand.card_ends[0] as usize] - card_reach_sum[hand.lose_cards[0] as usize])
+                            - (card_reach_sum[hand.card_ends[1] as usize] - card_reach_sum[hand.lose_cards[1] as usize]);
+                *result = (win - lose) * value;
+            }
+            
+            return_buffer(reach_sum);
+            return_buffer(card_reach_sum);
+        },
+        TerminalType::TerminalEquity => {
+            let value = node.pot_size as f64;
+            let leaf_payoffs = range_manager.get_leaf_payoffs(board_masks.0, board_masks.1);
+            let ip_hands = node.ip_num_hands;
+            
+            for (i, result) in results_new.iter_mut().enumerate() {
+                let mut sum = 0.0;
+                if oop == true {
+                    for j in 0..villain_hands {
+                        sum += leaf_payoffs[i*ip_hands + j] * villain_reach_probs[j];
+                    }
+                } else {
+                    for j in 0..villain_hands {
+                        sum -= leaf_payoffs[j*ip_hands + i] * villain_reach_probs[j];
+                    }
+                }
+                *result = sum * value;
+            }
+        },
+        TerminalType::TerminalAllIn => {
+            let (runouts, runouts_num) = range_manager.get_allin_runouts(board_masks.0, board_masks.1);
+            let value = node.pot_size as f64 / runouts_num;
+            
+            for runout in runouts.iter() {
+                let (hero_ranks, villain_ranks) = if oop == true {
+                    (&runout.oop, &runout.ip)
+                } else {
+                    (&runout.ip, &runout.oop)
+                };
+                
+                let mut card_sum_win = [0.0; 52];
+                let mut sum_win = 0.0;
+                let mut j = 0;
+                for &(i, hero_rank) in hero_ranks.iter() {
+                    while j < villain_ranks.len() && villain_ranks[j].1 < hero_rank {
+                        let villain_idx = villain_ranks[j].0 as usize;
+                        let villain_combo = villain_range[villain_idx];
+                        sum_win += villain_reach_probs[villain_idx];
+                        card_sum_win[villain_combo.0 as usize] += villain_reach_probs[villain_idx];
+                        card_sum_win[villain_combo.1 as usize] += villain_reach_probs[villain_idx];
+                        j += 1;
+                    }
+                    let hero_combo = hero_range[i as usize];
+                    results_new[i as usize] += (sum_win - card_sum_win[hero_combo.0 as usize] - card_sum_win[hero_combo.1 as usize]) * value;
+                }
                 
-                while j > 0 && villain_range[j-1].3 > hero_combo.3 {
-                    let villain_combo = villain_range[j-1];
-                    sum_lose += villain_reach_probs[j-1];
-                    card_sum_lose[villain_combo.0 as usize] += villain_reach_probs[j-1];
-                    card_sum_lose[villain_combo.1 as usize] += villain_reach_probs[j-1];
-                    j -= 1;
+                let mut card_sum_lose = [0.0; 52];
+                let mut sum_lose = 0.0;
+                let mut j = villain_ranks.len();
+                for &(i, hero_rank) in hero_ranks.iter().rev() {
+                    while j > 0 && villain_ranks[j-1].1 > hero_rank {
+                        let villain_idx = villain_ranks[j-1].0 as usize;
+                        let villain_combo = villain_range[villain_idx];
+                        sum_lose += villain_reach_probs[villain_idx];
+                        card_sum_lose[villain_combo.0 as usize] += villain_reach_probs[villain_idx];
+                        card_sum_lose[villain_combo.1 as usize] += villain_reach_probs[villain_idx];
+                        j -= 1;
+                    }
+                    let hero_combo = hero_range[i as usize];
+                    results_new[i as usize] -= (sum_lose - card_sum_lose[hero_combo.0 as usize] - card_sum_lose[hero_combo.1 as usize]) * value;
                 }
-                results_new[i] -= (sum_lose - card_sum_lose[hero_combo.0 as usize] - card_sum_lose[hero_combo.1 as usize]) * value;
             }
         },
         TerminalType::TerminalFold(fold_position) => {
             let mut villain_sum = 0.0;
-            let mut villain_card_sum = vec![0.0; 52];
+            let mut villain_card_sum = [0.0; 52];
             
             
             let value = if oop == *fold_position {
@@ -250,6 +413,55 @@ pub fn get_payoffs(oop: bool, range_manager: &RangeManager, board_masks: (u64, O
             }
         },
     }
-    
-    results_new
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hand_range::HandRange;
+    use rand::{Rng, SeedableRng};
+    use rand::rngs::StdRng;
+
+    #[test]
+    fn test_showdown_table() {
+        // wide ranges so every river has blocked pairs and chops
+        let oop_range = HandRange::from_string("22+,A2s+,K9s+,QTs+,ATo+,KJo+".to_string());
+        let ip_range = HandRange::from_string("55+,A5s+,KTs+,JTs,AJo+,KQo".to_string());
+        let mut range_manager = RangeManager::new(oop_range, ip_range, "2c7d9hTs".to_string());
+        range_manager.initialize_ranges();
+        let mut rng = StdRng::seed_from_u64(7);
+
+        let rivers: Vec<_> = range_manager.oop_board_range.keys().filter(|key| key.0.count_ones() == 5).copied().collect();
+        assert_eq!(rivers.len(), 48);
+        let (mut blocked, mut chops) = (0, 0);
+        for board_masks in rivers {
+            for oop in [true, false] {
+                let hero_range = &range_manager.get_range(oop, board_masks.0, board_masks.1).hands;
+                let villain_range = &range_manager.get_range(oop ^ true, board_masks.0, board_masks.1).hands;
+                let mut node = Node::new_root(100, 50, range_manager.get_num_hands(true, board_masks.0, board_masks.1), range_manager.get_num_hands(false, board_masks.0, board_masks.1));
+                node.node_type = NodeType::TerminalNode(TerminalType::TerminalShowdown);
+                let villain_reach_probs: Vec<f64> = villain_range.iter().map(|_| if rng.gen_bool(0.2) { 0.0 } else { rng.gen() }).collect();
+
+                let mut results = vec![];
+                get_payoffs(&mut results, oop, &range_manager, board_masks, &node, &villain_reach_probs, &TerminalType::TerminalShowdown);
+
+                for (hero_combo, result) in hero_range.iter().zip(results.iter()) {
+                    let mut expected = 0.0;
+                    for (villain_combo, reach_prob) in villain_range.iter().zip(villain_reach_probs.iter()) {
+                        let cards = [villain_combo.0, villain_combo.1];
+                        if cards.contains(&hero_combo.0) || cards.contains(&hero_combo.1) {
+                            blocked += 1;
+                        } else if villain_combo.3 == hero_combo.3 {
+                            chops += 1;
+                        } else if villain_combo.3 < hero_combo.3 {
+                            expected += reach_prob * node.pot_size as f64;
+                        } else {
+                            expected -= reach_prob * node.pot_size as f64;
+                        }
+                    }
+                    assert!((result - expected).abs() < 1e-9, "{} vs {} on {:?}", result, expected, board_masks);
+                }
+            }
+        }
+        assert!(blocked > 0 && chops > 0);
+    }
+}
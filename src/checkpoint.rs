@@ -0,0 +1,248 @@
+use crate::postfloptree::*;
+use crate::trainer::*;
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use rust_poker::hand_range::get_card_mask;
+
+pub(crate) const CHECKPOINT_MAGIC: &[u8; 4] = b"OSCP";
+const CHECKPOINT_VERSION: u32 = 3;
+
+#[derive(Debug, Clone, Copy)]
+pub enum CheckpointFrequency {
+    Iterations(u64),
+    Seconds(u64),
+}
+
+#[derive(Debug, Clone)]
+pub struct CheckpointConfig {
+    pub path: String,
+    pub frequency: CheckpointFrequency,
+}
+
+pub(crate) fn collect_action_nodes<'a>(node: &'a Node, nodes: &mut Vec<&'a ActionNodeInfo>) {
+    if let NodeType::ActionNode(node_info) = &node.node_type {
+        nodes.push(node_info);
+    }
+    for child in &node.children {
+        collect_action_nodes(child, nodes);
+    }
+}
+
+fn collect_action_nodes_mut<'a>(node: &'a mut Node, nodes: &mut Vec<&'a mut ActionNodeInfo>) {
+    if let NodeType::ActionNode(node_info) = &mut node.node_type {
+        nodes.push(node_info);
+    }
+    for child in node.children.iter_mut() {
+        collect_action_nodes_mut(child, nodes);
+    }
+}
+
+pub fn write_u64(writer: &mut impl Write, value: u64) -> io::Result<()> {
+    writer.write_all(&value.to_le_bytes())
+}
+
+pub fn read_u64(reader: &mut impl Read) -> io::Result<u64> {
+    let mut buf = [0u8; 8];
+    reader.read_exact(&mut buf)?;
+    Ok(u64::from_le_bytes(buf))
+}
+
+pub fn write_f64_slice(writer: &mut impl Write, values: &[f64]) -> io::Result<()> {
+    for value in values {
+        writer.write_all(&value.to_le_bytes())?;
+    }
+    Ok(())
+}
+
+pub fn read_f64_vec(reader: &mut impl Read, len: usize) -> io::Result<Vec<f64>> {
+    let mut values = vec![0.0; len];
+    let mut buf = [0u8; 8];
+    for value in values.iter_mut() {
+        reader.read_exact(&mut buf)?;
+        *value = f64::from_le_bytes(buf);
+    }
+    Ok(values)
+}
+
+pub fn invalid_data(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.to_string())
+}
+
+// FNV-1a, unlike the std hasher it's the same across runs and versions
+struct Fingerprint(u64);
+
+impl Fingerprint {
+    fn new() -> Fingerprint {
+        Fingerprint(0xcbf29ce484222325)
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= *byte as u64;
+            self.0 = self.0.wrapping_mul(0x100000001b3);
+        }
+    }
+
+    fn write_str(&mut self, value: &str) {
+        self.write(value.as_bytes());
+        // separates consecutive strings
+        self.write(&[0xff]);
+    }
+}
+
+// pots, stacks and actions with their sizings of every node, so covers the lines, pot, stack and depth limit
+fn fingerprint_tree(node: &Node, fingerprint: &mut Fingerprint) {
+    fingerprint.write(&node.pot_size.to_le_bytes());
+    fingerprint.write(&node.chance_start_stack.to_le_bytes());
+    match &node.node_type {
+        NodeType::ActionNode(node_info) => {
+            fingerprint.write_str(if node_info.oop { "OOP" } else { "IP" });
+            for action in &node_info.actions {
+                fingerprint.write_str(&action.to_string());
+            }
+        },
+        NodeType::TerminalNode(terminal_type) => fingerprint.write_str(&format!("{:?}", terminal_type)),
+        NodeType::ChanceNode(deck_left) => fingerprint.write(&[*deck_left]),
+        NodeType::ChanceNodeCard((new, old)) => {
+            fingerprint.write(&new.to_le_bytes());
+            fingerprint.write(&old.unwrap_or(0).to_le_bytes());
+        },
+    };
+    fingerprint.write(&(node.children.len() as u64).to_le_bytes());
+    for child in &node.children {
+        fingerprint_tree(child, fingerprint);
+    }
+}
+
+// identifies the spot a checkpoint belongs to: board, both ranges with their weights, the tree and the algorithm
+fn spot_fingerprint(trainer: &Trainer) -> u64 {
+    let mut fingerprint = Fingerprint::new();
+    let range_manager = &trainer.range_manager;
+    fingerprint.write_str(&range_manager.initial_board);
+    let board_mask = get_card_mask(&range_manager.initial_board);
+    for oop in [true, false] {
+        for combo in &range_manager.get_range(oop, board_mask, None).hands {
+            fingerprint.write(&[combo.0, combo.1, combo.2]);
+        }
+        fingerprint.write_str("");
+    }
+    fingerprint.write_str(&format!("{:?}", range_manager.depth_limit));
+    fingerprint.write_str(&format!("{:?}", trainer.algorithm));
+    fingerprint_tree(&trainer.root, &mut fingerprint);
+    fingerprint.0
+}
+
+// Stores the DCFR iteration counter and regret/strategy sums and locked strategies of every action node in depth
+// first order, with a fingerprint of the spot so a checkpoint can't be loaded into a different one.
+// The file is written next to the target and renamed, so a crash mid-write keeps the previous checkpoint.
+pub fn save_checkpoint(trainer: &Trainer, path: &str) -> io::Result<()> {
+    let mut nodes = vec![];
+    collect_action_nodes(&trainer.root, &mut nodes);
+
+    let tmp_path = format!("{}.tmp", path);
+    {
+        let mut writer = BufWriter::new(File::create(&tmp_path)?);
+        writer.write_all(CHECKPOINT_MAGIC)?;
+        writer.write_all(&CHECKPOINT_VERSION.to_le_bytes())?;
+        write_u64(&mut writer, spot_fingerprint(trainer))?;
+        write_u64(&mut writer, trainer.iteration)?;
+        write_u64(&mut writer, nodes.len() as u64)?;
+
+        for node_info in nodes {
+            write_u64(&mut writer, node_info.get_regret_sum().len() as u64)?;
+            write_f64_slice(&mut writer, node_info.get_regret_sum())?;
+            write_f64_slice(&mut writer, &node_info.strategy_sum)?;
+            match &node_info.locked_strategy {
+                Some(locked_strategy) => {
+                    write_u64(&mut writer, 1)?;
+                    write_f64_slice(&mut writer, locked_strategy)?;
+                },
+                None => write_u64(&mut writer, 0)?,
+            };
+        }
+        writer.flush()?;
+    }
+    fs::rename(&tmp_path, path)
+}
+
+// Loads a checkpoint into a tree built with the same configuration and solved with the same algorithm,
+// returns the iteration to resume from
+pub fn load_checkpoint(trainer: &mut Trainer, path: &str) -> io::Result<u64> {
+    let mut reader = BufReader::new(File::open(path)?);
+
+    let mut magic = [0u8; 4];
+    reader.read_exact(&mut magic)?;
+    let mut version = [0u8; 4];
+    reader.read_exact(&mut version)?;
+    if &magic != CHECKPOINT_MAGIC || u32::from_le_bytes(version) != CHECKPOINT_VERSION {
+        return Err(invalid_data("not a checkpoint file"));
+    }
+    if read_u64(&mut reader)? != spot_fingerprint(trainer) {
+        return Err(invalid_data("checkpoint is of a different board, ranges, lines, pot, stack or algorithm"));
+    }
+
+    let iteration = read_u64(&mut reader)?;
+    let nodes_num = read_u64(&mut reader)? as usize;
+
+    let mut nodes = vec![];
+    collect_action_nodes_mut(&mut trainer.root, &mut nodes);
+    if nodes.len() != nodes_num {
+        return Err(invalid_data("checkpoint doesn't match the built tree"));
+    }
+
+    // read everything first so a mismatching checkpoint leaves the tree untouched
+    let mut sums = vec![];
+    for node_info in nodes.iter() {
+        let len = read_u64(&mut reader)? as usize;
+        if len != node_info.strategy_sum.len() {
+            return Err(invalid_data("checkpoint doesn't match the built tree"));
+        }
+        let regret_sum = read_f64_vec(&mut reader, len)?;
+        let strategy_sum = read_f64_vec(&mut reader, len)?;
+        let locked_strategy = match read_u64(&mut reader)? {
+            0 => None,
+            _ => Some(read_f64_vec(&mut reader, len)?),
+        };
+        sums.push((regret_sum, strategy_sum, locked_strategy));
+    }
+
+    for (node_info, (regret_sum, strategy_sum, locked_strategy)) in nodes.iter_mut().zip(sums) {
+        node_info.set_sums(regret_sum, strategy_sum);
+        node_info.locked_strategy = locked_strategy;
+    }
+
+    Ok(iteration)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::*;
+
+    #[test]
+    fn test_checkpoint_round_trip() {
+        let path = std::env::temp_dir().join(format!("opensolver_checkpoint_{}.bin", std::process::id()));
+        let path = path.to_str().unwrap();
+
+        let mut trainer = test_config("2c7d9hTsJs").build().unwrap();
+        trainer.solve(&Accuracy::Chips(0.0), TrainFinish::Iterations(10), None, &mut |_| ());
+        let locked_line = "r:0:c".to_string();
+        if let NodeType::ActionNode(node_info) = &mut trainer.root.find_node_mut(&locked_line, &trainer.range_manager).node_type {
+            node_info.locked_strategy = Some(node_info.get_average_strategy());
+        }
+        save_checkpoint(&trainer, path).unwrap();
+        trainer.solve(&Accuracy::Chips(0.0), TrainFinish::Iterations(10), None, &mut |_| ());
+
+        let mut resumed = test_config("2c7d9hTsJs").build().unwrap();
+        resumed.iteration = load_checkpoint(&mut resumed, path).unwrap();
+        assert_eq!(resumed.iteration, 10);
+        assert!(matches!(&resumed.root.find_node_mut(&locked_line, &resumed.range_manager).node_type, NodeType::ActionNode(node_info) if node_info.locked_strategy.is_some()));
+        resumed.solve(&Accuracy::Chips(0.0), TrainFinish::Iterations(10), None, &mut |_| ());
+        assert_eq!(get_strategy(&resumed, "r:0").unwrap().frequencies, get_strategy(&trainer, "r:0").unwrap().frequencies);
+
+        // same tree shape on another board
+        let mut other = test_config("2c7d9hTsQs").build().unwrap();
+        assert!(load_checkpoint(&mut other, path).is_err());
+        let _ = fs::remove_file(path);
+    }
+}
@@ -0,0 +1,245 @@
+use crate::export::*;
+use crate::api::{GameConfig, bet_size, nearest_bet};
+use crate::checkpoint::*;
+use crate::warmstart::*;
+use crate::postfloptree::CfrAlgorithm;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File};
+use std::io::Read;
+
+/// What the compared nodes are sorted by, largest first
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CompareBy {
+    Frequency,
+    Range,
+    Ev,
+}
+
+/// A node of the first solution and the node it was matched to in the second one. Frequencies are of the
+/// whole range, the actions of the second solution are mapped to the nearest action of the first
+#[derive(Debug, Clone, Serialize)]
+pub struct NodeComparison {
+    pub line: String,
+    pub other_line: String,
+    pub player: String,
+    pub actions: Vec<String>,
+    pub frequencies: Vec<f64>,
+    pub other_frequencies: Vec<f64>,
+    /// largest difference of an action's frequency
+    pub frequency_difference: f64,
+    /// share of the reach that would have to move between hands to turn one range into the other
+    pub range_difference: f64,
+    /// reach weighted EV of the player to act
+    pub ev: f64,
+    pub other_ev: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SolutionComparison {
+    pub only_first: usize,
+    pub only_second: usize,
+    pub nodes: Vec<NodeComparison>,
+}
+
+/// Reads a strategy exported to JSON with export_strategy
+pub fn load_strategy(path: &str) -> Result<Vec<ExportedNode>, String> {
+    let data = fs::read_to_string(path).map_err(|e| format!("couldn't read {}: {}", path, e))?;
+    serde_json::from_str(&data).map_err(|e| format!("{} is not a JSON strategy export: {}", path, e))
+}
+
+/// Reads a JSON export, a checkpoint or a solution saved with save_solution. The binary files only hold the solver
+/// state, they are loaded onto a tree built from config with the algorithm and exported from the root
+pub fn load_solution(path: &str, config: Option<&GameConfig>, algorithm: CfrAlgorithm) -> Result<Vec<ExportedNode>, String> {
+    let mut magic = [0u8; 4];
+    if File::open(path).and_then(|mut file| file.read_exact(&mut magic)).is_err() || (&magic != CHECKPOINT_MAGIC && &magic != SOLUTION_MAGIC) {
+        return load_strategy(path);
+    }
+    let config = config.ok_or_else(|| format!("{} is a saved solution, set the tree it was solved with first", path))?;
+    let mut trainer = config.build()?;
+    trainer.algorithm = algorithm;
+    if &magic == CHECKPOINT_MAGIC {
+        trainer.iteration = load_checkpoint(&mut trainer, path).map_err(|e| format!("couldn't load {}: {}", path, e))?;
+    } else {
+        let snapshot = load_snapshot(path).map_err(|e| format!("couldn't load {}: {}", path, e))?;
+        let matched = warm_start(&mut trainer, &snapshot);
+        let mut nodes = vec![];
+        collect_action_nodes(&trainer.root, &mut nodes);
+        if matched != nodes.len() {
+            return Err(format!("{} doesn't match the tree, {} of {} nodes found", path, matched, nodes.len()));
+        }
+    }
+    get_subtree_strategy(&trainer, "r:0", None)
+}
+
+// the same action or the bet with the nearest size
+fn nearest_action(action: &str, actions: &[String]) -> Option<usize> {
+    if let Some(i) = actions.iter().position(|x| x == action) {
+        return Some(i);
+    }
+    nearest_bet(bet_size(action)?, actions)
+}
+
+// line of the second solution the line maps to, bets take the nearest size at every node on the way
+fn map_line(line: &str, nodes: &HashMap<&str, &ExportedNode>) -> Option<String> {
+    let mut tokens = line.split(':');
+    let mut mapped = format!("{}:{}", tokens.next()?, tokens.next()?);
+    for token in tokens {
+        let token = match (bet_size(token), nodes.get(mapped.as_str())) {
+            (Some(_), Some(node)) => node.actions[nearest_action(token, &node.actions)?].clone(),
+            _ => token.to_string(),
+        };
+        mapped = format!("{}:{}", mapped, token);
+    }
+    if nodes.contains_key(mapped.as_str()) {
+        Some(mapped)
+    } else {
+        None
+    }
+}
+
+fn range_frequencies(node: &ExportedNode) -> Vec<f64> {
+    let total_reach: f64 = node.reach.iter().sum();
+    node.strategy.iter().map(|frequencies| {
+        if total_reach > 0.0 {
+            frequencies.iter().zip(node.reach.iter()).map(|(f, r)| f * r).sum::<f64>() / total_reach
+        } else {
+            0.0
+        }
+    }).collect()
+}
+
+fn range_ev(node: &ExportedNode) -> f64 {
+    let total_reach: f64 = node.reach.iter().sum();
+    if total_reach > 0.0 {
+        node.ev.iter().zip(node.reach.iter()).map(|(ev, r)| ev * r).sum::<f64>() / total_reach
+    } else {
+        0.0
+    }
+}
+
+// total variation distance of the normalized reaches
+fn range_difference(first: &ExportedNode, second: &ExportedNode) -> f64 {
+    let normalized = |node: &ExportedNode| -> HashMap<String, f64> {
+        let total_reach: f64 = node.reach.iter().sum();
+        node.hands.iter().cloned().zip(node.reach.iter().map(|r| if total_reach > 0.0 { r / total_reach } else { 0.0 })).collect()
+    };
+    let (first, second) = (normalized(first), normalized(second));
+    let hands: HashSet<&String> = first.keys().chain(second.keys()).collect();
+    hands.iter().map(|hand| (first.get(*hand).unwrap_or(&0.0) - second.get(*hand).unwrap_or(&0.0)).abs()).sum::<f64>() / 2.0
+}
+
+fn compare_node(first: &ExportedNode, second: &ExportedNode) -> NodeComparison {
+    let frequencies = range_frequencies(first);
+    let mut other_frequencies = vec![0.0; first.actions.len()];
+    for (action, frequency) in second.actions.iter().zip(range_frequencies(second).iter()) {
+        // a fold or check missing from the first node has nothing to map to
+        if let Some(j) = nearest_action(action, &first.actions) {
+            other_frequencies[j] += frequency;
+        }
+    }
+    let frequency_difference = frequencies.iter().zip(other_frequencies.iter()).map(|(a, b)| (a - b).abs()).fold(0.0, f64::max);
+    NodeComparison {
+        line: first.line.clone(),
+        other_line: second.line.clone(),
+        player: first.player.clone(),
+        actions: first.actions.clone(),
+        frequencies,
+        other_frequencies,
+        frequency_difference,
+        range_difference: range_difference(first, second),
+        ev: range_ev(first),
+        other_ev: range_ev(second),
+    }
+}
+
+/// Matches the decision nodes of two solutions of the same spot by line and compares them, bet sizes missing
+/// from the second solution's tree are mapped to the nearest size. Nodes are sorted by the largest difference
+pub fn compare_solutions(first: &[ExportedNode], second: &[ExportedNode], compare_by: CompareBy) -> SolutionComparison {
+    let second_nodes: HashMap<&str, &ExportedNode> = second.iter().map(|node| (node.line.as_str(), node)).collect();
+    let mut matched = HashSet::new();
+    let mut nodes = vec![];
+    for node in first {
+        if let Some(other_line) = map_line(&node.line, &second_nodes) {
+            nodes.push(compare_node(node, second_nodes[other_line.as_str()]));
+            matched.insert(other_line);
+        }
+    }
+
+    let difference = |x: &NodeComparison| match compare_by {
+        CompareBy::Frequency => x.frequency_difference,
+        CompareBy::Range => x.range_difference,
+        CompareBy::Ev => (x.ev - x.other_ev).abs(),
+    };
+    nodes.sort_by(|a, b| difference(b).partial_cmp(&difference(a)).unwrap());
+    SolutionComparison { only_first: first.len() - nodes.len(), only_second: second_nodes.len() - matched.len(), nodes }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::test_config;
+    use crate::trainer::*;
+
+    fn node(line: &str, actions: &[&str], strategy: Vec<Vec<f64>>) -> ExportedNode {
+        ExportedNode {
+            line: line.to_string(),
+            board: "2c7d9h".to_string(),
+            player: "OOP".to_string(),
+            actions: actions.iter().map(|x| x.to_string()).collect(),
+            hands: vec!["AsAh".to_string(), "KsKh".to_string()],
+            reach: vec![1.0, 1.0],
+            ev: vec![40.0, 20.0],
+            strategy,
+        }
+    }
+
+    #[test]
+    fn test_compare_solutions() {
+        let first = vec![
+            node("r:0", &["c", "b25", "b50"], vec![vec![1.0, 0.0], vec![0.0, 1.0], vec![0.0, 0.0]]),
+            node("r:0:b25", &["f", "c"], vec![vec![0.5, 0.5], vec![0.5, 0.5]]),
+        ];
+        let second = vec![
+            node("r:0", &["c", "b33"], vec![vec![1.0, 1.0], vec![0.0, 0.0]]),
+            node("r:0:b33", &["f", "c"], vec![vec![0.5, 0.5], vec![0.5, 0.5]]),
+        ];
+        let comparison = compare_solutions(&first, &second, CompareBy::Frequency);
+        assert_eq!(comparison.nodes.len(), 2);
+        assert_eq!(comparison.only_first, 0);
+        assert_eq!(comparison.only_second, 0);
+        assert_eq!(comparison.nodes[0].line, "r:0");
+        assert_eq!(comparison.nodes[0].other_frequencies, vec![1.0, 0.0, 0.0]);
+        assert_eq!(comparison.nodes[0].frequency_difference, 0.5);
+        assert_eq!(comparison.nodes[1].other_line, "r:0:b33");
+        assert_eq!(comparison.nodes[1].frequency_difference, 0.0);
+    }
+
+    #[test]
+    fn test_load_solution() {
+        let path = std::env::temp_dir().join(format!("opensolver_compare_{}", std::process::id()));
+        let path = path.to_str().unwrap();
+        let mut config = test_config("2c7d9hTsJs");
+        let mut trainer = config.build().unwrap();
+        trainer.solve(&Accuracy::Chips(0.0), TrainFinish::Iterations(10), None, &mut |_| ());
+        let exported = get_subtree_strategy(&trainer, "r:0", None).unwrap();
+
+        let checkpoint = format!("{}.bin", path);
+        save_checkpoint(&trainer, &checkpoint).unwrap();
+        let solution = format!("{}.sol", path);
+        save_snapshot(&take_snapshot(&trainer), &solution).unwrap();
+        for file in [&checkpoint, &solution] {
+            let loaded = load_solution(file, Some(&config), trainer.algorithm).unwrap();
+            let comparison = compare_solutions(&exported, &loaded, CompareBy::Ev);
+            assert_eq!(comparison.nodes.len(), exported.len());
+            assert!(comparison.nodes.iter().all(|node| node.frequency_difference < 1e-12 && (node.ev - node.other_ev).abs() < 1e-9));
+            assert!(load_solution(file, None, trainer.algorithm).is_err());
+        }
+
+        // a saved solution of another tree doesn't cover this one
+        config.add_line(vec![50, 50]);
+        assert!(load_solution(&solution, Some(&config), trainer.algorithm).is_err());
+        let _ = fs::remove_file(&checkpoint);
+        let _ = fs::remove_file(&solution);
+    }
+}
@@ -0,0 +1,322 @@
+use crate::hand_range::*;
+use rust_poker::hand_evaluator::{Hand, evaluate};
+use rust_poker::hand_range::{get_card_mask};
+use rayon::prelude::*;
+
+/// Equity of a single combo against the opponent's whole range
+/// 0: combo
+/// 1: equity (ties count as half a win)
+/// 2: matchups, i.e. weighted amount of unblocked villain combos summed over all runouts
+#[derive(Debug, Clone, Copy)]
+pub struct ComboEquity(pub Combo, pub f64, pub f64);
+
+#[derive(Debug)]
+pub struct RangeEquity {
+    pub oop_equity: f64,
+    pub ip_equity: f64,
+    pub oop_combos: Vec<ComboEquity>,
+    pub ip_combos: Vec<ComboEquity>,
+}
+
+impl RangeEquity {
+    // equity distribution curve: (fraction of range, equity) sorted by ascending equity,
+    // where every combo is weighted by its range weight and its matchups
+    pub fn get_distribution(&self, oop: bool) -> Vec<(f64, f64)> {
+        let combos = match oop {
+            true => &self.oop_combos,
+            false => &self.ip_combos,
+        };
+
+        let mut weighted: Vec<(f64, f64)> = combos.iter()
+                                                  .filter(|x| x.2 > 0.0)
+                                                  .map(|x| (x.1, x.0.2 as f64 / 100.0 * x.2))
+                                                  .collect();
+        weighted.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+
+        let total: f64 = weighted.iter().map(|x| x.1).sum();
+        let mut cumulative = 0.0;
+        let mut distribution = vec![];
+        for (equity, weight) in weighted {
+            cumulative += weight;
+            distribution.push((cumulative / total, equity));
+        }
+
+        distribution
+    }
+}
+
+fn conflicts(combo: &Combo, board_mask: u64) -> bool {
+    (((1u64 << combo.0) | (1u64 << combo.1)) & board_mask) != 0
+}
+
+fn get_runouts(board_mask: u64, cards_to_come: usize) -> Vec<u64> {
+    let deck: Vec<u8> = (0..52).filter(|x| (1u64 << x) & board_mask == 0).collect();
+
+    match cards_to_come {
+        0 => vec![board_mask],
+        1 => deck.iter().map(|x| board_mask | (1u64 << x)).collect(),
+        2 => {
+            let mut runouts = vec![];
+            for (i, turn) in deck.iter().enumerate() {
+                for river in deck[i+1..].iter() {
+                    runouts.push(board_mask | (1u64 << turn) | (1u64 << river));
+                }
+            }
+            runouts
+        },
+        _ => panic!("Board must be either flop, turn or river"),
+    }
+}
+
+// returns (wins + ties/2, matchups) for every hero combo on a complete 5 card board
+fn showdown_shares(hero_range: &[Combo], villain_range: &[Combo], board_mask: u64) -> Vec<(f64, f64)> {
+    let board = Hand::from_bit_mask(board_mask);
+    let rank = |c: &Combo| evaluate(&(board + Hand::from_hole_cards(c.0, c.1)));
+
+    let mut hero: Vec<(usize, u16)> = hero_range.iter()
+                                                .enumerate()
+                                                .filter(|(_, c)| !conflicts(c, board_mask))
+                                                .map(|(i, c)| (i, rank(c)))
+                                                .collect();
+    let mut villain: Vec<(Combo, f64, u16)> = villain_range.iter()
+                                                           .filter(|c| !conflicts(c, board_mask) && c.2 > 0)
+                                                           .map(|c| (*c, c.2 as f64 / 100.0, rank(c)))
+                                                           .collect();
+    hero.sort_by_key(|x| x.1);
+    villain.sort_by_key(|x| x.2);
+
+    let mut card_sum = vec![0.0; 52];
+    let mut same_combo = vec![0.0; 52 * 52];
+    let mut sum = 0.0;
+    for (combo, weight, _) in villain.iter() {
+        card_sum[combo.0 as usize] += weight;
+        card_sum[combo.1 as usize] += weight;
+        same_combo[combo.0.max(combo.1) as usize * 52 + combo.0.min(combo.1) as usize] += weight;
+        sum += weight;
+    }
+
+    let mut win = vec![0.0; hero_range.len()];
+    let mut card_sum_win = vec![0.0; 52];
+    let mut sum_win = 0.0;
+    let mut j = 0;
+    for &(i, hero_rank) in hero.iter() {
+        let hero_combo = hero_range[i];
+        while j < villain.len() && villain[j].2 < hero_rank {
+            let (villain_combo, weight, _) = villain[j];
+            sum_win += weight;
+            card_sum_win[villain_combo.0 as usize] += weight;
+            card_sum_win[villain_combo.1 as usize] += weight;
+            j += 1;
+        }
+        win[i] = sum_win - card_sum_win[hero_combo.0 as usize] - card_sum_win[hero_combo.1 as usize];
+    }
+
+    let mut shares = vec![(0.0, 0.0); hero_range.len()];
+    let mut card_sum_lose = vec![0.0; 52];
+    let mut sum_lose = 0.0;
+    let mut j = villain.len();
+    for &(i, hero_rank) in hero.iter().rev() {
+        let hero_combo = hero_range[i];
+        while j > 0 && villain[j-1].2 > hero_rank {
+            let (villain_combo, weight, _) = villain[j-1];
+            sum_lose += weight;
+            card_sum_lose[villain_combo.0 as usize] += weight;
+            card_sum_lose[villain_combo.1 as usize] += weight;
+            j -= 1;
+        }
+        let lose = sum_lose - card_sum_lose[hero_combo.0 as usize] - card_sum_lose[hero_combo.1 as usize];
+        let total = sum - card_sum[hero_combo.0 as usize] - card_sum[hero_combo.1 as usize]
+                    + same_combo[hero_combo.0.max(hero_combo.1) as usize * 52 + hero_combo.0.min(hero_combo.1) as usize];
+        let tie = total - win[i] - lose;
+        shares[i] = (win[i] + tie / 2.0, total);
+    }
+
+    shares
+}
+
+fn add_shares(total: &mut [(f64, f64)], shares: &[(f64, f64)]) {
+    for (acc, share) in total.iter_mut().zip(shares.iter()) {
+        acc.0 += share.0;
+        acc.1 += share.1;
+    }
+}
+
+fn to_combo_equities(range: &[Combo], shares: &[(f64, f64)]) -> (f64, Vec<ComboEquity>) {
+    let mut range_wins = 0.0;
+    let mut range_matchups = 0.0;
+    let mut combos = vec![];
+
+    for (combo, share) in range.iter().zip(shares.iter()) {
+        let weight = combo.2 as f64 / 100.0;
+        range_wins += weight * share.0;
+        range_matchups += weight * share.1;
+        let equity = if share.1 > 0.0 {
+            share.0 / share.1
+        } else {
+            0.0
+        };
+        combos.push(ComboEquity(*combo, equity, share.1));
+    }
+
+    let range_equity = if range_matchups > 0.0 {
+        range_wins / range_matchups
+    } else {
+        0.0
+    };
+
+    (range_equity, combos)
+}
+
+// exact range vs range equity on a flop, turn or river by enumerating all remaining cards
+pub fn calc_range_equity(oop_range: &HandRange, ip_range: &HandRange, board: &str) -> RangeEquity {
+    let board_mask = get_card_mask(board);
+    let cards_to_come = 5 - board.len() / 2;
+
+    let mut oop_range = oop_range.clone();
+    let mut ip_range = ip_range.clone();
+    oop_range.remove_conflicting_combos(board_mask);
+    ip_range.remove_conflicting_combos(board_mask);
+    let oop_hands = &oop_range.hands;
+    let ip_hands = &ip_range.hands;
+
+    let runouts = get_runouts(board_mask, cards_to_come);
+    let (oop_shares, ip_shares) = runouts.par_iter()
+                                         .fold(|| (vec![(0.0, 0.0); oop_hands.len()], vec![(0.0, 0.0); ip_hands.len()]),
+                                               |mut acc, runout| {
+                                                   add_shares(&mut acc.0, &showdown_shares(oop_hands, ip_hands, *runout));
+                                                   add_shares(&mut acc.1, &showdown_shares(ip_hands, oop_hands, *runout));
+                                                   acc
+                                               })
+                                         .reduce(|| (vec![(0.0, 0.0); oop_hands.len()], vec![(0.0, 0.0); ip_hands.len()]),
+                                                 |mut a, b| {
+                                                     add_shares(&mut a.0, &b.0);
+                                                     add_shares(&mut a.1, &b.1);
+                                                     a
+                                                 });
+
+    let (oop_equity, oop_combos) = to_combo_equities(oop_hands, &oop_shares);
+    let (ip_equity, ip_combos) = to_combo_equities(ip_hands, &ip_shares);
+
+    RangeEquity { oop_equity, ip_equity, oop_combos, ip_combos }
+}
+
+// OOP and IP hand ranks on one runout in range order, None for combos the runout blocks
+type ComboRanks = (Vec<Option<u16>>, Vec<Option<u16>>);
+
+// Pairwise equity of every OOP combo against every IP combo, OOP major. Runouts are enumerated
+// per pair so card removal is exact, blocked pairs get None
+pub fn calc_equity_matrix(oop_hands: &[Combo], ip_hands: &[Combo], board_mask: u64) -> Vec<Option<f64>> {
+    let cards_to_come = 5 - board_mask.count_ones() as usize;
+    let runouts = get_runouts(board_mask, cards_to_come);
+    let rank = |c: &Combo, runout: u64| {
+        if conflicts(c, runout) {
+            None
+        } else {
+            Some(evaluate(&(Hand::from_bit_mask(runout) + Hand::from_hole_cards(c.0, c.1))))
+        }
+    };
+    let ranks: Vec<ComboRanks> = runouts.par_iter()
+                                                              .map(|runout| (oop_hands.iter().map(|c| rank(c, *runout)).collect(),
+                                                                             ip_hands.iter().map(|c| rank(c, *runout)).collect()))
+                                                              .collect();
+
+    oop_hands.par_iter().enumerate().flat_map_iter(|(i, oop_combo)| {
+        let mut shares = vec![(0u32, 0u32); ip_hands.len()];
+        for (oop_ranks, ip_ranks) in ranks.iter() {
+            let oop_rank = match oop_ranks[i] {
+                Some(x) => x,
+                None => continue,
+            };
+            for (j, ip_rank) in ip_ranks.iter().enumerate() {
+                if let Some(ip_rank) = ip_rank {
+                    // doubled so ties stay integral
+                    shares[j].0 += match oop_rank.cmp(ip_rank) {
+                        std::cmp::Ordering::Greater => 2,
+                        std::cmp::Ordering::Equal => 1,
+                        std::cmp::Ordering::Less => 0,
+                    };
+                    shares[j].1 += 2;
+                }
+            }
+        }
+        let oop_mask = (1u64 << oop_combo.0) | (1u64 << oop_combo.1);
+        shares.into_iter().zip(ip_hands.iter()).map(move |(share, ip_combo)| {
+            if conflicts(ip_combo, oop_mask) || share.1 == 0 {
+                None
+            } else {
+                Some(share.0 as f64 / share.1 as f64)
+            }
+        }).collect::<Vec<_>>()
+    }).collect()
+}
+
+// Combos of both players that don't conflict with a runout, as (index in range, rank) sorted by rank
+#[derive(Debug, Clone)]
+pub struct RunoutRanks {
+    pub oop: Vec<(u16, u16)>,
+    pub ip: Vec<(u16, u16)>,
+}
+
+// sorted hand ranks of every runout of an all in board, and how many runouts a pair of unblocked combos can see
+pub fn calc_runout_ranks(oop_hands: &[Combo], ip_hands: &[Combo], board_mask: u64) -> (Vec<RunoutRanks>, f64) {
+    let cards_to_come = 5 - board_mask.count_ones() as usize;
+    let runouts = get_runouts(board_mask, cards_to_come);
+    let sorted_ranks = |hands: &[Combo], runout: u64| {
+        let board = Hand::from_bit_mask(runout);
+        let mut ranks: Vec<(u16, u16)> = hands.iter()
+                                              .enumerate()
+                                              .filter(|(_, c)| !conflicts(c, runout))
+                                              .map(|(i, c)| (i as u16, evaluate(&(board + Hand::from_hole_cards(c.0, c.1)))))
+                                              .collect();
+        ranks.sort_by_key(|x| x.1);
+        ranks
+    };
+    let runout_ranks = runouts.par_iter()
+                              .map(|runout| RunoutRanks { oop: sorted_ranks(oop_hands, *runout), ip: sorted_ranks(ip_hands, *runout) })
+                              .collect();
+
+    let deck_left = 52 - board_mask.count_ones() as usize - 4;
+    let runouts_num = match cards_to_come {
+        0 => 1,
+        1 => deck_left,
+        _ => deck_left * (deck_left - 1) / 2,
+    };
+
+    (runout_ranks, runouts_num as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_range_equity_river() {
+        // QQ always beats JJ on a dry river
+        let equity = calc_range_equity(&HandRange::from_string("QQ".to_string()), &HandRange::from_string("JJ".to_string()), "As9d7c4h2s");
+        assert!((equity.oop_equity - 1.0).abs() < 1e-9);
+        assert!(equity.ip_equity.abs() < 1e-9);
+        // identical hands chop
+        let equity = calc_range_equity(&HandRange::from_string("QcQd".to_string()), &HandRange::from_string("QhQs".to_string()), "As9d7c4h2s");
+        assert!((equity.oop_equity - 0.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_range_equity_flop() {
+        // equities of both players sum up to one
+        let equity = calc_range_equity(&HandRange::from_string("AK,QQ".to_string()), &HandRange::from_string("JJ+".to_string()), "Ks7d2c");
+        assert!((equity.oop_equity + equity.ip_equity - 1.0).abs() < 1e-9);
+        let distribution = equity.get_distribution(true);
+        assert!((distribution.last().unwrap().0 - 1.0).abs() < 1e-9);
+        assert!(distribution.windows(2).all(|x| x[0].1 <= x[1].1));
+    }
+
+    #[test]
+    fn test_equity_matrix() {
+        let oop_range = HandRange::from_string("QcQd".to_string());
+        let ip_range = HandRange::from_string("QcQh,JcJd".to_string());
+        let matrix = calc_equity_matrix(&oop_range.hands, &ip_range.hands, get_card_mask("As9d7c4h2s"));
+        assert_eq!(matrix.len(), 2);
+        assert!(matrix.contains(&None));
+        assert!(matrix.contains(&Some(1.0)));
+    }
+}
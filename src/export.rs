@@ -0,0 +1,204 @@
+use crate::api::*;
+use crate::trainer::*;
+use crate::postfloptree::*;
+use crate::best_response::get_counterfactual_values;
+use crate::resolve::hand_order_index;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+/// Decision node of an exported subtree, per hand values are of the hands of the player to act reaching the node
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportedNode {
+    pub line: String,
+    pub board: String,
+    pub player: String,
+    pub actions: Vec<String>,
+    pub hands: Vec<String>,
+    pub reach: Vec<f64>,
+    pub ev: Vec<f64>,
+    /// per action the frequency of every hand
+    pub strategy: Vec<Vec<f64>>,
+}
+
+/// Every decision node at most depth actions and dealt cards below the line, in depth first order
+pub fn get_subtree_strategy(trainer: &Trainer, line: &str, depth: Option<usize>) -> Result<Vec<ExportedNode>, String> {
+    check_line(trainer, line)?;
+    let (board, node, _, _, _, _) = trainer.root.find_node(&line.to_string(), &trainer.range_manager);
+    let mut walk = ExportWalk { trainer, hand_order: hand_order(), hand_order_mapping: hand_order_mapping(), nodes: vec![] };
+    let reach = get_node_reach(trainer, line, trainer.range_manager.get_board_masks(&board), &walk.hand_order_mapping);
+    walk.export_node(line.to_string(), node, &board, depth, &reach);
+    Ok(walk.nodes)
+}
+
+// state shared by the whole walk, the exported nodes in depth first order
+struct ExportWalk<'a> {
+    trainer: &'a Trainer,
+    hand_order: Vec<String>,
+    hand_order_mapping: HashMap<String, usize>,
+    nodes: Vec<ExportedNode>,
+}
+
+impl<'a> ExportWalk<'a> {
+    // One walk of the subtree: the reach of both players goes down and the counterfactual values of both come back
+    // up, the EVs of a node are its values once the children are done. Reach and values are of OOP and IP in the
+    // order of the range manager's ranges for the board, below the depth the values are those of the average strategy
+    fn export_node(&mut self, line: String, node: &Node, board: &String, depth: Option<usize>, reach: &(Vec<f64>, Vec<f64>)) -> (Vec<f64>, Vec<f64>) {
+        let trainer = self.trainer;
+        let range_manager = &trainer.range_manager;
+        let board_masks = range_manager.get_board_masks(board);
+
+        match (&node.node_type, depth) {
+            (NodeType::TerminalNode(_), _) | (NodeType::ChanceNodeCard(_), Some(0)) | (NodeType::ChanceNode(_), Some(0)) => {
+                (get_counterfactual_values(range_manager, node, true, &reach.1, board_masks, false), get_counterfactual_values(range_manager, node, false, &reach.0, board_masks, false))
+            },
+            (NodeType::ChanceNodeCard(_), _) => {
+                // the root, r:0 is below it
+                let child = trainer.root.get_children(line, range_manager).remove(0);
+                self.export_node(child.line, &node.children[0], &child.board, depth.map(|x| x - 1), reach)
+            },
+            (NodeType::ChanceNode(deck_left), _) => {
+                let mut values = (vec![0.0; node.oop_num_hands], vec![0.0; node.ip_num_hands]);
+                for (child, child_info) in node.children.iter().zip(trainer.root.get_children(line, range_manager)) {
+                    let new_masks = match child.node_type {
+                        NodeType::ChanceNodeCard(masks) => masks,
+                        _ => panic!("all children in chance node should be ChanceNodeCard"),
+                    };
+                    let child_reach = if *deck_left == 0 {
+                        reach.clone()
+                    } else {
+                        (range_manager.get_villain_reach(false, new_masks.0, new_masks.1, &reach.0), range_manager.get_villain_reach(true, new_masks.0, new_masks.1, &reach.1))
+                    };
+                    let child_values = self.export_node(child_info.line, &child.children[0], &child_info.board, depth.map(|x| x - 1), &child_reach);
+                    for (oop, values, child_values) in [(true, &mut values.0, &child_values.0), (false, &mut values.1, &child_values.1)] {
+                        if *deck_left == 0 {
+                            for (value, child_value) in values.iter_mut().zip(child_values.iter()) {
+                                *value += child_value;
+                            }
+                        } else {
+                            for (i, mapping) in range_manager.get_reach_mapping(oop, new_masks.0, new_masks.1).iter().enumerate() {
+                                values[*mapping as usize] += child_values[i] / *deck_left as f64;
+                            }
+                        }
+                    }
+                }
+                values
+            },
+            (NodeType::ActionNode(node_info), _) => {
+                let oop = node_info.oop;
+                let (hero_reach, villain_reach) = if oop { (&reach.0, &reach.1) } else { (&reach.1, &reach.0) };
+                let hero_range = &range_manager.get_range(oop, board_masks.0, board_masks.1).hands;
+                let hero_hands = hero_range.len().max(1);
+                let average_strategy = node_info.get_average_strategy();
+                let children = trainer.root.get_children(line.clone(), range_manager);
+
+                // only hands reaching the node, in hand order
+                let mut hands: Vec<(usize, usize)> = hero_range.iter().enumerate().filter(|(i, _)| hero_reach[*i] > 0.0)
+                    .map(|(i, combo)| (hand_order_index(combo, &self.hand_order_mapping), i)).collect();
+                hands.sort();
+                let index = self.nodes.len();
+                self.nodes.push(ExportedNode {
+                    line: line.clone(),
+                    board: board.clone(),
+                    player: if oop { "OOP".to_string() } else { "IP".to_string() },
+                    actions: children.iter().map(|child| child.line.rsplit(':').next().unwrap().to_string()).collect(),
+                    hands: hands.iter().map(|&(j, _)| self.hand_order[j].clone()).collect(),
+                    reach: hands.iter().map(|&(_, i)| hero_reach[i]).collect(),
+                    ev: vec![],
+                    strategy: average_strategy.chunks_exact(hero_hands).map(|frequencies| hands.iter().map(|&(_, i)| frequencies[i]).collect()).collect(),
+                });
+
+                let values = if depth == Some(0) {
+                    (get_counterfactual_values(range_manager, node, true, &reach.1, board_masks, false), get_counterfactual_values(range_manager, node, false, &reach.0, board_masks, false))
+                } else {
+                    let mut values = (vec![0.0; node.oop_num_hands], vec![0.0; node.ip_num_hands]);
+                    for ((child, child_info), frequencies) in node.children.iter().zip(children).zip(average_strategy.chunks_exact(hero_hands)) {
+                        let mut child_reach = reach.clone();
+                        let child_hero_reach = if oop { &mut child_reach.0 } else { &mut child_reach.1 };
+                        for (reach, frequency) in child_hero_reach.iter_mut().zip(frequencies.iter()) {
+                            *reach *= frequency;
+                        }
+                        let child_values = self.export_node(child_info.line, child, board, depth.map(|x| x - 1), &child_reach);
+                        let (hero_values, villain_values) = if oop { (&mut values.0, &mut values.1) } else { (&mut values.1, &mut values.0) };
+                        let (child_hero_values, child_villain_values) = if oop { (&child_values.0, &child_values.1) } else { (&child_values.1, &child_values.0) };
+                        for ((value, child_value), frequency) in hero_values.iter_mut().zip(child_hero_values.iter()).zip(frequencies.iter()) {
+                            *value += frequency * child_value;
+                        }
+                        for (value, child_value) in villain_values.iter_mut().zip(child_villain_values.iter()) {
+                            *value += child_value;
+                        }
+                    }
+                    values
+                };
+
+                // same units as get_ev
+                let villain_range = &range_manager.get_range(!oop, board_masks.0, board_masks.1).hands;
+                let matchups = get_matchups(hero_range, villain_range, villain_reach);
+                let hero_values = if oop { &values.0 } else { &values.1 };
+                self.nodes[index].ev = hands.iter().map(|&(_, i)| value_to_ev(hero_values[i], matchups[i], node.pot_size)).collect();
+                values
+            },
+        }
+    }
+}
+
+/// One row per node, hand and action
+pub fn write_strategy_csv(nodes: &[ExportedNode], path: &str) -> std::io::Result<()> {
+    let mut file = BufWriter::new(File::create(path)?);
+    writeln!(file, "line,board,player,hand,reach,ev,action,frequency")?;
+    for node in nodes {
+        for (i, hand) in node.hands.iter().enumerate() {
+            for (action, frequencies) in node.actions.iter().zip(node.strategy.iter()) {
+                writeln!(file, "{},{},{},{},{},{},{},{}", node.line, node.board, node.player, hand, node.reach[i], node.ev[i], action, frequencies[i])?;
+            }
+        }
+    }
+    file.flush()
+}
+
+pub fn write_strategy_json(nodes: &[ExportedNode], path: &str) -> std::io::Result<()> {
+    let mut file = BufWriter::new(File::create(path)?);
+    serde_json::to_writer(&mut file, nodes)?;
+    file.flush()
+}
+
+/// Writes JSON for paths ending in .json and CSV otherwise
+pub fn export_strategy(trainer: &Trainer, line: &str, path: &str, depth: Option<usize>) -> Result<usize, String> {
+    let nodes = get_subtree_strategy(trainer, line, depth)?;
+    let result = if path.ends_with(".json") {
+        write_strategy_json(&nodes, path)
+    } else {
+        write_strategy_csv(&nodes, path)
+    };
+    result.map_err(|e| format!("couldn't write {}: {}", path, e))?;
+    Ok(nodes.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_subtree_strategy() {
+        let mut trainer = test_config("2c7d9hTsJs").build().unwrap();
+        trainer.solve(&Accuracy::Chips(0.0), TrainFinish::Iterations(10), None, &mut |_| ());
+
+        let nodes = get_subtree_strategy(&trainer, "r:0", None).unwrap();
+        let lines: Vec<&str> = nodes.iter().map(|node| node.line.as_str()).collect();
+        assert_eq!(lines, vec!["r:0", "r:0:c", "r:0:c:b25", "r:0:b25"]);
+
+        // the row of a hand matches the per node queries
+        let node = &nodes[2];
+        let hand = node.hands.iter().position(|hand| hand == "AhKh").unwrap();
+        let i = hand_order_mapping()["AhKh"];
+        let strategy = get_strategy(&trainer, "r:0:c:b25").unwrap();
+        assert_eq!(node.player, "OOP");
+        assert_eq!(node.actions, strategy.actions);
+        assert!((node.reach[hand] - get_range(&trainer, true, "r:0:c:b25").unwrap()[i]).abs() < 1e-9);
+        assert!((node.ev[hand] - get_ev(&trainer, true, "r:0:c:b25").unwrap().values[i]).abs() < 1e-9);
+        for (frequencies, expected) in node.strategy.iter().zip(strategy.frequencies.iter()) {
+            assert!((frequencies[hand] - expected[i]).abs() < 1e-9);
+        }
+    }
+}
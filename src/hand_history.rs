@@ -0,0 +1,166 @@
+use crate::api::*;
+use crate::trainer::*;
+use serde::Serialize;
+use std::collections::HashSet;
+
+/// The solution at one of hero's decisions, EVs are the part of the pot at the node like get_ev
+#[derive(Debug, Clone, Serialize)]
+pub struct HandHistoryDecision {
+    pub line: String,
+    pub board: String,
+    pub ev: f64,
+    pub actions: Vec<String>,
+    pub frequencies: Vec<f64>,
+    pub evs: Vec<f64>,
+    /// action of the hand history and the tree action it was translated to, none at the last decision
+    pub played: Option<(String, String)>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HandHistoryLookup {
+    /// tree line the hand history was translated to
+    pub line: String,
+    /// hand history actions with a size the tree doesn't have and the tree actions they were mapped to
+    pub translations: Vec<(String, String)>,
+    pub decisions: Vec<HandHistoryDecision>,
+}
+
+fn cards(board: &str) -> Vec<&str> {
+    (0..board.len() / 2).map(|i| &board[2*i..2*i + 2]).collect()
+}
+
+// tree action of a hand history action, bets are the amount the player has put in on the street after
+// betting or raising while the tree's are the total invested, street_start is the total before the street
+fn translate_action(action: &str, tree_actions: &[String], street_start: u32) -> Result<String, String> {
+    let action = if action == "x" { "c" } else { action };
+    if action == "c" || action == "f" {
+        return match tree_actions.iter().find(|x| *x == action) {
+            Some(x) => Ok(x.clone()),
+            None => Err(format!("{} isn't possible, the tree has {}", action, tree_actions.join(" "))),
+        };
+    }
+    let size = bet_size(action).ok_or_else(|| format!("invalid action {}", action))? + street_start;
+    nearest_bet(size, tree_actions)
+        .map(|i| tree_actions[i].clone())
+        .ok_or_else(|| format!("{} isn't possible, the tree has {}", action, tree_actions.join(" ")))
+}
+
+fn get_decision(trainer: &Trainer, oop: bool, line: &str, board: &str, hand_index: usize) -> Result<HandHistoryDecision, String> {
+    let strategy = get_strategy(trainer, line)?;
+    Ok(HandHistoryDecision {
+        line: line.to_string(),
+        board: board.to_string(),
+        ev: get_ev(trainer, oop, line)?.values[hand_index],
+        frequencies: strategy.frequencies.iter().map(|frequencies| frequencies[hand_index]).collect(),
+        evs: get_action_evs(trainer, line)?.iter().map(|values| values.values[hand_index]).collect(),
+        actions: strategy.actions,
+        played: None,
+    })
+}
+
+/// Follows a hand history through the tree and returns the solution for hero's hand at each of hero's decisions.
+/// board is the flop and the turn and river dealt in the hand, actions are "c" or "x" for checks and calls, "f"
+/// and "b<amount>" with the amount the player has put in on the street, mapped to the nearest size of the tree
+pub fn lookup_hand(trainer: &Trainer, oop: bool, hand: &str, board: &str, actions: &[&str]) -> Result<HandHistoryLookup, String> {
+    if !is_valid_board(board) {
+        return Err(format!("invalid board {}", board));
+    }
+    let initial_board = &trainer.range_manager.initial_board;
+    let tree_cards: HashSet<&str> = cards(initial_board).into_iter().collect();
+    if board.len() < initial_board.len() || cards(&board[..initial_board.len()]).into_iter().collect::<HashSet<_>>() != tree_cards {
+        return Err(format!("board {} doesn't start with the tree's board {}", board, initial_board));
+    }
+    // two cards of two characters, in either order
+    if hand.len() != 4 || !hand.is_ascii() {
+        return Err(format!("invalid hand {}", hand));
+    }
+    let hand_order_mapping = hand_order_mapping();
+    let hand_index = match hand_order_mapping.get(hand).or_else(|| hand_order_mapping.get(&format!("{}{}", &hand[2..], &hand[..2]))) {
+        Some(i) => *i,
+        None => return Err(format!("invalid hand {}", hand)),
+    };
+    if cards(hand).iter().any(|card| cards(board).contains(card)) {
+        return Err(format!("{} is on the board", hand));
+    }
+
+    let mut lookup = HandHistoryLookup { line: "r:0".to_string(), translations: vec![], decisions: vec![] };
+    let mut actions = actions.iter().peekable();
+    let mut street_start = 0;
+    loop {
+        let node = trainer.root.get_node(lookup.line.clone(), &trainer.range_manager);
+        if node.node_type == "END_NODE" || node.children_count == 0 {
+            break;
+        }
+        let children = trainer.root.get_children(lookup.line.clone(), &trainer.range_manager);
+        let tree_actions: Vec<String> = children.iter().map(|child| child.line.rsplit(':').next().unwrap().to_string()).collect();
+
+        if node.node_type == "SPLIT_NODE" {
+            // the dealt card of the hand history
+            let card = match cards(board).get(node.board.len() / 2) {
+                Some(card) => card.to_string(),
+                None if actions.peek().is_some() => return Err(format!("board {} is missing the card dealt at {}", board, lookup.line)),
+                None => break,
+            };
+            if !tree_actions.contains(&card) {
+                return Err(format!("{} can't be dealt at {}", card, lookup.line));
+            }
+            lookup.line = format!("{}:{}", lookup.line, card);
+            street_start = trainer.root.get_node(lookup.line.clone(), &trainer.range_manager).pot.0;
+            continue;
+        }
+
+        let hero_decision = get_strategy(trainer, &lookup.line)?.oop == oop;
+        let mut decision = if hero_decision {
+            Some(get_decision(trainer, oop, &lookup.line, &node.board, hand_index)?)
+        } else {
+            None
+        };
+        let action = match actions.next() {
+            Some(action) => action,
+            None => {
+                lookup.decisions.extend(decision);
+                break;
+            },
+        };
+        let tree_action = translate_action(action, &tree_actions, street_start).map_err(|e| format!("{} at {}", e, lookup.line))?;
+        if bet_size(action).is_some() && bet_size(action).unwrap() + street_start != bet_size(&tree_action).unwrap() {
+            lookup.translations.push((action.to_string(), tree_action.clone()));
+        }
+        if let Some(decision) = decision.as_mut() {
+            decision.played = Some((action.to_string(), tree_action.clone()));
+        }
+        lookup.decisions.extend(decision);
+        lookup.line = format!("{}:{}", lookup.line, tree_action);
+    }
+
+    if let Some(action) = actions.next() {
+        return Err(format!("the hand is over at {} before {}", lookup.line, action));
+    }
+    Ok(lookup)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_translate_action() {
+        let tree_actions: Vec<String> = vec!["c".to_string(), "b50".to_string(), "b100".to_string()];
+        assert_eq!(translate_action("x", &tree_actions, 0).unwrap(), "c");
+        assert_eq!(translate_action("b30", &tree_actions, 0).unwrap(), "b50");
+        assert_eq!(translate_action("b30", &tree_actions, 40).unwrap(), "b50");
+        assert_eq!(translate_action("b40", &tree_actions, 40).unwrap(), "b100");
+        assert!(translate_action("f", &tree_actions, 0).is_err());
+    }
+
+    #[test]
+    fn test_lookup_hand() {
+        let trainer = test_config("2c7d9hTsJs").build().unwrap();
+
+        let lookup = lookup_hand(&trainer, true, "KhAh", "2c7d9hTsJs", &["x", "b25"]).unwrap();
+        assert_eq!(lookup.line, "r:0:c:b25");
+        assert_eq!(lookup.decisions.len(), 2);
+        assert!(lookup_hand(&trainer, true, "Aéh", "2c7d9hTsJs", &[]).is_err());
+        assert!(lookup_hand(&trainer, true, "AhAh", "2c7d9hTsJs", &[]).is_err());
+    }
+}
@@ -447,6 +447,19 @@ pub fn mask_to_string(card_mask: u64) -> String {
     card_str
 }
 
+/// All 1326 hole card combinations in UPI order, e.g. 2d2c, 2h2c, 2h2d, ..., AsAh
+pub fn get_hand_order() -> Vec<String> {
+    // ranks ascending with suits in c, d, h, s order, the reverse of the card index order
+    let card = |i: u8| 1u64 << (i & !3 | (3 - (i & 3)));
+    let mut hand_order = Vec::with_capacity(1326);
+    for card_1 in 0..CARD_COUNT {
+        for card_2 in 0..card_1 {
+            hand_order.push(mask_to_string(card(card_1)) + &mask_to_string(card(card_2)));
+        }
+    }
+    hand_order
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -495,6 +508,16 @@ mod tests {
         assert_eq!(c.hands.len(), 16);
     }
 
+    #[test]
+    fn test_get_hand_order() {
+        let hand_order = get_hand_order();
+        assert_eq!(hand_order.len(), 1326);
+        assert_eq!(hand_order[0], "2d2c");
+        assert_eq!(hand_order[1325], "AsAh");
+        let unique: std::collections::HashSet<&String> = hand_order.iter().collect();
+        assert_eq!(unique.len(), 1326);
+    }
+
     #[test]
     fn test_hand_range_add_combo() {
         // invalid: card index out of bounds
@@ -1,4 +1,7 @@
 use std::collections::HashMap;
+use rust_poker::constants::{RANK_TO_CHAR, SUIT_TO_CHAR};
+
+use crate::hand_range::{char_to_rank, char_to_suit};
 
 enum FlopType {
     Monotone,
@@ -25,18 +28,23 @@ fn flop_type(board: &String) -> FlopType {
     }
 }
 
-pub fn normalize_flop(board: &String) -> String {
-    let iso_flag = 0;
-    if iso_flag == 0 {
-        return board.to_string();
-    }
-    let mut suits_count: HashMap<char,i64> = HashMap::new();
-    
+// number of cards of every suit on the board
+fn suit_counts(board: &str) -> HashMap<char, i64> {
+    let mut suits_count: HashMap<char, i64> = HashMap::new();
     for i in (0..board.len()).step_by(2) {
         let card = &board[i..i+2].to_lowercase().to_string();
         let suit = card.chars().nth(1).unwrap();
         *suits_count.entry(suit).or_insert(0) += 1;
     }
+    suits_count
+}
+
+pub fn normalize_flop(board: &String) -> String {
+    let iso_flag = 0;
+    if iso_flag == 0 {
+        return board.to_string();
+    }
+    let suits_count = suit_counts(board);
     
     let key_with_max_value = suits_count.iter().max_by_key(|entry | entry.1).unwrap();
     let key_with_min_value = suits_count.iter().min_by_key(|entry | entry.1).unwrap();
@@ -65,13 +73,7 @@ pub fn isomorphism_mapping(board: &String) -> HashMap<char, char>{
         
         return iso_mapping
     }
-    let mut suits_count: HashMap<char,i64> = HashMap::new();
-    
-    for i in (0..board.len()).step_by(2) {
-        let card = &board[i..i+2].to_lowercase().to_string();
-        let suit = card.chars().nth(1).unwrap();
-        *suits_count.entry(suit).or_insert(0) += 1;
-    }
+    let suits_count = suit_counts(board);
     
     let mut iso_mapping = HashMap::new();
     let suits = vec!['h','d','s','c'];
@@ -237,4 +239,142 @@ pub fn isomorphism_mapping(board: &String) -> HashMap<char, char>{
     
     
     iso_mapping
-}
\ No newline at end of file
+}
+
+fn card_to_string(card: u8) -> String {
+    format!("{}{}", RANK_TO_CHAR[usize::from(card >> 2)], SUIT_TO_CHAR[usize::from(card & 3)])
+}
+
+// canonical representative of the flop's isomorphism class. Like in isomorphism_mapping suits only differ by
+// the cards they have on the board, so they're relabeled in the order of their card counts and ranks, and the
+// cards are sorted by descending rank. Suits still tied have the same ranks and are interchangeable
+pub fn canonical_flop(board: &String) -> String {
+    let flop = board[0..6].to_lowercase();
+    let cards: Vec<(u8, char)> = (0..3).map(|i| (char_to_rank(flop[i*2..].chars().next().unwrap()), flop[i*2 + 1..].chars().next().unwrap())).collect();
+    let suits_count = suit_counts(&flop);
+    let suit_ranks = |suit: char| -> Vec<u8> {
+        let mut ranks: Vec<u8> = cards.iter().filter(|card| card.1 == suit).map(|card| card.0).collect();
+        ranks.sort_by(|a, b| b.cmp(a));
+        ranks
+    };
+
+    let mut suits: Vec<char> = suits_count.keys().cloned().collect();
+    suits.sort_by(|a, b| (suits_count[b], suit_ranks(*b)).cmp(&(suits_count[a], suit_ranks(*a))));
+    let mapping: HashMap<char, char> = suits.into_iter().zip(SUIT_TO_CHAR.iter().cloned()).collect();
+
+    let mut canonical_cards: Vec<u8> = cards.iter().map(|card| card.0*4 + char_to_suit(mapping[&card.1])).collect();
+    canonical_cards.sort_by(|a, b| b.cmp(a));
+    canonical_cards.into_iter().map(card_to_string).collect()
+}
+
+// all 1755 strategically distinct flops with their combinatorial weight (sums to 22100)
+pub fn get_all_flops() -> Vec<(String, u32)> {
+    let mut weights: HashMap<String, u32> = HashMap::new();
+
+    for c1 in 0..52u8 {
+        for c2 in 0..c1 {
+            for c3 in 0..c2 {
+                let flop = canonical_flop(&format!("{}{}{}", card_to_string(c1), card_to_string(c2), card_to_string(c3)));
+                *weights.entry(flop).or_insert(0) += 1;
+            }
+        }
+    }
+
+    let mut flops: Vec<(String, u32)> = weights.into_iter().collect();
+    // texture keys tie between flops of different classes, the flop itself makes the order independent of the map's
+    flops.sort_by(|a, b| (texture_key(&a.0), &a.0).cmp(&(texture_key(&b.0), &b.0)));
+    flops
+}
+
+// orders flops by suit texture first (rainbow, two tone, monotone), then by ranks from high to low
+fn texture_key(flop: &String) -> (usize, Vec<u8>) {
+    let mut suits: Vec<char> = flop.chars().skip(1).step_by(2).collect();
+    suits.sort();
+    suits.dedup();
+    let ranks: Vec<u8> = flop.to_lowercase().chars().step_by(2).map(|c| 12 - char_to_rank(c)).collect();
+    (3 - suits.len(), ranks)
+}
+
+// Weighted flop subset of the given size. The texture ordered list of all flops is cut into
+// buckets of roughly equal weight, and the weighted median flop of every bucket represents it
+// with the total weight of the bucket, so the subset keeps the real flop distribution.
+pub fn get_flop_subset(size: usize) -> Vec<(String, u32)> {
+    let all_flops = get_all_flops();
+    if size >= all_flops.len() {
+        return all_flops;
+    }
+
+    let total_weight: u32 = all_flops.iter().map(|x| x.1).sum();
+    let mut subset = vec![];
+    let mut bucket: Vec<&(String, u32)> = vec![];
+    let mut bucket_weight = 0;
+    let mut cumulative_weight = 0;
+
+    for (i, flop) in all_flops.iter().enumerate() {
+        bucket.push(flop);
+        bucket_weight += flop.1;
+        cumulative_weight += flop.1;
+
+        let buckets_left = size - subset.len();
+        let flops_left = all_flops.len() - i - 1;
+        let target = (subset.len() + 1) as f64 * total_weight as f64 / size as f64;
+        if (cumulative_weight as f64 >= target && buckets_left > 1) || flops_left < buckets_left || flops_left == 0 {
+            let mut median_weight = 0;
+            for candidate in bucket.iter() {
+                median_weight += candidate.1;
+                if median_weight * 2 >= bucket_weight {
+                    subset.push((candidate.0.clone(), bucket_weight));
+                    break;
+                }
+            }
+            bucket.clear();
+            bucket_weight = 0;
+        }
+    }
+
+    subset
+}
+
+// named subsets: "all", or "subset<N>" e.g. subset184
+pub fn get_named_flop_subset(name: &str) -> Option<Vec<(String, u32)>> {
+    if name == "all" {
+        return Some(get_all_flops());
+    }
+    match name.strip_prefix("subset").map(|x| x.parse::<usize>()) {
+        Some(Ok(size)) if size > 0 => Some(get_flop_subset(size)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_all_flops() {
+        let flops = get_all_flops();
+        assert_eq!(flops.len(), 1755);
+        assert_eq!(flops.iter().map(|x| x.1).sum::<u32>(), 22100);
+    }
+
+    #[test]
+    fn test_canonical_flop() {
+        assert_eq!(canonical_flop(&"2c7dAh".to_string()), canonical_flop(&"As7h2d".to_string()));
+        assert_ne!(canonical_flop(&"2c7cAh".to_string()), canonical_flop(&"2c7dAh".to_string()));
+    }
+
+    #[test]
+    fn test_flop_order() {
+        assert_eq!(get_all_flops(), get_all_flops());
+        assert_eq!(get_flop_subset(25), get_flop_subset(25));
+    }
+
+    #[test]
+    fn test_flop_subset() {
+        for size in [25, 49, 95, 184] {
+            let subset = get_flop_subset(size);
+            assert_eq!(subset.len(), size);
+            assert_eq!(subset.iter().map(|x| x.1).sum::<u32>(), 22100);
+        }
+    }
+}
@@ -0,0 +1,707 @@
+//! JSON lines protocol, an alternative to UPI for programs driving the solver.
+//!
+//! Every request is one JSON object with a `cmd` field and the command's arguments as fields, an optional
+//! `id` is echoed in everything sent back for the request:
+//!
+//! ```text
+//! {"id": 1, "cmd": "show_strategy", "line": "r:0"}
+//! ```
+//!
+//! Every request gets exactly one response line, `{"id": 1, "cmd": "show_strategy", "ok": true, "result": {...}}`
+//! on success or `{"id": 1, "cmd": "show_strategy", "ok": false, "error": {"code": "invalid_line", "message": "..."}}`.
+//! While `go` runs, every exploitability check is sent as `{"id": 1, "event": "progress", ...}` before the response.
+
+use crate::upi::*;
+use crate::api::*;
+use crate::trainer::*;
+use crate::hand_range::*;
+use crate::equity::*;
+use crate::export::export_strategy;
+use crate::report::write_report;
+use crate::simplify::*;
+use crate::compare::*;
+use crate::practice::*;
+use crate::hand_history::*;
+use crate::checkpoint::*;
+use crate::warmstart::*;
+use crate::resolve::*;
+use crate::batch::*;
+use crate::isomorphism::get_named_flop_subset;
+use crate::postfloptree::CfrAlgorithm;
+use crate::cfr::ChanceSampling;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::AtomicBool;
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub enum Player {
+    #[serde(rename = "oop", alias = "OOP")]
+    Oop,
+    #[serde(rename = "ip", alias = "IP")]
+    Ip,
+}
+
+impl Player {
+    fn is_oop(self) -> bool {
+        match self {
+            Player::Oop => true,
+            Player::Ip => false,
+        }
+    }
+}
+
+/// A range in range syntax, e.g. "AA,KQs@50", or 1326 weights between 0 and 1 in hand order
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum RangeInput {
+    Text(String),
+    Weights(Vec<f64>),
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "cmd", rename_all = "snake_case", deny_unknown_fields)]
+pub enum Command {
+    SetProtocol { protocol: String },
+    IsReady,
+    SetBoard { board: String },
+    SetPot { pot: u32 },
+    SetEffStack { eff_stack: u32 },
+    SetRange { player: Player, range: RangeInput },
+    AddLine { line: Vec<u32> },
+    ClearLines,
+    /// "flop", "turn" or null to solve to the river
+    SetDepthLimit {
+        street: Option<String>,
+        #[serde(default = "default_realization")]
+        oop_realization: f64,
+        #[serde(default = "default_realization")]
+        ip_realization: f64,
+    },
+    /// unit "chips" or "fraction", a percentage of the pot
+    SetAccuracy {
+        value: f64,
+        #[serde(default)]
+        unit: Option<String>,
+    },
+    SetInfoFreq { iterations: u64 },
+    /// "dcfr", "cfr+", "linear" or "vanilla", dcfr takes alpha, beta and gamma together or the defaults
+    SetAlgorithm {
+        algorithm: String,
+        #[serde(default)]
+        alpha: Option<f64>,
+        #[serde(default)]
+        beta: Option<f64>,
+        #[serde(default)]
+        gamma: Option<f64>,
+    },
+    /// cards dealt per chance node, null for a full traversal. polish is the exploitability in % of the pot
+    /// below which full traversals take over
+    SetSampling {
+        cards: Option<usize>,
+        #[serde(default)]
+        polish: f64,
+    },
+    /// full pass every interval iterations, null turns pruning off
+    SetPruning { interval: Option<u64> },
+    ShowAlgorithm,
+    /// unit "steps", "seconds" or "minutes", file null turns checkpoints off
+    SetCheckpoint {
+        file: Option<String>,
+        #[serde(default)]
+        every: Option<u64>,
+        #[serde(default)]
+        unit: Option<String>,
+    },
+    SaveCheckpoint { file: String },
+    /// the tree must be built with the settings and algorithm the checkpoint was saved with
+    LoadCheckpoint { file: String },
+    SaveSolution { file: String },
+    WarmStart { file: String },
+    BuildTree {
+        #[serde(default)]
+        warm: bool,
+    },
+    /// without steps or seconds runs until the accuracy is reached
+    Go {
+        #[serde(default)]
+        steps: Option<u64>,
+        #[serde(default)]
+        seconds: Option<u64>,
+    },
+    /// re-solves the turn or river subgame at line with the current lines, safe keeps the parent counterfactual
+    /// values of the opponent of the given player as a lower bound
+    ResolveSubgame {
+        line: String,
+        #[serde(default)]
+        safe: Option<Player>,
+    },
+    ExitSubgame,
+    ShowHandOrder,
+    ShowNode { line: String },
+    ShowChildren { line: String },
+    ShowStrategy { line: String },
+    ShowRange { player: Player, line: String },
+    CalcLineFreq { line: String },
+    CalcEv { player: Player, line: String },
+    CalcEqNode { player: Player, line: String },
+    CalcRangeEq {
+        #[serde(default)]
+        board: Option<String>,
+    },
+    CalcExploitability { line: String },
+    /// JSON for files ending in .json, CSV otherwise
+    ExportStrategy {
+        line: String,
+        file: String,
+        #[serde(default)]
+        depth: Option<usize>,
+    },
+    /// HTML page of the line and the nodes up to depth below it, 1 by default
+    Report {
+        line: String,
+        file: String,
+        #[serde(default)]
+        depth: Option<usize>,
+    },
+    /// method "round" or "drop" with a percentage, "pure" or "one_size"
+    SimplifyStrategy {
+        line: String,
+        method: String,
+        #[serde(default)]
+        percent: Option<f64>,
+        #[serde(default)]
+        subtree: bool,
+    },
+    UnlockStrategy {
+        line: String,
+        #[serde(default)]
+        subtree: bool,
+    },
+    /// two solutions, JSON exports, checkpoints or saved solutions loaded onto the tree set in the session,
+    /// sort_by "freq" (default), "range" or "ev", all nodes without count
+    CompareSolutions {
+        first: String,
+        second: String,
+        #[serde(default)]
+        sort_by: Option<String>,
+        #[serde(default)]
+        count: Option<usize>,
+    },
+    /// deals a hand to play against the solution, the result is the state at the player's first decision
+    PracticeHand {
+        player: Player,
+        #[serde(default)]
+        seed: Option<u64>,
+    },
+    /// result has the decision compared to the solution and the state after it
+    PracticeAction { action: String },
+    /// actions "c" or "x", "f" and "b<amount>" with the amount put in on the street
+    LookupHand {
+        player: Player,
+        hand: String,
+        board: String,
+        #[serde(default)]
+        actions: Vec<String>,
+    },
+    /// flops of a named subset with their weights, all flops without subset
+    ListFlops {
+        #[serde(default)]
+        subset: Option<String>,
+    },
+    /// solves every flop with the tree settings of the session and writes the CSV report to file. flops is a
+    /// named subset or comma separated flops, without steps or seconds every flop runs until the accuracy is reached
+    SolveBatch {
+        file: String,
+        flops: String,
+        #[serde(default)]
+        steps: Option<u64>,
+        #[serde(default)]
+        seconds: Option<u64>,
+    },
+    Exit,
+}
+
+fn default_realization() -> f64 {
+    1.0
+}
+
+impl Command {
+    // paths of the files the command reads or writes
+    fn files_mut(&mut self) -> Vec<&mut String> {
+        match self {
+            Command::ExportStrategy { file, .. } | Command::Report { file, .. } | Command::SolveBatch { file, .. } | Command::SaveCheckpoint { file }
+                | Command::LoadCheckpoint { file } | Command::SaveSolution { file } | Command::WarmStart { file } => vec![file],
+            Command::SetCheckpoint { file, .. } => file.iter_mut().collect(),
+            Command::CompareSolutions { first, second, .. } => vec![first, second],
+            _ => vec![],
+        }
+    }
+}
+
+/// Which files commands may read and write, the server doesn't let its clients reach the whole disk
+#[derive(Debug, Clone, PartialEq)]
+pub enum FileAccess {
+    Any,
+    Denied,
+    /// relative paths below the directory
+    Within(PathBuf),
+}
+
+fn confine_files(command: &mut Command, file_access: &FileAccess) -> Result<(), JsonError> {
+    for file in command.files_mut() {
+        match file_access {
+            FileAccess::Any => (),
+            FileAccess::Denied => return Err(JsonError::new("forbidden", "file arguments are disabled")),
+            FileAccess::Within(dir) => {
+                if !Path::new(file.as_str()).components().all(|x| matches!(x, Component::Normal(_))) {
+                    return Err(JsonError::new("forbidden", format!("{} has to be a relative path without ..", file)));
+                }
+                *file = dir.join(file.as_str()).to_string_lossy().into_owned();
+            },
+        };
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone)]
+pub struct JsonError {
+    /// parse_error, unknown_command, invalid_argument, missing_tree_settings, no_tree, invalid_line, io_error or forbidden
+    pub code: &'static str,
+    pub message: String,
+}
+
+impl JsonError {
+    fn new(code: &'static str, message: impl Into<String>) -> JsonError {
+        JsonError { code, message: message.into() }
+    }
+}
+
+fn respond(id: &Value, cmd: &str, result: Result<Value, JsonError>, send: &mut dyn FnMut(&Value)) {
+    match result {
+        Ok(result) => send(&json!({ "id": id, "cmd": cmd, "ok": true, "result": result })),
+        Err(e) => send(&json!({ "id": id, "cmd": cmd, "ok": false, "error": { "code": e.code, "message": e.message } })),
+    };
+}
+
+fn get_trainer(trainer: &Option<Trainer>) -> Result<&Trainer, JsonError> {
+    trainer.as_ref().ok_or_else(|| JsonError::new("no_tree", "Built tree not found"))
+}
+
+fn invalid_line(e: String) -> JsonError {
+    JsonError::new("invalid_line", e)
+}
+
+impl CliSession {
+    /// Handles one request, every response and event is passed to send. cancel stops a running go.
+    /// Returns false on exit
+    pub fn handle_json(&mut self, input: &str, cancel: Option<&AtomicBool>, send: &mut dyn FnMut(&Value)) -> bool {
+        let request: Value = match serde_json::from_str(input) {
+            Ok(x) => x,
+            Err(e) => {
+                respond(&Value::Null, "", Err(JsonError::new("parse_error", e.to_string())), send);
+                return true;
+            },
+        };
+        let id = request.get("id").cloned().unwrap_or(Value::Null);
+        let cmd = match request.get("cmd").and_then(|x| x.as_str()) {
+            Some(x) => x.to_string(),
+            None => {
+                respond(&id, "", Err(JsonError::new("parse_error", "missing cmd")), send);
+                return true;
+            },
+        };
+
+        let mut fields = request.clone();
+        if let Some(object) = fields.as_object_mut() {
+            object.remove("id");
+        }
+        let command = match serde_json::from_value::<Command>(fields) {
+            Ok(x) => x,
+            Err(e) => {
+                let code = if e.to_string().starts_with(&format!("unknown variant `{}`", cmd)) { "unknown_command" } else { "invalid_argument" };
+                respond(&id, &cmd, Err(JsonError::new(code, e.to_string())), send);
+                return true;
+            },
+        };
+
+        if let Command::Exit = command {
+            respond(&id, &cmd, Ok(Value::Null), send);
+            return false;
+        }
+        let result = self.run_json_command(&id, command, cancel, send);
+        respond(&id, &cmd, result, send);
+        true
+    }
+
+    fn run_json_command(&mut self, id: &Value, mut command: Command, cancel: Option<&AtomicBool>, send: &mut dyn FnMut(&Value)) -> Result<Value, JsonError> {
+        confine_files(&mut command, &self.file_access)?;
+        match command {
+            Command::SetProtocol { protocol } => {
+                match protocol.as_str() {
+                    "json" => (),
+                    "upi" => self.protocol = Protocol::Upi,
+                    _ => return Err(JsonError::new("invalid_argument", format!("unknown protocol {}", protocol))),
+                };
+            },
+            Command::IsReady => (),
+            Command::SetBoard { board } => {
+                if !is_valid_board(&board) {
+                    return Err(JsonError::new("invalid_argument", format!("invalid board {}", board)));
+                }
+                self.tree_information.board = Some(board);
+            },
+            Command::SetPot { pot } => self.tree_information.pot = Some(pot),
+            Command::SetEffStack { eff_stack } => self.tree_information.eff_stack = Some(eff_stack),
+            Command::SetRange { player, range } => {
+                let range = match range {
+                    RangeInput::Text(text) => HandRange::from_string(text),
+                    RangeInput::Weights(weights) => {
+                        if weights.len() != self.hand_order.len() {
+                            return Err(JsonError::new("invalid_argument", format!("expected {} weights", self.hand_order.len())));
+                        }
+                        range_from_weights(&weights, &self.hand_order)
+                    },
+                };
+                match player {
+                    Player::Oop => self.tree_information.oop_range = Some(range),
+                    Player::Ip => self.tree_information.ip_range = Some(range),
+                };
+            },
+            Command::AddLine { line } => self.tree_information.lines.get_or_insert_with(Vec::new).push(line),
+            Command::ClearLines => self.tree_information.lines = None,
+            Command::SetDepthLimit { street, oop_realization, ip_realization } => {
+                let limit = match street.as_deref() {
+                    None | Some("river") => None,
+                    Some("flop") => Some(6),
+                    Some("turn") => Some(8),
+                    Some(x) => return Err(JsonError::new("invalid_argument", format!("unknown street {}", x))),
+                };
+                if oop_realization.is_nan() || ip_realization.is_nan() || oop_realization < 0.0 || ip_realization < 0.0 {
+                    return Err(JsonError::new("invalid_argument", "negative realization"));
+                }
+                self.tree_information.depth_limit = limit.map(|limit| (limit, oop_realization, ip_realization));
+            },
+            Command::SetAccuracy { value, unit } => {
+                if value.is_nan() || value < 0.0 {
+                    return Err(JsonError::new("invalid_argument", "negative accuracy"));
+                }
+                self.accuracy = match unit.as_deref() {
+                    None | Some("chips") => Accuracy::Chips(value),
+                    Some("fraction") => Accuracy::Fraction(value),
+                    Some(x) => return Err(JsonError::new("invalid_argument", format!("unknown unit {}", x))),
+                };
+            },
+            Command::SetInfoFreq { iterations } => {
+                if iterations == 0 {
+                    return Err(JsonError::new("invalid_argument", "iterations must be positive"));
+                }
+                self.info_freq = iterations;
+            },
+            Command::SetAlgorithm { algorithm, alpha, beta, gamma } => {
+                self.algorithm = match (algorithm.as_str(), alpha, beta, gamma) {
+                    ("dcfr", None, None, None) => CfrAlgorithm::default(),
+                    ("dcfr", Some(alpha), Some(beta), Some(gamma)) => CfrAlgorithm::Dcfr { alpha, beta, gamma },
+                    ("dcfr", _, _, _) => return Err(JsonError::new("invalid_argument", "dcfr takes alpha, beta and gamma together")),
+                    ("cfr+", _, _, _) => CfrAlgorithm::CfrPlus,
+                    ("linear", _, _, _) => CfrAlgorithm::LinearCfr,
+                    ("vanilla", _, _, _) => CfrAlgorithm::VanillaCfr,
+                    _ => return Err(JsonError::new("invalid_argument", format!("unknown algorithm {}", algorithm))),
+                };
+            },
+            Command::SetSampling { cards, polish } => {
+                if polish.is_nan() || polish < 0.0 {
+                    return Err(JsonError::new("invalid_argument", "negative polish"));
+                }
+                self.sampling = match cards {
+                    None => (ChanceSampling::Full, 0.0),
+                    Some(0) => return Err(JsonError::new("invalid_argument", "cards must be positive")),
+                    Some(cards) => (ChanceSampling::Sampled(cards), polish),
+                };
+            },
+            Command::SetPruning { interval } => {
+                if interval == Some(0) {
+                    return Err(JsonError::new("invalid_argument", "interval must be positive"));
+                }
+                self.pruning_interval = interval;
+            },
+            Command::ShowAlgorithm => return Ok(json!({ "algorithm": self.algorithm.to_string() })),
+            Command::SetCheckpoint { file, every, unit } => {
+                self.checkpoint = match (file, every) {
+                    (None, _) => None,
+                    (Some(_), None) | (Some(_), Some(0)) => return Err(JsonError::new("invalid_argument", "every must be positive")),
+                    (Some(path), Some(every)) => {
+                        let frequency = match unit.as_deref() {
+                            None | Some("steps") => CheckpointFrequency::Iterations(every),
+                            Some("seconds") => CheckpointFrequency::Seconds(every),
+                            Some("minutes") => CheckpointFrequency::Seconds(every * 60),
+                            Some(x) => return Err(JsonError::new("invalid_argument", format!("unknown unit {}", x))),
+                        };
+                        Some(CheckpointConfig { path, frequency })
+                    },
+                };
+            },
+            Command::SaveCheckpoint { file } => {
+                save_checkpoint(get_trainer(&self.trainer)?, &file).map_err(|e| JsonError::new("io_error", e.to_string()))?;
+            },
+            Command::LoadCheckpoint { file } => {
+                let trainer = self.trainer.as_mut().ok_or_else(|| JsonError::new("no_tree", "Built tree not found"))?;
+                trainer.algorithm = self.algorithm;
+                trainer.iteration = load_checkpoint(trainer, &file).map_err(|e| JsonError::new("io_error", e.to_string()))?;
+                return Ok(json!({ "iterations": trainer.iteration }));
+            },
+            Command::SaveSolution { file } => {
+                save_snapshot(&take_snapshot(get_trainer(&self.trainer)?), &file).map_err(|e| JsonError::new("io_error", e.to_string()))?;
+            },
+            Command::WarmStart { file } => {
+                let trainer = self.trainer.as_mut().ok_or_else(|| JsonError::new("no_tree", "Built tree not found"))?;
+                let snapshot = load_snapshot(&file).map_err(|e| JsonError::new("io_error", e.to_string()))?;
+                return Ok(json!({ "warm_started_nodes": warm_start(trainer, &snapshot) }));
+            },
+            Command::ResolveSubgame { line, safe } => {
+                let lines = self.tree_information.lines.clone().ok_or_else(|| JsonError::new("missing_tree_settings", "missing lines"))?;
+                let subgame = get_subgame(get_trainer(&self.trainer)?, &line, &self.hand_order, &self.hand_order_map).map_err(invalid_line)?;
+                // the safe bound is on the opponent of the given player
+                let subgame_trainer = build_subgame(&subgame, lines, safe.map(|player| !player.is_oop()));
+                self.parent_trainer = self.trainer.take();
+                self.trainer = Some(subgame_trainer);
+                return Ok(json!({ "board": subgame.board, "pot": subgame.pot, "eff_stack": subgame.eff_stack }));
+            },
+            Command::ExitSubgame => {
+                let parent = self.parent_trainer.take().ok_or_else(|| JsonError::new("invalid_argument", "not in a subgame"))?;
+                self.trainer = Some(parent);
+            },
+            Command::BuildTree { warm } => {
+                let matched = rebuild_trainer(&self.tree_information, &mut self.trainer, warm).map_err(|e| {
+                    let code = if e == "missing/incorrect tree" { "missing_tree_settings" } else { "invalid_argument" };
+                    JsonError::new(code, e)
+                })?;
+                return Ok(json!({ "warm_started_nodes": matched }));
+            },
+            Command::Go { steps, seconds } => {
+                let train_finish = match (steps, seconds) {
+                    (None, None) => TrainFinish::Indefinite,
+                    (Some(steps), None) => TrainFinish::Iterations(steps),
+                    (None, Some(seconds)) => TrainFinish::Seconds(seconds),
+                    _ => return Err(JsonError::new("invalid_argument", "only one of steps and seconds")),
+                };
+                let trainer = self.trainer.as_mut().ok_or_else(|| JsonError::new("no_tree", "Built tree not found"))?;
+                apply_solver_settings(trainer, &self.checkpoint, &self.algorithm, &self.sampling, &self.pruning_interval, self.info_freq);
+                let pot = trainer.root.pot_size as f64;
+                let exploitability = trainer.solve(&self.accuracy, train_finish, cancel, &mut |progress| {
+                    let mut event = serde_json::to_value(progress).unwrap();
+                    event["id"] = id.clone();
+                    event["event"] = json!("progress");
+                    event["exploitability_percent"] = json!(progress.exploitability / pot * 100.0);
+                    send(&event);
+                });
+                return Ok(json!({ "iterations": trainer.iteration, "exploitability": exploitability, "exploitability_percent": exploitability / pot * 100.0 }));
+            },
+            Command::ListFlops { subset } => {
+                let name = subset.unwrap_or_else(|| "all".to_string());
+                let flops = get_named_flop_subset(&name).ok_or_else(|| JsonError::new("invalid_argument", format!("unknown subset {}", name)))?;
+                return Ok(json!(flops.iter().map(|(flop, weight)| json!({ "flop": flop, "weight": weight })).collect::<Vec<_>>()));
+            },
+            Command::SolveBatch { file, flops, steps, seconds } => {
+                let train_finish = match (steps, seconds) {
+                    (None, None) => TrainFinish::Indefinite,
+                    (Some(steps), None) => TrainFinish::Iterations(steps),
+                    (None, Some(seconds)) => TrainFinish::Seconds(seconds),
+                    _ => return Err(JsonError::new("invalid_argument", "only one of steps and seconds")),
+                };
+                let template = get_tree_template(&self.tree_information, &self.algorithm, &self.sampling, &self.pruning_interval, self.info_freq)
+                    .ok_or_else(|| JsonError::new("missing_tree_settings", "missing/incorrect tree"))?;
+                let flops = parse_flops(&flops).map_err(|e| JsonError::new("invalid_argument", e))?;
+                let pot = template.pot as f64;
+                let results = solve_batch(&template, &flops, &self.accuracy, train_finish, cancel, &mut |i, progress| {
+                    let mut event = serde_json::to_value(progress).unwrap();
+                    event["id"] = id.clone();
+                    event["event"] = json!("progress");
+                    event["flop"] = json!(flops[i].0);
+                    event["exploitability_percent"] = json!(progress.exploitability / pot * 100.0);
+                    send(&event);
+                });
+                write_batch_report(&results, &file).map_err(|e| JsonError::new("io_error", format!("couldn't write report: {}", e)))?;
+                return Ok(json!(results.iter().map(|result| json!({
+                    "flop": result.flop,
+                    "weight": result.weight,
+                    "actions": result.actions.iter().map(|action| action.to_string()).collect::<Vec<_>>(),
+                    "frequencies": result.frequencies,
+                    "oop_ev": result.oop_ev,
+                    "ip_ev": result.ip_ev,
+                    "exploitability": result.exploitability,
+                    "exploitability_percent": result.exploitability_pct(),
+                })).collect::<Vec<_>>()));
+            },
+            Command::ShowHandOrder => return Ok(json!(self.hand_order)),
+            Command::ShowNode { line } => {
+                let trainer = get_trainer(&self.trainer)?;
+                check_line(trainer, &line).map_err(invalid_line)?;
+                let node_info = trainer.root.get_node(line, &trainer.range_manager);
+                return Ok(json!({
+                    "line": node_info.line,
+                    "node_type": node_info.node_type,
+                    "board": node_info.board,
+                    "pot": [node_info.pot.0, node_info.pot.1, node_info.pot.2],
+                    "children_count": node_info.children_count,
+                    "flags": node_info.flags,
+                }));
+            },
+            Command::ShowChildren { line } => {
+                let trainer = get_trainer(&self.trainer)?;
+                check_line(trainer, &line).map_err(invalid_line)?;
+                let children: Vec<String> = trainer.root.get_children(line, &trainer.range_manager).into_iter().map(|child| child.line).collect();
+                return Ok(json!(children));
+            },
+            Command::ShowStrategy { line } => {
+                let strategy = get_strategy(get_trainer(&self.trainer)?, &line).map_err(invalid_line)?;
+                return Ok(json!(strategy));
+            },
+            Command::ShowRange { player, line } => {
+                let range = get_range(get_trainer(&self.trainer)?, player.is_oop(), &line).map_err(invalid_line)?;
+                return Ok(json!(range));
+            },
+            Command::CalcLineFreq { line } => {
+                let freq = get_line_freq(get_trainer(&self.trainer)?, &line).map_err(invalid_line)?;
+                return Ok(json!(freq));
+            },
+            Command::CalcEv { player, line } => {
+                let evs = get_ev(get_trainer(&self.trainer)?, player.is_oop(), &line).map_err(invalid_line)?;
+                return Ok(json!(evs));
+            },
+            Command::CalcEqNode { player, line } => {
+                let equities = get_equity(get_trainer(&self.trainer)?, player.is_oop(), &line).map_err(invalid_line)?;
+                return Ok(json!(equities));
+            },
+            Command::CalcRangeEq { board } => {
+                let board = board.or_else(|| self.tree_information.board.clone());
+                match (&self.tree_information.oop_range, &self.tree_information.ip_range, board) {
+                    (Some(oop_range), Some(ip_range), Some(board)) => {
+                        if !is_valid_board(&board) {
+                            return Err(JsonError::new("invalid_argument", format!("invalid board {}", board)));
+                        }
+                        let range_equity = calc_range_equity(oop_range, ip_range, &board);
+                        return Ok(json!({
+                            "oop_equity": range_equity.oop_equity,
+                            "ip_equity": range_equity.ip_equity,
+                            "oop_distribution": range_equity.get_distribution(true),
+                            "ip_distribution": range_equity.get_distribution(false),
+                        }));
+                    },
+                    _ => return Err(JsonError::new("missing_tree_settings", "missing ranges or board")),
+                };
+            },
+            Command::CalcExploitability { line } => {
+                let node_exploitability = get_node_exploitability(get_trainer(&self.trainer)?, &line).map_err(invalid_line)?;
+                let mut result = json!(node_exploitability);
+                result["exploitability"] = json!(node_exploitability.exploitability());
+                return Ok(result);
+            },
+            Command::ExportStrategy { line, file, depth } => {
+                let trainer = get_trainer(&self.trainer)?;
+                check_line(trainer, &line).map_err(invalid_line)?;
+                let count = export_strategy(trainer, &line, &file, depth).map_err(|e| JsonError::new("io_error", e))?;
+                return Ok(json!({ "nodes": count }));
+            },
+            Command::Report { line, file, depth } => {
+                let trainer = get_trainer(&self.trainer)?;
+                check_line(trainer, &line).map_err(invalid_line)?;
+                let count = write_report(trainer, &line, &file, Some(depth.unwrap_or(1))).map_err(|e| JsonError::new("io_error", e))?;
+                return Ok(json!({ "nodes": count }));
+            },
+            Command::SimplifyStrategy { line, method, percent, subtree } => {
+                let percent = percent.filter(|x| *x > 0.0 && *x <= 100.0);
+                let simplification = match (method.as_str(), percent) {
+                    ("round", Some(percent)) => Simplification::Round(percent),
+                    ("drop", Some(percent)) => Simplification::Drop(percent),
+                    ("pure", _) => Simplification::Round(100.0),
+                    ("one_size", _) => Simplification::OneBetSize,
+                    _ => return Err(JsonError::new("invalid_argument", format!("invalid method {} or percent", method))),
+                };
+                let trainer = self.trainer.as_mut().ok_or_else(|| JsonError::new("no_tree", "Built tree not found"))?;
+                let result = simplify_strategy(trainer, &line, simplification, subtree).map_err(invalid_line)?;
+                return Ok(json!(result));
+            },
+            Command::UnlockStrategy { line, subtree } => {
+                let trainer = self.trainer.as_mut().ok_or_else(|| JsonError::new("no_tree", "Built tree not found"))?;
+                let count = unlock_strategy(trainer, &line, subtree).map_err(invalid_line)?;
+                return Ok(json!({ "nodes": count }));
+            },
+            Command::CompareSolutions { first, second, sort_by, count } => {
+                let compare_by = match sort_by.as_deref() {
+                    None | Some("freq") => CompareBy::Frequency,
+                    Some("range") => CompareBy::Range,
+                    Some("ev") => CompareBy::Ev,
+                    Some(x) => return Err(JsonError::new("invalid_argument", format!("invalid sort_by {}", x))),
+                };
+                let config = get_game_config(&self.tree_information).ok();
+                let first = load_solution(&first, config.as_ref(), self.algorithm).map_err(|e| JsonError::new("io_error", e))?;
+                let second = load_solution(&second, config.as_ref(), self.algorithm).map_err(|e| JsonError::new("io_error", e))?;
+                let mut comparison = compare_solutions(&first, &second, compare_by);
+                if let Some(count) = count {
+                    comparison.nodes.truncate(count);
+                }
+                return Ok(json!(comparison));
+            },
+            Command::PracticeHand { player, seed } => {
+                let practice_hand = PracticeHand::deal(get_trainer(&self.trainer)?, player.is_oop(), seed).map_err(|e| JsonError::new("invalid_argument", e))?;
+                let state = practice_hand.state();
+                self.practice = Some(practice_hand);
+                return Ok(json!(state));
+            },
+            Command::PracticeAction { action } => {
+                let trainer = get_trainer(&self.trainer)?;
+                let practice_hand = self.practice.as_mut().ok_or_else(|| JsonError::new("invalid_argument", "no hand dealt, use practice_hand"))?;
+                let decision = practice_hand.play(trainer, &action).map_err(|e| JsonError::new("invalid_argument", e))?;
+                return Ok(json!({ "decision": decision, "state": practice_hand.state() }));
+            },
+            Command::LookupHand { player, hand, board, actions } => {
+                let actions: Vec<&str> = actions.iter().map(|x| x.as_str()).collect();
+                let lookup = lookup_hand(get_trainer(&self.trainer)?, player.is_oop(), &hand, &board, &actions).map_err(|e| JsonError::new("invalid_argument", e))?;
+                return Ok(json!(lookup));
+            },
+            Command::Exit => (),
+        };
+        Ok(Value::Null)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_command() {
+        let command: Command = serde_json::from_str(r#"{"cmd": "calc_ev", "player": "OOP", "line": "r:0:c"}"#).unwrap();
+        assert!(matches!(command, Command::CalcEv { player: Player::Oop, .. }));
+        let command: Command = serde_json::from_str(r#"{"cmd": "set_range", "player": "ip", "range": "AA,KK"}"#).unwrap();
+        assert!(matches!(command, Command::SetRange { range: RangeInput::Text(_), .. }));
+        let command: Command = serde_json::from_str(r#"{"cmd": "go", "steps": 10}"#).unwrap();
+        assert!(matches!(command, Command::Go { steps: Some(10), seconds: None }));
+        let command: Command = serde_json::from_str(r#"{"cmd": "set_sampling", "cards": null}"#).unwrap();
+        assert!(matches!(command, Command::SetSampling { cards: None, .. }));
+        let command: Command = serde_json::from_str(r#"{"cmd": "resolve_subgame", "line": "r:0:c:c:Ks", "safe": "IP"}"#).unwrap();
+        assert!(matches!(command, Command::ResolveSubgame { safe: Some(Player::Ip), .. }));
+        let command: Command = serde_json::from_str(r#"{"cmd": "solve_batch", "file": "batch.csv", "flops": "subset25", "steps": 20}"#).unwrap();
+        assert!(matches!(command, Command::SolveBatch { steps: Some(20), seconds: None, .. }));
+        let command: Command = serde_json::from_str(r#"{"cmd": "list_flops"}"#).unwrap();
+        assert!(matches!(command, Command::ListFlops { subset: None }));
+        assert!(matches!(serde_json::from_str(r#"{"cmd": "show_algorithm"}"#).unwrap(), Command::ShowAlgorithm));
+        // missing and unknown arguments
+        assert!(serde_json::from_str::<Command>(r#"{"cmd": "set_pot"}"#).is_err());
+        assert!(serde_json::from_str::<Command>(r#"{"cmd": "set_pot", "pot": 10, "stack": 5}"#).is_err());
+    }
+
+    #[test]
+    fn test_confine_files() {
+        let export = |file: &str| Command::ExportStrategy { line: "r:0".to_string(), file: file.to_string(), depth: None };
+        let within = FileAccess::Within(PathBuf::from("/srv/solutions"));
+        let mut command = export("flop/2c7d9h.json");
+        confine_files(&mut command, &within).unwrap();
+        assert!(matches!(command, Command::ExportStrategy { file, .. } if file == "/srv/solutions/flop/2c7d9h.json"));
+        assert_eq!(confine_files(&mut export("../secret.json"), &within).unwrap_err().code, "forbidden");
+        assert_eq!(confine_files(&mut export("/etc/passwd"), &within).unwrap_err().code, "forbidden");
+        assert_eq!(confine_files(&mut export("a.json"), &FileAccess::Denied).unwrap_err().code, "forbidden");
+        assert!(confine_files(&mut Command::IsReady, &FileAccess::Denied).is_ok());
+    }
+}
@@ -0,0 +1,40 @@
+//! OpenSolver, a postflop solver for Texas Hold'em using Discounted CFR.
+//!
+//! The [`api`] module is the interface for embedding the solver, see [`GameConfig`] to build a game,
+//! [`Trainer::solve`] to solve it and the query functions in [`api`] for the results. [`CliSession`]
+//! is the UPI command line client built on top of it.
+
+#[macro_use]
+mod output;
+mod postfloptree;
+mod range;
+mod cfr;
+mod hand_range;
+mod best_response;
+mod trainer;
+mod isomorphism;
+mod equity;
+mod batch;
+mod checkpoint;
+mod warmstart;
+mod resolve;
+pub mod api;
+mod export;
+mod report;
+mod simplify;
+mod compare;
+mod practice;
+mod hand_history;
+mod upi;
+mod json_protocol;
+mod server;
+
+pub use api::*;
+pub use hand_range::HandRange;
+pub use trainer::{Trainer, Accuracy, TrainFinish, Progress};
+pub use postfloptree::CfrAlgorithm;
+pub use cfr::ChanceSampling;
+pub use range::RangeManager;
+pub use upi::{CliSession, Protocol};
+pub use server::{serve, ServeOptions};
+pub use output::{set_log, set_quiet, error_count};
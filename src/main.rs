@@ -1,27 +1,87 @@
-use crate::postfloptree::*;
-use crate::range::*;
-use crate::hand_range::*;
-use crate::trainer::*;
-use crate::upi::*;
-
-mod postfloptree;
-mod range;
-mod cfr;
-mod hand_range;
-mod best_response;
-mod trainer;
-mod isomorphism;
-mod upi;
-
-use rust_poker::hand_range::{get_card_mask};
-
+use opensolver::{HandRange, RangeManager, Trainer};
+use opensolver::{CliSession, Protocol};
+use opensolver::{serve, ServeOptions};
+use opensolver::{set_log, set_quiet, error_count};
 
 #[global_allocator]
 static GLOBAL: mimalloc::MiMalloc = mimalloc::MiMalloc;
 
+const USAGE: &str = "usage: opensolver [run <script>] [--keep-going] [--json] [--quiet] [--log <file>]
+       opensolver serve [--port <port>] [--host <host>] [--origin <origin>] [--token <token>] [--dir <directory>]";
+
 fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    let option = |name: &str| args.iter().position(|arg| arg == name).and_then(|i| args.get(i + 1));
+    // serve [--port <port>] [--host <host>] [--origin <origin>] [--token <token>] [--dir <directory>]
+    if args.len() > 1 && args[1] == "serve" {
+        let port = match option("--port").map(|x| x.parse::<u16>()) {
+            Some(Ok(port)) => port,
+            Some(Err(_)) => {
+                println!("ERROR: serve invalid port");
+                std::process::exit(1);
+            },
+            None => 8080,
+        };
+        let options = ServeOptions {
+            host: option("--host").map_or("127.0.0.1".to_string(), |x| x.clone()),
+            port,
+            origin: option("--origin").cloned(),
+            token: option("--token").cloned(),
+            dir: option("--dir").map(|x| x.into()),
+        };
+        if let Err(e) = serve(options) {
+            println!("ERROR: serve {}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+    
+    let mut script = None;
+    let mut i = 1;
+    while i < args.len() {
+        match args[i].as_str() {
+            "run" if i + 1 < args.len() => {
+                script = Some(args[i + 1].clone());
+                i += 1;
+            },
+            "--log" if i + 1 < args.len() => {
+                if let Err(e) = set_log(&args[i + 1]) {
+                    println!("ERROR: couldn't open log {}: {}", args[i + 1], e);
+                    std::process::exit(1);
+                }
+                i += 1;
+            },
+            "--quiet" => set_quiet(true),
+            "--keep-going" => (),
+            "--json" => (),
+            _ => {
+                println!("{}", USAGE);
+                std::process::exit(1);
+            },
+        };
+        i += 1;
+    }
+    
     let mut cli_session = CliSession::new();
-    cli_session.start();
+    if args.iter().any(|arg| arg == "--json") {
+        cli_session.set_protocol(Protocol::Json);
+    }
+    cli_session.set_keep_going(args.iter().any(|arg| arg == "--keep-going"));
+    // a script runs instead of reading stdin
+    match script {
+        Some(script) => {
+            if let Err(e) = cli_session.run_script(&script) {
+                println!("ERROR: run {}", e);
+                std::process::exit(1);
+            }
+            // with --keep-going the script ran to the end
+            if error_count() > 0 {
+                println!("ERROR: run {} failed commands", error_count());
+                std::process::exit(1);
+            }
+        },
+        None => cli_session.start(),
+    };
 }
 
 
@@ -0,0 +1,68 @@
+//! Output of the UPI session. [`output!`] lines are always printed, [`info!`] lines (acknowledgements and
+//! solver progress) are dropped with `--quiet`. With `--log <file>` both and the input commands are also
+//! appended to the file. Failed commands are counted so a script run can tell whether everything went through.
+
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+static QUIET: AtomicBool = AtomicBool::new(false);
+static LOG: Mutex<Option<File>> = Mutex::new(None);
+static ERRORS: AtomicUsize = AtomicUsize::new(0);
+
+pub fn set_quiet(quiet: bool) {
+    QUIET.store(quiet, Ordering::Relaxed);
+}
+
+pub fn set_log(path: &str) -> std::io::Result<()> {
+    let file = OpenOptions::new().create(true).append(true).open(path)?;
+    *LOG.lock().unwrap() = Some(file);
+    Ok(())
+}
+
+fn log(line: &str) {
+    if let Some(file) = LOG.lock().unwrap().as_mut() {
+        // a full disk shouldn't stop the solver
+        let _ = writeln!(file, "{}", line);
+    }
+}
+
+/// Counts a failed command, ERROR lines are counted when they're written
+pub fn count_error() {
+    ERRORS.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Failed commands since the start
+pub fn error_count() -> usize {
+    ERRORS.load(Ordering::Relaxed)
+}
+
+pub fn write_line(line: &str, info: bool) {
+    if line.starts_with("ERROR:") {
+        count_error();
+    }
+    if !info || !QUIET.load(Ordering::Relaxed) {
+        println!("{}", line);
+    }
+    log(line);
+}
+
+/// Logs a command read from the input or a script
+pub fn log_input(line: &str) {
+    log(&format!("> {}", line));
+}
+
+#[macro_export]
+macro_rules! output {
+    ($($arg:tt)*) => {
+        $crate::output::write_line(&format!($($arg)*), false)
+    };
+}
+
+#[macro_export]
+macro_rules! info {
+    ($($arg:tt)*) => {
+        $crate::output::write_line(&format!($($arg)*), true)
+    };
+}
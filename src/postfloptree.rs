@@ -5,15 +5,69 @@ use rust_poker::hand_range::{get_card_mask,mask_to_string};
 use std::collections::HashMap;
 use std::fmt;
 
-// discount CFR params
-const ALPHA: f64 = 1.5;
-const BETA: f64 = 0.5;
-const GAMMA: f64 = 2.0;
+#[derive(Debug,Clone,Copy)]
+pub enum CfrAlgorithm {
+    // discounted CFR, see https://arxiv.org/pdf/1809.04040.pdf
+    Dcfr { alpha: f64, beta: f64, gamma: f64 },
+    // regrets floored at zero and linearly weighted average strategy
+    CfrPlus,
+    // iteration t weighted by t, equivalent to DCFR with alpha = beta = gamma = 1
+    LinearCfr,
+    VanillaCfr,
+}
+
+impl Default for CfrAlgorithm {
+    fn default() -> Self {
+        CfrAlgorithm::Dcfr { alpha: 1.5, beta: 0.0, gamma: 2.0 }
+    }
+}
+
+impl CfrAlgorithm {
+    // multipliers for positive and negative cumulative regrets after iteration t
+    pub fn regret_discounts(&self, n_iterations: u64) -> (f64, f64) {
+        let t = n_iterations as f64;
+        match self {
+            CfrAlgorithm::Dcfr { alpha, beta, .. } => {
+                let x = f64::powf(t, *alpha);
+                let y = f64::powf(t, *beta);
+                (x / (x + 1.0), y / (y + 1.0))
+            },
+            CfrAlgorithm::LinearCfr => (t / (t + 1.0), t / (t + 1.0)),
+            CfrAlgorithm::CfrPlus => (1.0, 0.0),
+            CfrAlgorithm::VanillaCfr => (1.0, 1.0),
+        }
+    }
+    
+    // multiplier for the cumulative strategy after iteration t
+    pub fn strategy_discount(&self, n_iterations: u64) -> f64 {
+        let t = n_iterations as f64;
+        match self {
+            CfrAlgorithm::Dcfr { gamma, .. } => f64::powf(t / (t + 1.0), *gamma),
+            CfrAlgorithm::LinearCfr | CfrAlgorithm::CfrPlus => t / (t + 1.0),
+            CfrAlgorithm::VanillaCfr => 1.0,
+        }
+    }
+}
+
+impl fmt::Display for CfrAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CfrAlgorithm::Dcfr { alpha, beta, gamma } => write!(f, "dcfr {} {} {}", alpha, beta, gamma),
+            CfrAlgorithm::CfrPlus => write!(f, "cfr+"),
+            CfrAlgorithm::LinearCfr => write!(f, "linear"),
+            CfrAlgorithm::VanillaCfr => write!(f, "vanilla"),
+        }
+    }
+}
 
 
 #[derive(Debug,Clone,Copy)]
 pub enum TerminalType {
     TerminalShowdown,
+    // leaf of a depth limited tree, valued by the equity of both ranges
+    TerminalEquity,
+    // both players all in before the river, the remaining cards are enumerated in the payoffs
+    TerminalAllIn,
     TerminalFold(bool),
 }
 
@@ -25,6 +79,8 @@ pub struct ActionNodeInfo {
     regret_sum: Vec<f64>,
     pub actions_num: usize,
     hands_num: usize,
+    // strategy fixed by simplify_strategy, played instead of the current and average strategy
+    pub locked_strategy: Option<Vec<f64>>,
 }
 
 impl ActionNodeInfo {
@@ -33,93 +89,113 @@ impl ActionNodeInfo {
         let strategy_sum = vec![0.0; hands_num * actions_num];
         let regret_sum = strategy_sum.clone();
         
-        ActionNodeInfo { oop, actions, strategy_sum, regret_sum, actions_num, hands_num }
+        ActionNodeInfo { oop, actions, strategy_sum, regret_sum, actions_num, hands_num, locked_strategy: None }
     }
     
+    // regrets, strategies and their sums are stored action major: all hands of action 0, then action 1, ...
+    // so every update is a straight loop over one action that the compiler can vectorise
     pub fn get_current_strategy(&self) -> Vec<f64> {
-        let mut strategy = self.regret_sum.clone();
-        strategy.iter_mut().for_each(|x| *x = x.max(0.0));
-     
-        strategy.chunks_mut(self.actions_num).for_each(|slice| {
-            let hand_sum_regrets: f64 = slice.iter().sum();
-            if hand_sum_regrets > 0.0 {
-                slice.iter_mut().for_each(|x| *x /= hand_sum_regrets);
-            } else {
-                slice.iter_mut().for_each(|x| *x = 1.0/self.actions_num as f64);
-            }
-        });
-        
+        if let Some(locked_strategy) = &self.locked_strategy {
+            return locked_strategy.clone();
+        }
+        let mut strategy: Vec<f64> = self.regret_sum.iter().map(|x| x.max(0.0)).collect();
+        normalize_strategy(&mut strategy, self.hands_num, self.actions_num);
         strategy
     }
     
     pub fn update_regret_sum_1(&mut self, action_utilities: &[f64], n_action: usize) {
-        let mut offset = 0;
-        for utility in action_utilities.iter() {
-            self.regret_sum[offset+n_action] += utility;
-            offset += self.actions_num;
+        let regrets = &mut self.regret_sum[n_action*self.hands_num..(n_action+1)*self.hands_num];
+        for (regret, utility) in regrets.iter_mut().zip(action_utilities.iter()) {
+            *regret += utility;
         }
     }
     
-    pub fn update_regret_sum_2(&mut self, action_utilities: &[f64], n_iterations: u64) {
-        let mut x = f64::powf(n_iterations as f64, ALPHA);
-        x = x / (x + 1.0);
-        let mut offset = 0;
+    // actions never played by any hand with negative regret for every hand, see regret-based pruning
+    // https://papers.nips.cc/paper/2015/file/c54e7837e0cd0ced286cb5995327d1ab-Paper.pdf
+    pub fn get_prunable_actions(&self, strategy: &[f64]) -> Vec<bool> {
+        if self.hands_num == 0 {
+            return vec![false; self.actions_num];
+        }
         
-        for utility in action_utilities.iter() {
-            for j in 0..self.actions_num {
-                self.regret_sum[offset+j] -= utility;
-                if self.regret_sum[offset+j] > 0.0 {
-                    self.regret_sum[offset+j] *= x;
-                } else {
-                    self.regret_sum[offset+j] *= BETA;
+        self.regret_sum.chunks_exact(self.hands_num)
+                       .zip(strategy.chunks_exact(self.hands_num))
+                       .map(|(regrets, action_strategy)| regrets.iter().all(|x| *x < 0.0) && action_strategy.iter().all(|x| *x <= 0.0))
+                       .collect()
+    }
+    
+    // pruned actions get no instantaneous regret, only the discount
+    pub fn update_regret_sum_2(&mut self, action_utilities: &[f64], n_iterations: u64, algorithm: &CfrAlgorithm, pruned: &[bool]) {
+        if self.hands_num == 0 {
+            return;
+        }
+        let (x, y) = algorithm.regret_discounts(n_iterations);
+        
+        for (j, regrets) in self.regret_sum.chunks_exact_mut(self.hands_num).enumerate() {
+            if pruned[j] == false {
+                for (regret, utility) in regrets.iter_mut().zip(action_utilities.iter()) {
+                    *regret -= utility;
                 }
             }
-            offset += self.actions_num;
+            for regret in regrets.iter_mut() {
+                *regret *= if *regret > 0.0 { x } else { y };
+            }
         }
     }
     
-    pub fn update_strategy_sum(&mut self, strategy: &[f64], reach_probs: &[f64], n_iterations: u64 ) {
-        let x = f64::powf(n_iterations as f64 / (n_iterations as f64 + 1.0), GAMMA);
-        let mut offset = 0;
-        for reach_prob in reach_probs.iter() {
-            for j in 0..self.actions_num {
-                self.strategy_sum[offset+j] += strategy[offset+j] * reach_prob;
-                self.strategy_sum[offset+j] *= x;
+    pub fn update_strategy_sum(&mut self, strategy: &[f64], reach_probs: &[f64], n_iterations: u64, algorithm: &CfrAlgorithm) {
+        if self.hands_num == 0 {
+            return;
+        }
+        let x = algorithm.strategy_discount(n_iterations);
+        
+        for (strategy_sum, action_strategy) in self.strategy_sum.chunks_exact_mut(self.hands_num).zip(strategy.chunks_exact(self.hands_num)) {
+            for ((sum, action_freq), reach_prob) in strategy_sum.iter_mut().zip(action_strategy.iter()).zip(reach_probs.iter()) {
+                *sum = (*sum + action_freq * reach_prob) * x;
             }
-            
-            offset += self.actions_num;
         }
     }
     
+    pub fn get_regret_sum(&self) -> &[f64] {
+        &self.regret_sum
+    }
+    
+    pub fn set_sums(&mut self, regret_sum: Vec<f64>, strategy_sum: Vec<f64>) {
+        self.regret_sum = regret_sum;
+        self.strategy_sum = strategy_sum;
+    }
+    
     pub fn get_average_strategy(&self) -> Vec<f64> {
-        let mut average_strategy = vec![0.0; self.hands_num * self.actions_num];
-        let mut offset = 0;
-        
-        for _ in 0..self.hands_num {
-            let mut total = 0.0;
-            
-            for j in 0..self.actions_num {
-                total += self.strategy_sum[offset+j];
-            }
-            
-            if total > 0.0 {
-                for j in 0..self.actions_num {
-                    average_strategy[offset+j] = self.strategy_sum[offset+j] / total;
-                }
-            } else {
-                for j in 0..self.actions_num {
-                    average_strategy[offset+j] = 1.0/self.actions_num as f64;
-                }
-            }
-            
-            offset += self.actions_num;
+        if let Some(locked_strategy) = &self.locked_strategy {
+            return locked_strategy.clone();
         }
-        
+        let mut average_strategy = self.strategy_sum.clone();
+        normalize_strategy(&mut average_strategy, self.hands_num, self.actions_num);
         average_strategy
     }
 
 }
 
+// normalizes action major weights per hand, hands without any weight play uniformly
+pub(crate) fn normalize_strategy(strategy: &mut [f64], hands_num: usize, actions_num: usize) {
+    if hands_num == 0 {
+        return;
+    }
+    
+    let mut totals = vec![0.0; hands_num];
+    for action_weights in strategy.chunks_exact(hands_num) {
+        for (total, weight) in totals.iter_mut().zip(action_weights.iter()) {
+            *total += weight;
+        }
+    }
+    
+    let uniform = 1.0 / actions_num as f64;
+    for action_weights in strategy.chunks_exact_mut(hands_num) {
+        for (weight, total) in action_weights.iter_mut().zip(totals.iter()) {
+            *weight = if *total > 0.0 { *weight / total } else { uniform };
+        }
+    }
+}
+
 #[derive(Debug)]
 pub enum NodeType {
     ActionNode(ActionNodeInfo),
@@ -139,6 +215,8 @@ pub struct Node {
     pub chance_start_pot: u32,
     pub oop_num_hands: usize,
     pub ip_num_hands: usize,
+    // a chance node in this subtree deals more than one card, set once by mark_card_splits
+    pub card_split: bool,
 }
 
 #[derive(Debug)]
@@ -188,12 +266,12 @@ fn chunks(s: &str, length: usize) -> impl Iterator<Item=&str> {
 
 impl Node {
     pub fn new_root(chance_start_stack: u32, pot_size: u32, oop_num_hands: usize, ip_num_hands: usize) -> Node {
-        Node { node_type: NodeType::ChanceNode(0), children: vec![] , pot_size, chance_start_stack, oop_invested: 0, ip_invested: 0, chance_start_pot: pot_size, oop_num_hands, ip_num_hands}
+        Node { node_type: NodeType::ChanceNode(0), children: vec![] , pot_size, chance_start_stack, oop_invested: 0, ip_invested: 0, chance_start_pot: pot_size, oop_num_hands, ip_num_hands, card_split: false }
     }
     
     // functions for UPI compatibility
     
-    fn find_node(&self, line: &String, range_manager: &RangeManager) -> (String, &Node, u32, u32, u32, u32) {
+    pub fn find_node(&self, line: &String, range_manager: &RangeManager) -> (String, &Node, u32, u32, u32, u32) {
         let v = line.as_str().split(':').collect::<Vec<&str>>();
         let mut current_board = range_manager.initial_board.clone();
         
@@ -327,6 +405,38 @@ impl Node {
         (current_board, current_node, oop_invested, ip_invested, start_pot, previous_invested)
     }
     
+    // child indices from the root action node to the node of the line, in the order of get_children
+    fn get_path(&self, line: &String, range_manager: &RangeManager) -> Vec<usize> {
+        let actions: Vec<&str> = line.split(':').collect();
+        let mut path = vec![];
+        let mut current_line = "r:0".to_string();
+        for action in actions.iter().skip(2) {
+            let next_line = format!("{}:{}", current_line, action);
+            let children = self.get_children(current_line, range_manager);
+            match children.iter().position(|child| child.line == next_line) {
+                Some(i) => path.push(i),
+                None => panic!("Couldn't find line"),
+            };
+            current_line = next_line;
+        }
+        path
+    }
+    
+    pub fn find_node_mut(&mut self, line: &String, range_manager: &RangeManager) -> &mut Node {
+        let path = self.get_path(line, range_manager);
+        let mut current_node = &mut self.children[0].children[0];
+        for i in path {
+            // dealt cards lead to the action node below the card node
+            let chance = matches!(current_node.node_type, NodeType::ChanceNode(_));
+            current_node = if chance {
+                &mut current_node.children[i].children[0]
+            } else {
+                &mut current_node.children[i]
+            };
+        }
+        current_node
+    }
+    
     pub fn get_line_freq(&self, line: String, range_manager: &RangeManager, hand_order_mapping: &HashMap<String, usize>) -> f64 {
         let mut line_freqs = vec![0.0, 0.0];
         for (i,&oop) in [false, true].iter().enumerate() {
@@ -427,7 +537,7 @@ impl Node {
                                 TerminalType::TerminalFold(_) => {
                                     "f".to_string()
                                 },
-                                TerminalType::TerminalShowdown => {
+                                TerminalType::TerminalShowdown | TerminalType::TerminalEquity | TerminalType::TerminalAllIn => {
                                     if oop_invested > ip_invested {
                                         ip_invested = oop_invested;
                                     } else {
@@ -522,13 +632,12 @@ impl Node {
                     player_range_mapping.insert((hand.0, hand.1), hand_idx);
                 }
                 let average_strategy = node_info.get_average_strategy();
-                let mut counter = 0;
-                average_strategy.chunks(node_info.actions_num).for_each(|slice| {
-                    for (i, action_freq) in slice.iter().enumerate() {
+                let hands_num = player_range.len();
+                for (i, action_strategy) in average_strategy.chunks_exact(hands_num.max(1)).enumerate() {
+                    for (counter, action_freq) in action_strategy.iter().enumerate() {
                         final_strategy[i][**player_range_mapping.get(&(player_range[counter].0, player_range[counter].1)).unwrap() as usize] = *action_freq;
                     }
-                    counter += 1;
-                });
+                }
                 final_strategy
             },
             _ => panic!("incorrect path"),
@@ -591,12 +700,11 @@ impl Node {
                             } else {
                                 if node_info.oop == oop {
                                     let average_strategy = node_info.get_average_strategy();
-                                    let mut counter = 0;
-                                    average_strategy.chunks(node_info.actions_num).for_each(|slice| {
-                                        let action_freq = slice[action_num as usize];
+                                    let hands_num = player_range.len();
+                                    let action_strategy = &average_strategy[action_num as usize*hands_num..(action_num as usize + 1)*hands_num];
+                                    for (counter, action_freq) in action_strategy.iter().enumerate() {
                                         final_range[**player_range_mapping.get(&(player_range[counter].0, player_range[counter].1)).unwrap() as usize] *= action_freq;
-                                        counter += 1;
-                                    });
+                                    }
                                 }
                             }
                             
@@ -640,12 +748,11 @@ impl Node {
                             } else {
                                 if node_info.oop == oop {
                                     let average_strategy = node_info.get_average_strategy();
-                                    let mut counter = 0;
-                                    average_strategy.chunks(node_info.actions_num).for_each(|slice| {
-                                        let action_freq = slice[action_num as usize];
+                                    let hands_num = player_range.len();
+                                    let action_strategy = &average_strategy[action_num as usize*hands_num..(action_num as usize + 1)*hands_num];
+                                    for (counter, action_freq) in action_strategy.iter().enumerate() {
                                         final_range[**player_range_mapping.get(&(player_range[counter].0, player_range[counter].1)).unwrap() as usize] *= action_freq;
-                                        counter += 1;
-                                    });
+                                    }
                                 }
                             }
                             
@@ -677,12 +784,11 @@ impl Node {
                             } else {
                                 if node_info.oop == oop {
                                     let average_strategy = node_info.get_average_strategy();
-                                    let mut counter = 0;
-                                    average_strategy.chunks(node_info.actions_num).for_each(|slice| {
-                                        let action_freq = slice[action_num as usize];
+                                    let hands_num = player_range.len();
+                                    let action_strategy = &average_strategy[action_num as usize*hands_num..(action_num as usize + 1)*hands_num];
+                                    for (counter, action_freq) in action_strategy.iter().enumerate() {
                                         final_range[**player_range_mapping.get(&(player_range[counter].0, player_range[counter].1)).unwrap() as usize] *= action_freq;
-                                        counter += 1;
-                                    });
+                                    }
                                 }
                             }
                         },
@@ -750,6 +856,20 @@ pub enum ActionType {
     Raise{sizing: u32},
 }
 
+impl fmt::Display for ActionType {
+    // short notation of reports and saved solutions, not UPI lines: checks are x where UPI writes c, and bets and
+    // raises show the sizing stored in the tree where UPI lines have the total invested on the street
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ActionType::Fold => write!(f, "f"),
+            ActionType::Check => write!(f, "x"),
+            ActionType::Call => write!(f, "c"),
+            ActionType::Bet(sizing) => write!(f, "b{}", sizing),
+            ActionType::Raise{sizing} => write!(f, "b{}", sizing),
+        }
+    }
+}
+
 pub fn get_sizings(lines: Vec<Vec<u32>>) -> HashMap<String, Vec<ActionType>> {
     let mut sizing_mapping = HashMap::new();
     
@@ -808,6 +928,55 @@ pub fn get_sizings(lines: Vec<Vec<u32>>) -> HashMap<String, Vec<ActionType>> {
     sizing_mapping
 }
 
+// node after a street is closed by a call or check
+fn street_end_node_type(current_board: &String, range_manager: &RangeManager, all_in: bool) -> NodeType {
+    let depth_limit = match range_manager.depth_limit {
+        Some((limit, _, _)) => current_board.len() == limit,
+        None => false,
+    };
+    
+    if current_board.len() == 10 {
+        NodeType::TerminalNode(TerminalType::TerminalShowdown)
+    } else if all_in {
+        NodeType::TerminalNode(TerminalType::TerminalAllIn)
+    } else if depth_limit {
+        NodeType::TerminalNode(TerminalType::TerminalEquity)
+    } else {
+        NodeType::ChanceNode((range_manager.get_board_deck(get_card_mask(current_board)).len() - 4).try_into().unwrap())
+    }
+}
+
+// boards of all all in terminals, their runouts are precomputed by the range manager
+pub fn collect_allin_boards(node: &Node, board_masks: (u64, Option<u64>), boards: &mut Vec<(u64, Option<u64>)>) {
+    match node.node_type {
+        NodeType::TerminalNode(TerminalType::TerminalAllIn) => {
+            if !boards.contains(&board_masks) {
+                boards.push(board_masks);
+            }
+        },
+        NodeType::ChanceNodeCard(new_masks) => {
+            for child in &node.children {
+                collect_allin_boards(child, new_masks, boards);
+            }
+        },
+        _ => {
+            for child in &node.children {
+                collect_allin_boards(child, board_masks, boards);
+            }
+        },
+    }
+}
+
+// marks the nodes with a card split below, the solvers parallelise over those cards instead of the actions
+pub fn mark_card_splits(node: &mut Node) -> bool {
+    let mut card_split = matches!(node.node_type, NodeType::ChanceNode(_)) && node.children.len() > 1;
+    for child in node.children.iter_mut() {
+        card_split |= mark_card_splits(child);
+    }
+    node.card_split = card_split;
+    card_split
+}
+
 pub fn recursive_build(latest_action: Option<ActionType>, sizing_mapping: &HashMap<String, Vec<ActionType>>, action_line: &String, current_node: &mut Node, range_manager: &RangeManager, current_board: &String) {
     match &current_node.node_type {
         NodeType::ChanceNode(_) => {
@@ -825,7 +994,7 @@ pub fn recursive_build(latest_action: Option<ActionType>, sizing_mapping: &HashM
                             let new_board_mask = get_card_mask(&new_board);
                             
                             let new_eff_stack = current_node.chance_start_stack - (current_node.pot_size - current_node.chance_start_pot)/2;
-                            let mut node_new = Node { node_type: NodeType::ChanceNodeCard((new_board_mask, None)), children: vec![], pot_size: current_node.pot_size, chance_start_stack: new_eff_stack, oop_invested: 0, ip_invested: 0, chance_start_pot: current_node.pot_size, oop_num_hands: range_manager.get_num_hands(true, board_mask, None), ip_num_hands: range_manager.get_num_hands(false, board_mask, None) };
+                            let mut node_new = Node { node_type: NodeType::ChanceNodeCard((new_board_mask, None)), children: vec![], pot_size: current_node.pot_size, chance_start_stack: new_eff_stack, oop_invested: 0, ip_invested: 0, chance_start_pot: current_node.pot_size, oop_num_hands: range_manager.get_num_hands(true, board_mask, None), ip_num_hands: range_manager.get_num_hands(false, board_mask, None), card_split: false };
                             recursive_build(None, sizing_mapping, action_line, &mut node_new, range_manager, &new_board);
                             current_node.children.push(node_new);
                         }
@@ -847,7 +1016,7 @@ pub fn recursive_build(latest_action: Option<ActionType>, sizing_mapping: &HashM
                             };
                             
                             let new_eff_stack = current_node.chance_start_stack - (current_node.pot_size - current_node.chance_start_pot)/2;
-                            let mut node_new = Node { node_type: NodeType::ChanceNodeCard((new_board_mask, old_board_mask)), children: vec![], pot_size: current_node.pot_size, chance_start_stack: new_eff_stack, oop_invested: 0, ip_invested: 0, chance_start_pot: current_node.pot_size, oop_num_hands: range_manager.get_num_hands(true, new_board_mask, old_board_mask), ip_num_hands: range_manager.get_num_hands(false, new_board_mask, old_board_mask) };
+                            let mut node_new = Node { node_type: NodeType::ChanceNodeCard((new_board_mask, old_board_mask)), children: vec![], pot_size: current_node.pot_size, chance_start_stack: new_eff_stack, oop_invested: 0, ip_invested: 0, chance_start_pot: current_node.pot_size, oop_num_hands: range_manager.get_num_hands(true, new_board_mask, old_board_mask), ip_num_hands: range_manager.get_num_hands(false, new_board_mask, old_board_mask), card_split: false };
                             recursive_build(None, sizing_mapping, action_line, &mut node_new, range_manager, &new_board);
                             current_node.children.push(node_new);
                         }
@@ -857,7 +1026,7 @@ pub fn recursive_build(latest_action: Option<ActionType>, sizing_mapping: &HashM
                 },
                 None => {
                         let board_mask = get_card_mask(current_board);
-                        let mut node_new = Node { node_type: NodeType::ChanceNodeCard((board_mask, None)), children: vec![], pot_size: current_node.pot_size, chance_start_stack: current_node.chance_start_stack, oop_invested: 0, ip_invested: 0, chance_start_pot: current_node.chance_start_pot, oop_num_hands: range_manager.get_num_hands(true, board_mask, None), ip_num_hands: range_manager.get_num_hands(false, board_mask, None) };
+                        let mut node_new = Node { node_type: NodeType::ChanceNodeCard((board_mask, None)), children: vec![], pot_size: current_node.pot_size, chance_start_stack: current_node.chance_start_stack, oop_invested: 0, ip_invested: 0, chance_start_pot: current_node.chance_start_pot, oop_num_hands: range_manager.get_num_hands(true, board_mask, None), ip_num_hands: range_manager.get_num_hands(false, board_mask, None), card_split: false };
                         let current_line = "r".to_string();
                         recursive_build(None, sizing_mapping, &current_line, &mut node_new, range_manager, current_board);
                         current_node.children.push(node_new);
@@ -884,7 +1053,7 @@ pub fn recursive_build(latest_action: Option<ActionType>, sizing_mapping: &HashM
             };
             
             actions_new.dedup();
-            let mut node_new = Node { node_type: NodeType::ActionNode(ActionNodeInfo::new(true, actions_new, range_manager.get_num_hands(true, board_mask, old_board_mask))), children: vec![], pot_size: current_node.pot_size, chance_start_stack: current_node.chance_start_stack, oop_invested: 0, ip_invested: 0, chance_start_pot: current_node.chance_start_pot, oop_num_hands: range_manager.get_num_hands(true, board_mask, old_board_mask), ip_num_hands: range_manager.get_num_hands(false, board_mask, old_board_mask) };
+            let mut node_new = Node { node_type: NodeType::ActionNode(ActionNodeInfo::new(true, actions_new, range_manager.get_num_hands(true, board_mask, old_board_mask))), children: vec![], pot_size: current_node.pot_size, chance_start_stack: current_node.chance_start_stack, oop_invested: 0, ip_invested: 0, chance_start_pot: current_node.chance_start_pot, oop_num_hands: range_manager.get_num_hands(true, board_mask, old_board_mask), ip_num_hands: range_manager.get_num_hands(false, board_mask, old_board_mask), card_split: false };
             
             recursive_build(None, sizing_mapping, action_line, &mut node_new, range_manager, current_board);
             current_node.children.push(node_new);
@@ -909,18 +1078,14 @@ pub fn recursive_build(latest_action: Option<ActionType>, sizing_mapping: &HashM
                                     None
                                 };
 
-                                let mut node_new = Node { node_type: NodeType::TerminalNode(TerminalType::TerminalFold(oop)), children: vec![], pot_size: eff_pot_size, chance_start_stack: current_node.chance_start_stack, oop_invested: 0, ip_invested: 0, chance_start_pot: current_node.chance_start_pot, oop_num_hands: range_manager.get_num_hands(true, board_mask, old_board_mask), ip_num_hands: range_manager.get_num_hands(false, board_mask, old_board_mask) };
+                                let mut node_new = Node { node_type: NodeType::TerminalNode(TerminalType::TerminalFold(oop)), children: vec![], pot_size: eff_pot_size, chance_start_stack: current_node.chance_start_stack, oop_invested: 0, ip_invested: 0, chance_start_pot: current_node.chance_start_pot, oop_num_hands: range_manager.get_num_hands(true, board_mask, old_board_mask), ip_num_hands: range_manager.get_num_hands(false, board_mask, old_board_mask), card_split: false };
                                 recursive_build(Some(*action), sizing_mapping, action_line, &mut node_new, range_manager, current_board);
                                 current_node.children.push(node_new);
                             },
                             ActionType::Call => {
                                 // Add terminal call, or next street if turn/river
                                 let eff_pot_size = current_node.pot_size + (sizing - min(current_node.oop_invested,current_node.ip_invested));
-                                let node_type_new = if current_board.len() == 10 {
-                                    NodeType::TerminalNode(TerminalType::TerminalShowdown)
-                                } else {
-                                    NodeType::ChanceNode((range_manager.get_board_deck(get_card_mask(current_board)).len() - 4).try_into().unwrap())
-                                };
+                                let node_type_new = street_end_node_type(current_board, range_manager, sizing == current_node.chance_start_stack);
                                 
                                 let board_mask = get_card_mask(current_board);
                                 let old_board_mask = if range_manager.initial_board.len() == 6 && current_board.len() == 10 {
@@ -930,7 +1095,7 @@ pub fn recursive_build(latest_action: Option<ActionType>, sizing_mapping: &HashM
                                     None
                                 };
                                 let action_line = format!("{}:c",action_line);
-                                let mut node_new = Node { node_type: node_type_new, children: vec![], pot_size: eff_pot_size, chance_start_stack: current_node.chance_start_stack, oop_invested: 0, ip_invested: 0, chance_start_pot: current_node.chance_start_pot, oop_num_hands: range_manager.get_num_hands(true, board_mask, old_board_mask), ip_num_hands: range_manager.get_num_hands(false, board_mask, old_board_mask) };
+                                let mut node_new = Node { node_type: node_type_new, children: vec![], pot_size: eff_pot_size, chance_start_stack: current_node.chance_start_stack, oop_invested: 0, ip_invested: 0, chance_start_pot: current_node.chance_start_pot, oop_num_hands: range_manager.get_num_hands(true, board_mask, old_board_mask), ip_num_hands: range_manager.get_num_hands(false, board_mask, old_board_mask), card_split: false };
                                 
                                 recursive_build(Some(*action), sizing_mapping, &action_line, &mut node_new, range_manager, current_board);
                                 current_node.children.push(node_new);
@@ -977,7 +1142,7 @@ pub fn recursive_build(latest_action: Option<ActionType>, sizing_mapping: &HashM
                                     None
                                 };
                                 
-                                let mut node_new = Node { node_type: NodeType::ActionNode(ActionNodeInfo::new(oop_new, actions_new, range_manager.get_num_hands(oop_new, board_mask, old_board_mask))), children: vec![], pot_size: pot_size_new, chance_start_stack: current_node.chance_start_stack, oop_invested: oop_invested_new, ip_invested: ip_invested_new, chance_start_pot: current_node.chance_start_pot, oop_num_hands: range_manager.get_num_hands(true, board_mask, old_board_mask), ip_num_hands: range_manager.get_num_hands(false, board_mask, old_board_mask) };
+                                let mut node_new = Node { node_type: NodeType::ActionNode(ActionNodeInfo::new(oop_new, actions_new, range_manager.get_num_hands(oop_new, board_mask, old_board_mask))), children: vec![], pot_size: pot_size_new, chance_start_stack: current_node.chance_start_stack, oop_invested: oop_invested_new, ip_invested: ip_invested_new, chance_start_pot: current_node.chance_start_pot, oop_num_hands: range_manager.get_num_hands(true, board_mask, old_board_mask), ip_num_hands: range_manager.get_num_hands(false, board_mask, old_board_mask), card_split: false };
                                 
                                 recursive_build(Some(*action), sizing_mapping, &action_line, &mut node_new, range_manager, current_board);
                                 current_node.children.push(node_new);
@@ -993,11 +1158,7 @@ pub fn recursive_build(latest_action: Option<ActionType>, sizing_mapping: &HashM
                     for action in actions {
                         match action {
                             ActionType::Check => { // XX line - terminal showdown (or to next chance node)
-                                let node_type_new = if current_board.len() == 10 {
-                                    NodeType::TerminalNode(TerminalType::TerminalShowdown)
-                                } else {
-                                    NodeType::ChanceNode((range_manager.get_board_deck(get_card_mask(current_board)).len() - 4).try_into().unwrap())
-                                };
+                                let node_type_new = street_end_node_type(current_board, range_manager, false);
                                 let board_mask = get_card_mask(current_board);
                                 let old_board_mask = if range_manager.initial_board.len() == 6 && current_board.len() == 10 {
                                     let turn_board = &current_board[0..8];
@@ -1006,7 +1167,7 @@ pub fn recursive_build(latest_action: Option<ActionType>, sizing_mapping: &HashM
                                     None
                                 };
                                 let action_line = format!("{}:x",action_line);
-                                let mut node_new = Node { node_type: node_type_new, children: vec![], pot_size: current_node.pot_size, chance_start_stack: current_node.chance_start_stack, oop_invested: 0, ip_invested: 0, chance_start_pot: current_node.chance_start_pot, oop_num_hands: range_manager.get_num_hands(true, board_mask, old_board_mask), ip_num_hands: range_manager.get_num_hands(false, board_mask, old_board_mask) };
+                                let mut node_new = Node { node_type: node_type_new, children: vec![], pot_size: current_node.pot_size, chance_start_stack: current_node.chance_start_stack, oop_invested: 0, ip_invested: 0, chance_start_pot: current_node.chance_start_pot, oop_num_hands: range_manager.get_num_hands(true, board_mask, old_board_mask), ip_num_hands: range_manager.get_num_hands(false, board_mask, old_board_mask), card_split: false };
                                 recursive_build(Some(*action), sizing_mapping, &action_line, &mut node_new, range_manager, current_board);
                                 current_node.children.push(node_new);
                             },
@@ -1035,7 +1196,7 @@ pub fn recursive_build(latest_action: Option<ActionType>, sizing_mapping: &HashM
                                     None
                                 };
                                 
-                                let mut node_new = Node { node_type: NodeType::ActionNode(ActionNodeInfo::new(true, actions_new, range_manager.get_num_hands(true, board_mask, old_board_mask))), children: vec![], pot_size: pot_size_new, chance_start_stack: current_node.chance_start_stack, oop_invested: 0, ip_invested: *sizing, chance_start_pot: current_node.chance_start_pot, oop_num_hands: range_manager.get_num_hands(true, board_mask, old_board_mask), ip_num_hands: range_manager.get_num_hands(false, board_mask, old_board_mask) };
+                                let mut node_new = Node { node_type: NodeType::ActionNode(ActionNodeInfo::new(true, actions_new, range_manager.get_num_hands(true, board_mask, old_board_mask))), children: vec![], pot_size: pot_size_new, chance_start_stack: current_node.chance_start_stack, oop_invested: 0, ip_invested: *sizing, chance_start_pot: current_node.chance_start_pot, oop_num_hands: range_manager.get_num_hands(true, board_mask, old_board_mask), ip_num_hands: range_manager.get_num_hands(false, board_mask, old_board_mask), card_split: false };
                                 recursive_build(Some(*action), sizing_mapping, &action_line, &mut node_new, range_manager, current_board);
                                 current_node.children.push(node_new);
                             }
@@ -1057,16 +1218,12 @@ pub fn recursive_build(latest_action: Option<ActionType>, sizing_mapping: &HashM
                                 } else {
                                     None
                                 };
-                                let mut node_new = Node { node_type: NodeType::TerminalNode(TerminalType::TerminalFold(oop)), children: vec![], pot_size: eff_pot_size, chance_start_stack: current_node.chance_start_stack, oop_invested: 0, ip_invested: 0, chance_start_pot: current_node.chance_start_pot, oop_num_hands: range_manager.get_num_hands(true, board_mask, old_board_mask), ip_num_hands: range_manager.get_num_hands(false, board_mask, old_board_mask) };
+                                let mut node_new = Node { node_type: NodeType::TerminalNode(TerminalType::TerminalFold(oop)), children: vec![], pot_size: eff_pot_size, chance_start_stack: current_node.chance_start_stack, oop_invested: 0, ip_invested: 0, chance_start_pot: current_node.chance_start_pot, oop_num_hands: range_manager.get_num_hands(true, board_mask, old_board_mask), ip_num_hands: range_manager.get_num_hands(false, board_mask, old_board_mask), card_split: false };
                                 recursive_build(Some(*action), sizing_mapping, &action_line, &mut node_new, range_manager, current_board);
                                 current_node.children.push(node_new);
                             },
                             ActionType::Call => {
-                                let node_type_new = if current_board.len() == 10 {
-                                    NodeType::TerminalNode(TerminalType::TerminalShowdown)
-                                } else {
-                                    NodeType::ChanceNode((range_manager.get_board_deck(get_card_mask(current_board)).len() - 4).try_into().unwrap())
-                                };
+                                let node_type_new = street_end_node_type(current_board, range_manager, sizing == current_node.chance_start_stack);
                                 let board_mask = get_card_mask(current_board);
                                 let old_board_mask = if range_manager.initial_board.len() == 6 && current_board.len() == 10 {
                                     let turn_board = &current_board[0..8];
@@ -1076,7 +1233,7 @@ pub fn recursive_build(latest_action: Option<ActionType>, sizing_mapping: &HashM
                                 };
                                 let action_line = format!("{}:c",action_line);
                                 let eff_pot_size = current_node.pot_size + (sizing - min(current_node.oop_invested, current_node.ip_invested));
-                                let mut node_new = Node { node_type: node_type_new, children: vec![], pot_size: eff_pot_size, chance_start_stack: current_node.chance_start_stack, oop_invested: 0, ip_invested: 0, chance_start_pot: current_node.chance_start_pot, oop_num_hands: range_manager.get_num_hands(true, board_mask, old_board_mask), ip_num_hands: range_manager.get_num_hands(false, board_mask, old_board_mask) };
+                                let mut node_new = Node { node_type: node_type_new, children: vec![], pot_size: eff_pot_size, chance_start_stack: current_node.chance_start_stack, oop_invested: 0, ip_invested: 0, chance_start_pot: current_node.chance_start_pot, oop_num_hands: range_manager.get_num_hands(true, board_mask, old_board_mask), ip_num_hands: range_manager.get_num_hands(false, board_mask, old_board_mask), card_split: false };
                                 recursive_build(Some(*action), sizing_mapping, &action_line, &mut node_new, range_manager, current_board);
                                 current_node.children.push(node_new);
                             },
@@ -1125,7 +1282,7 @@ pub fn recursive_build(latest_action: Option<ActionType>, sizing_mapping: &HashM
                                     None
                                 };
                                 
-                                let mut node_new = Node { node_type: NodeType::ActionNode(ActionNodeInfo::new(oop_new, actions_new, range_manager.get_num_hands(oop_new, board_mask, old_board_mask))), children: vec![], pot_size: pot_size_new, chance_start_stack: current_node.chance_start_stack, oop_invested: oop_invested_new, ip_invested: ip_invested_new, chance_start_pot: current_node.chance_start_pot, oop_num_hands: range_manager.get_num_hands(true, board_mask, old_board_mask), ip_num_hands: range_manager.get_num_hands(false, board_mask, old_board_mask) };
+                                let mut node_new = Node { node_type: NodeType::ActionNode(ActionNodeInfo::new(oop_new, actions_new, range_manager.get_num_hands(oop_new, board_mask, old_board_mask))), children: vec![], pot_size: pot_size_new, chance_start_stack: current_node.chance_start_stack, oop_invested: oop_invested_new, ip_invested: ip_invested_new, chance_start_pot: current_node.chance_start_pot, oop_num_hands: range_manager.get_num_hands(true, board_mask, old_board_mask), ip_num_hands: range_manager.get_num_hands(false, board_mask, old_board_mask), card_split: false };
                                 recursive_build(Some(*action), sizing_mapping, &action_line, &mut node_new, range_manager, current_board);
                                 current_node.children.push(node_new);
                             },
@@ -1159,7 +1316,7 @@ pub fn recursive_build(latest_action: Option<ActionType>, sizing_mapping: &HashM
                                     None
                                 };
                                 let action_line = format!("{}:x",action_line);
-                                let mut node_new = Node { node_type: NodeType::ActionNode(ActionNodeInfo::new(false, actions_new, range_manager.get_num_hands(false, board_mask, old_board_mask))), children: vec![], pot_size: current_node.pot_size, chance_start_stack: current_node.chance_start_stack, oop_invested: 0, ip_invested: 0, chance_start_pot: current_node.chance_start_pot, oop_num_hands: range_manager.get_num_hands(true, board_mask, old_board_mask), ip_num_hands: range_manager.get_num_hands(false, board_mask, old_board_mask)};
+                                let mut node_new = Node { node_type: NodeType::ActionNode(ActionNodeInfo::new(false, actions_new, range_manager.get_num_hands(false, board_mask, old_board_mask))), children: vec![], pot_size: current_node.pot_size, chance_start_stack: current_node.chance_start_stack, oop_invested: 0, ip_invested: 0, chance_start_pot: current_node.chance_start_pot, oop_num_hands: range_manager.get_num_hands(true, board_mask, old_board_mask), ip_num_hands: range_manager.get_num_hands(false, board_mask, old_board_mask), card_split: false };
                                 recursive_build(Some(*action), sizing_mapping, &action_line, &mut node_new, range_manager, current_board);
                                 current_node.children.push(node_new);
                             },
@@ -1187,7 +1344,7 @@ pub fn recursive_build(latest_action: Option<ActionType>, sizing_mapping: &HashM
                                     None
                                 };
                                 
-                                let mut node_new = Node { node_type: NodeType::ActionNode(ActionNodeInfo::new(false, actions_new, range_manager.get_num_hands(false, board_mask, old_board_mask))), children: vec![], pot_size: pot_size_new, chance_start_stack: current_node.chance_start_stack, oop_invested: *sizing, ip_invested: 0, chance_start_pot: current_node.chance_start_pot, oop_num_hands: range_manager.get_num_hands(true, board_mask, old_board_mask), ip_num_hands: range_manager.get_num_hands(false, board_mask, old_board_mask) };
+                                let mut node_new = Node { node_type: NodeType::ActionNode(ActionNodeInfo::new(false, actions_new, range_manager.get_num_hands(false, board_mask, old_board_mask))), children: vec![], pot_size: pot_size_new, chance_start_stack: current_node.chance_start_stack, oop_invested: *sizing, ip_invested: 0, chance_start_pot: current_node.chance_start_pot, oop_num_hands: range_manager.get_num_hands(true, board_mask, old_board_mask), ip_num_hands: range_manager.get_num_hands(false, board_mask, old_board_mask), card_split: false };
                                 recursive_build(Some(*action), sizing_mapping, &action_line, &mut node_new, range_manager, current_board);
                                 current_node.children.push(node_new);
                             },
@@ -0,0 +1,239 @@
+use crate::api::*;
+use crate::trainer::*;
+use rand::distributions::{Distribution, WeightedIndex};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use serde::Serialize;
+
+/// The user's decision compared to the solution, EVs are the part of the pot at the node like get_ev
+#[derive(Debug, Clone, Serialize)]
+pub struct PracticeDecision {
+    pub line: String,
+    pub action: String,
+    pub frequency: f64,
+    pub ev: f64,
+    pub best_action: String,
+    pub best_ev: f64,
+    pub ev_loss: f64,
+    pub actions: Vec<String>,
+    pub frequencies: Vec<f64>,
+    pub evs: Vec<f64>,
+}
+
+/// Where the hand stands after the opponent's actions and the dealt cards
+#[derive(Debug, Clone, Serialize)]
+pub struct PracticeState {
+    pub player: String,
+    pub hand: String,
+    pub board: String,
+    pub line: String,
+    /// what happened since the user's last decision, e.g. "IP b50" or "turn Ks"
+    pub events: Vec<String>,
+    /// actions of the user, empty once the hand is over
+    pub actions: Vec<String>,
+    pub finished: bool,
+    /// shown when the hand is over
+    pub villain_hand: Option<String>,
+    pub decisions: usize,
+    pub ev_loss: f64,
+}
+
+/// A hand against the solution: the user plays a combo dealt from a player's range, the opponent's combo
+/// plays the average strategy and the cards are dealt at random
+pub struct PracticeHand {
+    oop: bool,
+    hand: String,
+    villain_hand: String,
+    line: String,
+    board: String,
+    events: Vec<String>,
+    actions: Vec<String>,
+    finished: bool,
+    decisions: usize,
+    ev_loss: f64,
+    rng: StdRng,
+}
+
+fn player_name(oop: bool) -> String {
+    if oop { "OOP".to_string() } else { "IP".to_string() }
+}
+
+fn cards(hand: &str) -> [&str; 2] {
+    [&hand[0..2], &hand[2..4]]
+}
+
+fn blocked(hand: &str, card: &str) -> bool {
+    cards(hand).contains(&card)
+}
+
+impl PracticeHand {
+    /// Deals the hands at the root and plays until the user's first decision, seed makes the hand repeatable
+    pub fn deal(trainer: &Trainer, oop: bool, seed: Option<u64>) -> Result<PracticeHand, String> {
+        let mut rng = match seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        };
+        let hand_order = hand_order();
+        let range = get_range(trainer, oop, "r:0")?;
+        let hand = WeightedIndex::new(&range).map(|x| hand_order[x.sample(&mut rng)].clone())
+            .map_err(|_| format!("{}'s range is empty", player_name(oop)))?;
+
+        let villain_range: Vec<f64> = get_range(trainer, !oop, "r:0")?.iter().zip(hand_order.iter())
+            .map(|(weight, villain_hand)| if cards(villain_hand).iter().any(|card| blocked(&hand, card)) { 0.0 } else { *weight })
+            .collect();
+        let villain_hand = WeightedIndex::new(&villain_range).map(|x| hand_order[x.sample(&mut rng)].clone())
+            .map_err(|_| format!("{}'s range is empty after dealing {}", player_name(!oop), hand))?;
+
+        let board = trainer.range_manager.initial_board.clone();
+        let mut practice_hand = PracticeHand { oop, hand, villain_hand, line: "r:0".to_string(), board, events: vec![], actions: vec![], finished: false, decisions: 0, ev_loss: 0.0, rng };
+        practice_hand.advance(trainer)?;
+        Ok(practice_hand)
+    }
+
+    // plays the opponent and deals cards until it's the user's turn or the hand is over
+    fn advance(&mut self, trainer: &Trainer) -> Result<(), String> {
+        let hand_order_mapping = hand_order_mapping();
+        loop {
+            let node = trainer.root.get_node(self.line.clone(), &trainer.range_manager);
+            self.board = node.board.clone();
+            if node.node_type == "END_NODE" || node.children_count == 0 {
+                self.finished = true;
+                self.actions = vec![];
+                return Ok(());
+            }
+
+            let children = trainer.root.get_children(self.line.clone(), &trainer.range_manager);
+            if node.node_type == "SPLIT_NODE" {
+                let cards: Vec<String> = children.iter().map(|child| child.line.rsplit(':').next().unwrap().to_string())
+                    .filter(|card| !blocked(&self.hand, card) && !blocked(&self.villain_hand, card))
+                    .collect();
+                if cards.is_empty() {
+                    return Err(format!("no card left to deal at {}", self.line));
+                }
+                let card = &cards[WeightedIndex::new(vec![1.0; cards.len()]).unwrap().sample(&mut self.rng)];
+                let street = if self.board.len() == 6 { "turn" } else { "river" };
+                self.events.push(format!("{} {}", street, card));
+                self.line = format!("{}:{}", self.line, card);
+                continue;
+            }
+
+            let strategy = get_strategy(trainer, &self.line)?;
+            if strategy.oop == self.oop {
+                self.actions = strategy.actions;
+                return Ok(());
+            }
+            let villain_index = hand_order_mapping[&self.villain_hand];
+            let frequencies: Vec<f64> = strategy.frequencies.iter().map(|frequencies| frequencies[villain_index]).collect();
+            let action = &strategy.actions[WeightedIndex::new(&frequencies).map_err(|e| e.to_string())?.sample(&mut self.rng)];
+            self.events.push(format!("{} {}", player_name(strategy.oop), action));
+            self.line = format!("{}:{}", self.line, action);
+        }
+    }
+
+    /// Takes the user's action, compares it to the solution and plays on until the next decision
+    pub fn play(&mut self, trainer: &Trainer, action: &str) -> Result<PracticeDecision, String> {
+        if self.finished {
+            return Err("the hand is over".to_string());
+        }
+        let strategy = get_strategy(trainer, &self.line)?;
+        let j = strategy.actions.iter().position(|x| x == action)
+            .ok_or_else(|| format!("invalid action {}, expected one of {}", action, strategy.actions.join(" ")))?;
+        let hand_index = hand_order_mapping()[&self.hand];
+        let frequencies: Vec<f64> = strategy.frequencies.iter().map(|frequencies| frequencies[hand_index]).collect();
+        let evs: Vec<f64> = get_action_evs(trainer, &self.line)?.iter().map(|values| values.values[hand_index]).collect();
+        let best = (0..evs.len()).max_by(|&a, &b| evs[a].partial_cmp(&evs[b]).unwrap()).unwrap();
+
+        let decision = PracticeDecision {
+            line: self.line.clone(),
+            action: action.to_string(),
+            frequency: frequencies[j],
+            ev: evs[j],
+            best_action: strategy.actions[best].clone(),
+            best_ev: evs[best],
+            ev_loss: evs[best] - evs[j],
+            actions: strategy.actions,
+            frequencies,
+            evs,
+        };
+        self.decisions += 1;
+        self.ev_loss += decision.ev_loss;
+        self.events.clear();
+        self.line = format!("{}:{}", self.line, action);
+        self.advance(trainer)?;
+        Ok(decision)
+    }
+
+    pub fn state(&self) -> PracticeState {
+        PracticeState {
+            player: player_name(self.oop),
+            hand: self.hand.clone(),
+            board: self.board.clone(),
+            line: self.line.clone(),
+            events: self.events.clone(),
+            actions: self.actions.clone(),
+            finished: self.finished,
+            villain_hand: if self.finished { Some(self.villain_hand.clone()) } else { None },
+            decisions: self.decisions,
+            ev_loss: self.ev_loss,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_practice_hand() {
+        let mut trainer = test_config("2c7d9hTs").build().unwrap();
+        trainer.solve(&Accuracy::Chips(0.0), TrainFinish::Iterations(10), None, &mut |_| ());
+        let hand_order_mapping = hand_order_mapping();
+        let mut sampled = vec![];
+        let mut rivers = vec![];
+
+        for seed in 0..20 {
+            // IP, the opponent acts first
+            let mut practice_hand = PracticeHand::deal(&trainer, false, Some(seed)).unwrap();
+            let state = practice_hand.state();
+            assert_eq!(format!("{:?}", state), format!("{:?}", PracticeHand::deal(&trainer, false, Some(seed)).unwrap().state()));
+            assert!(get_range(&trainer, false, "r:0").unwrap()[hand_order_mapping[&state.hand]] > 0.0);
+            assert!(get_range(&trainer, true, "r:0").unwrap()[hand_order_mapping[&practice_hand.villain_hand]] > 0.0);
+            assert!(!cards(&state.hand).iter().any(|card| blocked(&practice_hand.villain_hand, card)));
+
+            // the opponent only takes actions its hand plays
+            let villain_action = state.events[0].strip_prefix("OOP ").unwrap();
+            let strategy = get_strategy(&trainer, "r:0").unwrap();
+            let j = strategy.actions.iter().position(|x| x == villain_action).unwrap();
+            assert!(strategy.frequencies[j][hand_order_mapping[&practice_hand.villain_hand]] > 0.0);
+            sampled.push(villain_action.to_string());
+
+            while !practice_hand.state().finished {
+                let line = practice_hand.state().line;
+                let action = practice_hand.state().actions[seed as usize % practice_hand.state().actions.len()].clone();
+                let decision = practice_hand.play(&trainer, &action).unwrap();
+                let i = hand_order_mapping[&state.hand];
+                let strategy = get_strategy(&trainer, &line).unwrap();
+                let evs = get_action_evs(&trainer, &line).unwrap();
+                let j = strategy.actions.iter().position(|x| *x == action).unwrap();
+                assert_eq!(decision.frequency, strategy.frequencies[j][i]);
+                assert_eq!(decision.ev, evs[j].values[i]);
+                assert!(decision.ev_loss >= 0.0 && (decision.best_ev - decision.ev - decision.ev_loss).abs() < 1e-12);
+                assert!(evs.iter().all(|values| values.values[i] <= decision.best_ev));
+
+                for event in practice_hand.state().events.iter().filter_map(|event| event.strip_prefix("river ")) {
+                    assert!(!blocked(&state.hand, event) && !blocked(&practice_hand.villain_hand, event) && !state.board.contains(event));
+                    rivers.push(event.to_string());
+                }
+            }
+            let state = practice_hand.state();
+            assert!(state.villain_hand.is_some() && state.actions.is_empty());
+            assert!(practice_hand.play(&trainer, "c").is_err());
+        }
+        // both opponent actions and several rivers came up
+        sampled.sort();
+        sampled.dedup();
+        rivers.sort();
+        rivers.dedup();
+        assert!(sampled.len() > 1 && rivers.len() > 1);
+    }
+}
@@ -3,6 +3,7 @@ use std::collections::HashMap;
 
 use crate::hand_range::*;
 use crate::isomorphism::*;
+use crate::equity::*;
 
 use rust_poker::hand_evaluator::{Hand};
 use rust_poker::constants::RANK_TO_CHAR;
@@ -20,6 +21,14 @@ pub struct RangeManager {
     pub board_deck: HashMap<u64, Vec<u8>>,
     oop_reach_mapping: HashMap<(u64, Option<u64>), Vec<u16>>,
     ip_reach_mapping: HashMap<(u64, Option<u64>), Vec<u16>>,
+    // depth limited solving: board length where the tree stops and equity realization factors of OOP and IP
+    pub depth_limit: Option<(usize, f64, f64)>,
+    // OOP major payoff tables (2*share-1, 0 for blocked pairs) of the leaves, per board
+    leaf_payoffs: HashMap<(u64, Option<u64>), Vec<f64>>,
+    // runouts of boards where both players can be all in before the river
+    allin_runouts: HashMap<(u64, Option<u64>), (Vec<RunoutRanks>, f64)>,
+    // showdown tables of OOP and IP for every river
+    showdown_tables: HashMap<(u64, Option<u64>), (ShowdownTable, ShowdownTable)>,
 }
 
 fn board_to_u8(board: &String) -> Vec<u8> {
@@ -49,6 +58,76 @@ fn u8_to_board(u8_board: Vec<u8>) -> String {
     board
 }
 
+// Lookup positions of one hand, the card positions are at win_end, lose_start and the end of the
+// card's block in the card prefix sums, for both hole cards
+#[derive(Debug, Clone, Copy)]
+pub struct ShowdownHand {
+    pub win_end: u16,
+    pub lose_start: u16,
+    pub win_cards: [u16; 2],
+    pub lose_cards: [u16; 2],
+    pub card_ends: [u16; 2],
+}
+
+// Per river lookup of one player against the rank sorted villain range. Villain reach is turned into
+// prefix sums, overall and per card, so a showdown payoff is a few lookups per hand instead of a sweep.
+// Every card has its own block in the card prefix sums starting with a zero. Payoffs are differences of
+// prefix sums, equal to a sweep up to rounding but not bit identical, so solves drift slightly from a sweep
+#[derive(Debug)]
+pub struct ShowdownTable {
+    pub hands: Vec<ShowdownHand>,
+    // where the reach of every villain combo goes in the card prefix sums, one entry per hole card
+    pub villain_cards: Vec<[u16; 2]>,
+}
+
+impl ShowdownTable {
+    pub fn new(hero_range: &[Combo], villain_range: &[Combo]) -> ShowdownTable {
+        let mut card_counts = [0u16; 52];
+        for combo in villain_range {
+            card_counts[combo.0 as usize] += 1;
+            card_counts[combo.1 as usize] += 1;
+        }
+        let mut card_offsets = [0u16; 52];
+        for card in 1..52 {
+            card_offsets[card] = card_offsets[card-1] + card_counts[card-1] + 1;
+        }
+        
+        // number of villain combos holding a card before every villain index
+        let mut card_positions = vec![[0u16; 52]; villain_range.len() + 1];
+        let mut villain_cards = Vec::with_capacity(villain_range.len());
+        for (j, combo) in villain_range.iter().enumerate() {
+            let cards = [combo.0 as usize, combo.1 as usize];
+            card_positions[j+1] = card_positions[j];
+            card_positions[j+1][cards[0]] += 1;
+            card_positions[j+1][cards[1]] += 1;
+            villain_cards.push([card_offsets[cards[0]] + card_positions[j+1][cards[0]], card_offsets[cards[1]] + card_positions[j+1][cards[1]]]);
+        }
+        
+        let mut hands = Vec::with_capacity(hero_range.len());
+        let mut win_end = 0;
+        let mut lose_start = 0;
+        for combo in hero_range {
+            while win_end < villain_range.len() && villain_range[win_end].3 < combo.3 {
+                win_end += 1;
+            }
+            while lose_start < villain_range.len() && villain_range[lose_start].3 <= combo.3 {
+                lose_start += 1;
+            }
+            let cards = [combo.0 as usize, combo.1 as usize];
+            let slots = |positions: &[u16; 52]| [card_offsets[cards[0]] + positions[cards[0]], card_offsets[cards[1]] + positions[cards[1]]];
+            hands.push(ShowdownHand {
+                win_end: win_end as u16,
+                lose_start: lose_start as u16,
+                win_cards: slots(&card_positions[win_end]),
+                lose_cards: slots(&card_positions[lose_start]),
+                card_ends: slots(&card_counts),
+            });
+        }
+        
+        ShowdownTable { hands, villain_cards }
+    }
+}
+
 impl RangeManager {
     pub fn initialize_ranges(&mut self) {
         let board_mask = get_card_mask(&self.initial_board);
@@ -189,7 +268,71 @@ impl RangeManager {
         let oop_joint_combos = vec![];
         let ip_joint_combos = vec![];
         
-        RangeManager { oop_board_range, ip_board_range, initial_board, oop_joint_combos, ip_joint_combos, board_deck, oop_reach_mapping, ip_reach_mapping }
+        RangeManager { oop_board_range, ip_board_range, initial_board, oop_joint_combos, ip_joint_combos, board_deck, oop_reach_mapping, ip_reach_mapping, depth_limit: None, leaf_payoffs: HashMap::new(), allin_runouts: HashMap::new(), showdown_tables: HashMap::new() }
+    }
+    
+    // equity tables for every board the depth limited tree ends on, needs initialized ranges
+    pub fn initialize_leaf_payoffs(&mut self) {
+        let (limit, oop_realization, ip_realization) = match self.depth_limit {
+            Some(x) => x,
+            None => return,
+        };
+        
+        let keys: Vec<(u64, Option<u64>)> = self.oop_board_range.keys().filter(|k| k.0.count_ones() as usize * 2 == limit).cloned().collect();
+        for key in keys {
+            let oop_hands = &self.oop_board_range.get(&key).unwrap().hands;
+            let ip_hands = &self.ip_board_range.get(&key).unwrap().hands;
+            let payoffs = calc_equity_matrix(oop_hands, ip_hands, key.0).iter().map(|equity| {
+                match equity {
+                    Some(equity) => {
+                        let oop_share = equity * oop_realization;
+                        let ip_share = (1.0 - equity) * ip_realization;
+                        if oop_share + ip_share > 0.0 {
+                            2.0 * oop_share / (oop_share + ip_share) - 1.0
+                        } else {
+                            0.0
+                        }
+                    },
+                    None => 0.0,
+                }
+            }).collect();
+            self.leaf_payoffs.insert(key, payoffs);
+        }
+    }
+    
+    pub fn get_leaf_payoffs(&self, board: u64, previous_board: Option<u64>) -> &Vec<f64> {
+        self.leaf_payoffs.get(&(board, previous_board)).unwrap()
+    }
+    
+    pub fn initialize_allin_runouts(&mut self, boards: &[(u64, Option<u64>)]) {
+        for key in boards {
+            if !self.allin_runouts.contains_key(key) {
+                let runouts = calc_runout_ranks(&self.oop_board_range.get(key).unwrap().hands, &self.ip_board_range.get(key).unwrap().hands, key.0);
+                self.allin_runouts.insert(*key, runouts);
+            }
+        }
+    }
+    
+    pub fn get_allin_runouts(&self, board: u64, previous_board: Option<u64>) -> &(Vec<RunoutRanks>, f64) {
+        self.allin_runouts.get(&(board, previous_board)).unwrap()
+    }
+    
+    pub fn get_showdown_table(&self, oop: bool, board: u64, previous_board: Option<u64>) -> &ShowdownTable {
+        let tables = self.showdown_tables.get(&(board, previous_board)).unwrap();
+        match oop {
+            true => &tables.0,
+            false => &tables.1,
+        }
+    }
+    
+    // key of a board in the range maps, rivers of a flop tree also carry their turn
+    pub fn get_board_masks(&self, board: &String) -> (u64, Option<u64>) {
+        let previous_board = if self.initial_board.len() == 6 && board.len() == 10 {
+            Some(get_card_mask(&board[0..8].to_string()))
+        } else {
+            None
+        };
+        (get_card_mask(board), previous_board)
     }
     
     pub fn get_board_deck(&self, board: u64) -> &Vec<u8> {
@@ -380,6 +523,15 @@ impl RangeManager {
             
             self.ip_reach_mapping.insert(*key, new_reach_probs);
         }
+        
+        for (key, oop_range) in self.oop_board_range.iter() {
+            if Hand::from_bit_mask(key.0).count() != 5 {
+                continue;
+            }
+            let ip_range = self.ip_board_range.get(key).unwrap();
+            let tables = (ShowdownTable::new(&oop_range.hands, &ip_range.hands), ShowdownTable::new(&ip_range.hands, &oop_range.hands));
+            self.showdown_tables.insert(*key, tables);
+        }
     }
     
     pub fn get_range(&self, oop: bool, board: u64, previous_board: Option<u64>) -> &HandRange {
@@ -0,0 +1,308 @@
+use crate::api::*;
+use crate::export::*;
+use crate::trainer::*;
+use std::collections::HashMap;
+use std::fmt::Write as FmtWrite;
+use std::fs;
+use rust_poker::hand_range::get_card_mask;
+
+const RANKS: [char; 13] = ['A', 'K', 'Q', 'J', 'T', '9', '8', '7', '6', '5', '4', '3', '2'];
+const SUITS: [char; 4] = ['c', 'd', 'h', 's'];
+
+// per hand class (AA, AKs, AKo, ...) values weighted by the reach of the combos
+#[derive(Default)]
+struct ClassSummary {
+    combos: usize,
+    reach: f64,
+    ev: f64,
+    equity: f64,
+    frequencies: Vec<f64>,
+}
+
+fn rank_index(rank: char) -> usize {
+    RANKS.iter().position(|&x| x == rank).unwrap()
+}
+
+/// Row and column in the 13x13 grid, pairs on the diagonal, suited hands above and offsuit hands below it
+fn grid_position(hand: &str) -> (usize, usize) {
+    let chars: Vec<char> = hand.chars().collect();
+    let (high, low) = {
+        let (a, b) = (rank_index(chars[0]), rank_index(chars[2]));
+        (a.min(b), a.max(b))
+    };
+    if chars[1] == chars[3] {
+        (high, low)
+    } else {
+        (low, high)
+    }
+}
+
+fn class_name(row: usize, col: usize) -> String {
+    if row == col {
+        format!("{}{}", RANKS[row], RANKS[col])
+    } else if row < col {
+        format!("{}{}s", RANKS[row], RANKS[col])
+    } else {
+        format!("{}{}o", RANKS[col], RANKS[row])
+    }
+}
+
+// combos of the class that don't share a card with the board, 6, 4 and 12 on an unrelated board
+fn available_combos(row: usize, col: usize, board: &str) -> usize {
+    let board_mask = get_card_mask(board);
+    let (high, low) = (RANKS[row.min(col)], RANKS[row.max(col)]);
+    let mut available = 0;
+    for (i, &suit) in SUITS.iter().enumerate() {
+        for (j, &other_suit) in SUITS.iter().enumerate() {
+            let in_class = if row == col { i < j } else if row < col { i == j } else { i != j };
+            if in_class && get_card_mask(&format!("{}{}{}{}", high, suit, low, other_suit)) & board_mask == 0 {
+                available += 1;
+            }
+        }
+    }
+    available
+}
+
+// check and call green, fold blue, bets from orange to dark red with the sizing
+fn action_colors(actions: &[String]) -> Vec<(f64, f64, f64)> {
+    let bets = actions.iter().filter(|x| x.starts_with('b')).count();
+    let mut bet_index = 0;
+    actions.iter().map(|action| {
+        match action.chars().next() {
+            Some('f') => (59.0, 125.0, 216.0),
+            Some('b') => {
+                let t = if bets > 1 { bet_index as f64 / (bets - 1) as f64 } else { 0.5 };
+                bet_index += 1;
+                (240.0 - 90.0 * t, 140.0 - 120.0 * t, 60.0 - 40.0 * t)
+            },
+            _ => (90.0, 180.0, 90.0),
+        }
+    }).collect()
+}
+
+fn css_color(color: (f64, f64, f64)) -> String {
+    format!("rgb({},{},{})", color.0.round(), color.1.round(), color.2.round())
+}
+
+fn blend(colors: &[(f64, f64, f64)], frequencies: &[f64]) -> (f64, f64, f64) {
+    let mut blended = (0.0, 0.0, 0.0);
+    for (color, frequency) in colors.iter().zip(frequencies.iter()) {
+        blended.0 += color.0 * frequency;
+        blended.1 += color.1 * frequency;
+        blended.2 += color.2 * frequency;
+    }
+    blended
+}
+
+fn summarize(node: &ExportedNode, equity: &HashMap<String, f64>) -> Vec<Vec<ClassSummary>> {
+    let mut grid: Vec<Vec<ClassSummary>> = (0..13).map(|_| (0..13).map(|_| ClassSummary::default()).collect()).collect();
+    for (i, hand) in node.hands.iter().enumerate() {
+        let (row, col) = grid_position(hand);
+        let summary = &mut grid[row][col];
+        let reach = node.reach[i];
+        summary.combos += 1;
+        summary.reach += reach;
+        summary.ev += node.ev[i] * reach;
+        summary.equity += equity.get(hand).unwrap_or(&0.0) * reach;
+        summary.frequencies.resize(node.actions.len(), 0.0);
+        for (j, frequencies) in node.strategy.iter().enumerate() {
+            summary.frequencies[j] += frequencies[i] * reach;
+        }
+    }
+    for summary in grid.iter_mut().flatten() {
+        if summary.reach > 0.0 {
+            summary.ev /= summary.reach;
+            summary.equity /= summary.reach;
+            for frequency in summary.frequencies.iter_mut() {
+                *frequency /= summary.reach;
+            }
+        }
+    }
+    grid
+}
+
+fn write_node(html: &mut String, trainer: &Trainer, node: &ExportedNode, reported: &HashMap<String, usize>) -> Result<(), String> {
+    let oop = node.player == "OOP";
+    let hand_order = hand_order();
+    let equity_values = get_equity(trainer, oop, &node.line)?;
+    let equity: HashMap<String, f64> = hand_order.iter().cloned().zip(equity_values.values.iter().cloned()).collect();
+    let grid = summarize(node, &equity);
+    let colors = action_colors(&node.actions);
+    let node_info = trainer.root.get_node(node.line.clone(), &trainer.range_manager);
+
+    let total_reach: f64 = node.reach.iter().sum();
+    let range_frequencies: Vec<f64> = node.strategy.iter().map(|frequencies| {
+        if total_reach > 0.0 {
+            frequencies.iter().zip(node.reach.iter()).map(|(f, r)| f * r).sum::<f64>() / total_reach
+        } else {
+            0.0
+        }
+    }).collect();
+
+    let _ = writeln!(html, "<section id=\"{}\">", node.line);
+    let _ = writeln!(html, "<h2>{} &middot; {} to act</h2>", node.line, node.player);
+    let _ = writeln!(html, "<p>Board {} &middot; pot {} &middot; invested OOP {} IP {}</p>", node.board, node_info.pot.2, node_info.pot.0, node_info.pot.1);
+
+    // navigation
+    let _ = write!(html, "<p class=\"nav\">");
+    if let Some(parent) = node.line.rfind(':').map(|i| &node.line[..i]) {
+        if reported.contains_key(parent) {
+            let _ = write!(html, "up: <a href=\"#{0}\">{0}</a> &middot; ", parent);
+        }
+    }
+    let _ = write!(html, "children:");
+    for child in trainer.root.get_children(node.line.clone(), &trainer.range_manager) {
+        if reported.contains_key(&child.line) {
+            let _ = write!(html, " <a href=\"#{0}\">{0}</a>", child.line);
+        } else {
+            let _ = write!(html, " {}", child.line);
+        }
+    }
+    let _ = writeln!(html, "</p>");
+
+    // legend with the frequencies of the whole range
+    let _ = write!(html, "<p>");
+    for (j, action) in node.actions.iter().enumerate() {
+        let _ = write!(html, "<span class=\"legend\" style=\"background:{}\">{} {:.1}%</span>", css_color(colors[j]), action, range_frequencies[j] * 100.0);
+    }
+    let _ = writeln!(html, "</p>");
+
+    let _ = writeln!(html, "<div class=\"grids\">");
+    let _ = writeln!(html, "<table class=\"grid\"><caption>Strategy</caption>");
+    for (row, grid_row) in grid.iter().enumerate() {
+        let _ = write!(html, "<tr>");
+        for (col, summary) in grid_row.iter().enumerate() {
+            let name = class_name(row, col);
+            if summary.reach > 0.0 {
+                let tooltip: Vec<String> = node.actions.iter().zip(summary.frequencies.iter()).map(|(a, f)| format!("{} {:.1}%", a, f * 100.0)).collect();
+                let _ = write!(html, "<td style=\"background:{}\" title=\"{}\">{}</td>", css_color(blend(&colors, &summary.frequencies)), tooltip.join(", "), name);
+            } else {
+                let _ = write!(html, "<td class=\"empty\">{}</td>", name);
+            }
+        }
+        let _ = writeln!(html, "</tr>");
+    }
+    let _ = writeln!(html, "</table>");
+
+    let _ = writeln!(html, "<table class=\"grid\"><caption>Range</caption>");
+    for (row, grid_row) in grid.iter().enumerate() {
+        let _ = write!(html, "<tr>");
+        for (col, summary) in grid_row.iter().enumerate() {
+            // average weight over the combos of the class the board doesn't block
+            let available = available_combos(row, col, &node.board);
+            if available == 0 {
+                let _ = write!(html, "<td class=\"empty\">{}</td>", class_name(row, col));
+                continue;
+            }
+            let weight = summary.reach / available as f64;
+            let shade = 255.0 - 150.0 * weight.min(1.0);
+            let _ = write!(html, "<td style=\"background:rgb(255,{},{})\" title=\"{:.1}%\">{}</td>", (shade + 30.0).min(255.0).round(), shade.round(), weight * 100.0, class_name(row, col));
+        }
+        let _ = writeln!(html, "</tr>");
+    }
+    let _ = writeln!(html, "</table>");
+    let _ = writeln!(html, "</div>");
+
+    // per class values
+    let _ = write!(html, "<table class=\"values\"><tr><th>hand</th><th>combos</th><th>weight</th><th>EV</th><th>equity</th>");
+    for action in &node.actions {
+        let _ = write!(html, "<th>{}</th>", action);
+    }
+    let _ = writeln!(html, "</tr>");
+    for (row, grid_row) in grid.iter().enumerate() {
+        for (col, summary) in grid_row.iter().enumerate() {
+            if summary.reach <= 0.0 {
+                continue;
+            }
+            let _ = write!(html, "<tr><td>{}</td><td>{}</td><td>{:.3}</td><td>{:.2}</td><td>{:.1}%</td>", class_name(row, col), summary.combos, summary.reach, summary.ev, summary.equity * 100.0);
+            for frequency in &summary.frequencies {
+                let _ = write!(html, "<td>{:.1}%</td>", frequency * 100.0);
+            }
+            let _ = writeln!(html, "</tr>");
+        }
+    }
+    let _ = writeln!(html, "</table>");
+    let _ = writeln!(html, "</section>");
+    Ok(())
+}
+
+/// Writes a self-contained HTML page with every decision node at most depth actions and dealt cards below the line
+pub fn write_report(trainer: &Trainer, line: &str, path: &str, depth: Option<usize>) -> Result<usize, String> {
+    let nodes = get_subtree_strategy(trainer, line, depth)?;
+    let reported: HashMap<String, usize> = nodes.iter().enumerate().map(|(i, node)| (node.line.clone(), i)).collect();
+
+    let mut html = String::new();
+    html.push_str("<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\"><title>OpenSolver report</title>\n<style>\n");
+    html.push_str("body { font-family: sans-serif; margin: 20px; }\n");
+    html.push_str("section { border-top: 1px solid #ccc; padding-top: 10px; margin-top: 20px; }\n");
+    html.push_str(".grids { display: flex; gap: 30px; flex-wrap: wrap; }\n");
+    html.push_str(".grid { border-collapse: collapse; }\n");
+    html.push_str(".grid td { width: 38px; height: 28px; text-align: center; font-size: 11px; border: 1px solid #fff; }\n");
+    html.push_str(".grid td.empty { background: #eee; color: #aaa; }\n");
+    html.push_str(".legend { display: inline-block; padding: 3px 8px; margin-right: 6px; color: #fff; }\n");
+    html.push_str(".values { border-collapse: collapse; margin-top: 15px; font-size: 12px; }\n");
+    html.push_str(".values td, .values th { padding: 2px 8px; text-align: right; border-bottom: 1px solid #eee; }\n");
+    html.push_str("</style></head><body>\n");
+    let _ = writeln!(html, "<h1>OpenSolver report: {}</h1>", line);
+    let _ = writeln!(html, "<p>Iterations {}, frequencies of hand classes are weighted by the reach of their combos.</p>", trainer.iteration);
+
+    let _ = write!(html, "<p class=\"nav\">nodes:");
+    for node in &nodes {
+        let _ = write!(html, " <a href=\"#{0}\">{0}</a>", node.line);
+    }
+    let _ = writeln!(html, "</p>");
+
+    for node in &nodes {
+        write_node(&mut html, trainer, node, &reported)?;
+    }
+    html.push_str("</body></html>\n");
+
+    fs::write(path, html).map_err(|e| format!("couldn't write {}: {}", path, e))?;
+    Ok(nodes.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_grid_position() {
+        assert_eq!(grid_position("AsAh"), (0, 0));
+        assert_eq!(grid_position("AsKs"), (0, 1));
+        assert_eq!(grid_position("KhAs"), (1, 0));
+        assert_eq!(grid_position("3c2c"), (11, 12));
+        assert_eq!(class_name(0, 1), "AKs");
+        assert_eq!(class_name(1, 0), "AKo");
+        assert_eq!(class_name(12, 12), "22");
+    }
+
+    #[test]
+    fn test_write_report() {
+        let path = std::env::temp_dir().join(format!("opensolver_report_{}.html", std::process::id()));
+        let path = path.to_str().unwrap();
+
+        let mut trainer = test_config("2c7d9hTsAs").build().unwrap();
+        trainer.solve(&Accuracy::Chips(0.0), TrainFinish::Iterations(10), None, &mut |_| ());
+        assert!(write_report(&trainer, "r:0", path, Some(1)).unwrap() > 1);
+        let html = fs::read_to_string(path).unwrap();
+        let _ = fs::remove_file(path);
+
+        // AA and AK keep their full weight with an ace on the board, 3 and 9 of their combos are left
+        assert!(html.contains("title=\"100.0%\">AA<"));
+        assert!(html.contains("title=\"100.0%\">AKs<"));
+        assert!(html.contains("title=\"100.0%\">AKo<"));
+        assert_eq!(available_combos(0, 0, "2c7d9hTsAs"), 3);
+        assert_eq!(available_combos(1, 0, "2c7d9hTsAs"), 9);
+        assert_eq!(available_combos(1, 0, "2c7d9hTs"), 12);
+
+        // strategy cells average the frequencies of the combos by their reach, on the root the range weights
+        let strategy = get_strategy(&trainer, "r:0").unwrap();
+        let kings: Vec<usize> = hand_order().iter().enumerate().filter(|(_, hand)| hand.starts_with('K') && hand.chars().nth(2) == Some('K')).map(|(i, _)| i).collect();
+        assert_eq!(kings.len(), 6);
+        let tooltip: Vec<String> = strategy.actions.iter().enumerate().map(|(j, action)| {
+            let frequency = kings.iter().map(|&i| strategy.frequencies[j][i]).sum::<f64>() / 6.0;
+            format!("{} {:.1}%", action, frequency * 100.0)
+        }).collect();
+        assert!(html.contains(&format!("title=\"{}\">KK<", tooltip.join(", "))));
+    }
+}
@@ -0,0 +1,209 @@
+use crate::postfloptree::*;
+use crate::range::*;
+use crate::hand_range::*;
+use crate::trainer::*;
+use crate::best_response::*;
+use crate::api::check_line;
+use std::collections::HashMap;
+use rust_poker::hand_range::{get_card_mask};
+
+// Re-solving gadget: for every combo the constrained player chooses between entering the subgame
+// and taking the counterfactual value it had in the parent solve
+#[derive(Debug)]
+pub struct ResolveGadget {
+    pub oop: bool,
+    pub alt_values: Vec<f64>,
+    regret_sum: Vec<f64>,
+    follow_sum: Vec<f64>,
+    weight_sum: f64,
+}
+
+impl ResolveGadget {
+    pub fn new(oop: bool, alt_values: Vec<f64>) -> ResolveGadget {
+        let hands_num = alt_values.len();
+        ResolveGadget { oop, alt_values, regret_sum: vec![0.0; 2*hands_num], follow_sum: vec![0.0; hands_num], weight_sum: 0.0 }
+    }
+
+    // regret matching over follow/terminate, a combo without regrets enters the subgame
+    pub fn get_follow_probs(&self) -> Vec<f64> {
+        self.regret_sum.chunks(2).map(|r| {
+            if r[0] + r[1] > 0.0 {
+                r[0] / (r[0] + r[1])
+            } else {
+                1.0
+            }
+        }).collect()
+    }
+
+    pub fn get_average_follow_probs(&self) -> Vec<f64> {
+        if self.weight_sum > 0.0 {
+            self.follow_sum.iter().map(|x| x / self.weight_sum).collect()
+        } else {
+            self.get_follow_probs()
+        }
+    }
+
+    // CFR+ update with linear averaging, follow_values are the subgame root values of the constrained player
+    pub fn update(&mut self, follow_values: &[f64], n_iterations: u64) {
+        let follow_probs = self.get_follow_probs();
+        let weight = (n_iterations + 1) as f64;
+        for (i, follow_prob) in follow_probs.iter().enumerate() {
+            let value = follow_prob * follow_values[i] + (1.0 - follow_prob) * self.alt_values[i];
+            self.regret_sum[2*i] = (self.regret_sum[2*i] + follow_values[i] - value).max(0.0);
+            self.regret_sum[2*i+1] = (self.regret_sum[2*i+1] + self.alt_values[i] - value).max(0.0);
+            self.follow_sum[i] += weight * follow_prob;
+        }
+        self.weight_sum += weight;
+    }
+}
+
+#[derive(Debug)]
+pub struct Subgame {
+    pub board: String,
+    pub pot: u32,
+    pub eff_stack: u32,
+    pub oop_range: HandRange,
+    pub ip_range: HandRange,
+    // parent counterfactual values of both players at the subgame root, keyed by combo
+    oop_values: HashMap<(u8, u8), f64>,
+    ip_values: HashMap<(u8, u8), f64>,
+}
+
+pub fn hand_order_index(combo: &Combo, hand_order_mapping: &HashMap<String, usize>) -> usize {
+    let hand = combo.to_string();
+    match hand_order_mapping.get(&hand) {
+        Some(x) => *x,
+        None => *hand_order_mapping.get(&format!("{}{}", &hand[2..], &hand[0..2])).unwrap(),
+    }
+}
+
+// the solver only supports whole percent combo weights, so the reaching range is scaled up to
+// a maximum of 100 which leaves the strategy unchanged. Combos below half a percent of that are dropped
+fn reach_to_range(reach: &[f64], hand_order: &[String]) -> (HandRange, f64) {
+    let max_reach = reach.iter().cloned().fold(0.0, f64::max);
+    let mut hand_range_string = String::new();
+    if max_reach > 0.0 {
+        for (i, weight) in reach.iter().enumerate() {
+            let new_weight = (weight / max_reach * 100.0).round() as u8;
+            if new_weight > 0 {
+                hand_range_string = format!("{}{}@{},", hand_range_string, hand_order[i], new_weight);
+            }
+        }
+    }
+    hand_range_string.pop();
+    (HandRange::from_string(hand_range_string), max_reach)
+}
+
+// Ranges, pot and stacks at the start of a turn or river of a solved tree, line has to end with the dealt card
+pub fn get_subgame(trainer: &Trainer, line: &String, hand_order: &[String], hand_order_mapping: &HashMap<String, usize>) -> Result<Subgame, String> {
+    check_line(trainer, line)?;
+    let range_manager = &trainer.range_manager;
+    let last_action = line.rsplit(':').next().unwrap();
+    if last_action.len() != 2 || last_action.starts_with('b') || line == "r" {
+        return Err("line has to end with a turn or river card".to_string());
+    }
+
+    let (board, node, _, _, _, _) = trainer.root.find_node(line, range_manager);
+    let node_oop = match &node.node_type {
+        NodeType::ActionNode(node_info) => node_info.oop,
+        _ => return Err("couldn't find card in tree".to_string()),
+    };
+    if !node_oop || board.len() == range_manager.initial_board.len() {
+        return Err("line has to end with a turn or river card".to_string());
+    }
+
+    let (board_mask, old_board_mask) = range_manager.get_board_masks(&board);
+
+    let oop_reach = trainer.root.get_range(true, line.clone(), range_manager, hand_order_mapping);
+    let ip_reach = trainer.root.get_range(false, line.clone(), range_manager, hand_order_mapping);
+    let (oop_range, oop_scale) = reach_to_range(&oop_reach, hand_order);
+    let (ip_range, ip_scale) = reach_to_range(&ip_reach, hand_order);
+    if oop_scale == 0.0 || ip_scale == 0.0 {
+        return Err("line is never reached".to_string());
+    }
+
+    // values are computed against the rescaled villain range so they match the subgame
+    let mut values = vec![HashMap::new(), HashMap::new()];
+    for (k, &pos) in [true, false].iter().enumerate() {
+        let (villain_reach, villain_scale) = if pos {
+            (&ip_reach, ip_scale)
+        } else {
+            (&oop_reach, oop_scale)
+        };
+        let villain_reach_probs: Vec<f64> = range_manager.get_range(!pos, board_mask, old_board_mask).hands.iter()
+                                                .map(|c| villain_reach[hand_order_index(c, hand_order_mapping)] / villain_scale)
+                                                .collect();
+        let hero_values = get_counterfactual_values(range_manager, node, pos, &villain_reach_probs, (board_mask, old_board_mask), false);
+        for (i, combo) in range_manager.get_range(pos, board_mask, old_board_mask).hands.iter().enumerate() {
+            values[k].insert((combo.0, combo.1), hero_values[i]);
+        }
+    }
+    let ip_values = values.pop().unwrap();
+    let oop_values = values.pop().unwrap();
+
+    Ok(Subgame { board, pot: node.pot_size, eff_stack: node.chance_start_stack, oop_range, ip_range, oop_values, ip_values })
+}
+
+// Builds the subgame tree with new lines. With safe set, the given player gets a gadget so the
+// opponent can't be exploited more than in the parent solve
+pub fn build_subgame(subgame: &Subgame, lines: Vec<Vec<u32>>, safe: Option<bool>) -> Trainer {
+    let range_manager = RangeManager::new(subgame.oop_range.clone(), subgame.ip_range.clone(), subgame.board.clone());
+    let mut trainer = Trainer::new(range_manager, lines, subgame.eff_stack, subgame.pot);
+
+    if let Some(oop) = safe {
+        let board_mask = get_card_mask(&trainer.range_manager.initial_board);
+        let parent_values = if oop {
+            &subgame.oop_values
+        } else {
+            &subgame.ip_values
+        };
+        let alt_values = trainer.range_manager.get_range(oop, board_mask, None).hands.iter()
+                            .map(|c| *parent_values.get(&(c.0, c.1)).unwrap_or(&0.0))
+                            .collect();
+        trainer.gadget = Some(ResolveGadget::new(oop, alt_values));
+    }
+
+    trainer
+}
+
+fn get_matchups(range_manager: &RangeManager, board_mask: u64) -> f64 {
+    let oop_range = &range_manager.get_range(true, board_mask, None).hands;
+    let ip_range = &range_manager.get_range(false, board_mask, None).hands;
+    let mut matchups = 0.0;
+    for oop_combo in oop_range {
+        let oop_mask = (1u64 << oop_combo.0) | (1u64 << oop_combo.1);
+        for ip_combo in ip_range {
+            if oop_mask & ((1u64 << ip_combo.0) | (1u64 << ip_combo.1)) == 0 {
+                matchups += (oop_combo.2 as f64 / 100.0) * (ip_combo.2 as f64 / 100.0);
+            }
+        }
+    }
+    matchups
+}
+
+// Exploitability of the gadget game in the same units as BestResponse::print_exploitability
+pub fn get_gadget_exploitability(gadget: &ResolveGadget, range_manager: &RangeManager, root: &Node) -> f64 {
+    let board_mask = get_card_mask(&range_manager.initial_board);
+    let gadget_reach = range_manager.get_initial_reach_probs(gadget.oop);
+    let other_reach = range_manager.get_initial_reach_probs(!gadget.oop);
+    let follow_probs = gadget.get_average_follow_probs();
+
+    // the gadget player picks the better of the parent value and a best response in the subgame
+    let gadget_br = get_counterfactual_values(range_manager, root, gadget.oop, &other_reach, (board_mask, None), true);
+    let mut total = 0.0;
+    for (i, reach) in gadget_reach.iter().enumerate() {
+        total += reach * gadget_br[i].max(gadget.alt_values[i]);
+    }
+
+    // the other player best responds to the combos entering the subgame and loses the parent value of the rest
+    let follow_reach: Vec<f64> = gadget_reach.iter().zip(follow_probs.iter()).map(|(r, f)| r * f).collect();
+    let other_br = get_counterfactual_values(range_manager, root, !gadget.oop, &follow_reach, (board_mask, None), true);
+    for (i, reach) in other_reach.iter().enumerate() {
+        total += reach * other_br[i];
+    }
+    for (i, reach) in gadget_reach.iter().enumerate() {
+        total -= reach * (1.0 - follow_probs[i]) * gadget.alt_values[i];
+    }
+
+    total / get_matchups(range_manager, board_mask) / 4.0
+}
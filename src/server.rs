@@ -0,0 +1,291 @@
+//! Local HTTP server holding named solver sessions, started with `opensolver serve --port <port>`.
+//!
+//! Requests and responses use the JSON lines protocol, see [`crate::json_protocol`]:
+//!
+//! - `GET /sessions` lists the session names
+//! - `POST /sessions/<name>` runs the JSON command in the body, the session is created on first use. `go`
+//!   answers when solving stops, other commands to the same session wait for it
+//! - `POST /sessions/<name>/stop` stops a running `go` of the session
+//! - `DELETE /sessions/<name>` drops the session
+//! - `GET /sessions/<name>/events` upgrades to a WebSocket receiving every response and progress event of the session
+//!
+//! Browsers may only call the server from the origin given with `--origin`, requests with another `Origin` are
+//! refused. With `--token` every request needs an `Authorization: Bearer <token>` header, or a `token` query
+//! parameter for the event stream, a token is required to serve on a host other than loopback. Commands taking
+//! files only get relative paths below the directory given with `--dir` and are refused without it.
+
+use crate::upi::CliSession;
+use crate::json_protocol::FileAccess;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::path::PathBuf;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{channel, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use tiny_http::{Header, Method, Request, Response, Server, StatusCode};
+use tungstenite::protocol::{Role, WebSocket};
+use tungstenite::handshake::derive_accept_key;
+use tungstenite::Message;
+
+struct Session {
+    cli_session: Mutex<CliSession>,
+    cancel: AtomicBool,
+    // websocket connections of the session
+    subscribers: Mutex<Vec<Sender<String>>>,
+}
+
+impl Session {
+    fn new(file_access: FileAccess) -> Session {
+        let mut cli_session = CliSession::new();
+        cli_session.set_file_access(file_access);
+        Session { cli_session: Mutex::new(cli_session), cancel: AtomicBool::new(false), subscribers: Mutex::new(vec![]) }
+    }
+
+    fn broadcast(&self, message: &str) {
+        // closed connections drop their receiver
+        self.subscribers.lock().unwrap().retain(|subscriber| subscriber.send(message.to_string()).is_ok());
+    }
+}
+
+type Sessions = Arc<Mutex<HashMap<String, Arc<Session>>>>;
+
+#[derive(Debug, Clone)]
+pub struct ServeOptions {
+    pub host: String,
+    pub port: u16,
+    /// the only origin browsers may call from
+    pub origin: Option<String>,
+    pub token: Option<String>,
+    /// directory the file arguments of commands are confined to
+    pub dir: Option<PathBuf>,
+}
+
+fn is_loopback(host: &str) -> bool {
+    host == "localhost" || host.parse::<IpAddr>().is_ok_and(|ip| ip.is_loopback())
+}
+
+/// Serves until the process is killed, every request is handled on its own thread
+pub fn serve(options: ServeOptions) -> Result<(), String> {
+    if options.token.is_none() && !is_loopback(&options.host) {
+        return Err(format!("a --token is required to serve on {}", options.host));
+    }
+    let file_access = match &options.dir {
+        Some(dir) if dir.is_dir() => FileAccess::Within(dir.clone()),
+        Some(dir) => return Err(format!("{} is not a directory", dir.display())),
+        None => FileAccess::Denied,
+    };
+    let server = Server::http((options.host.as_str(), options.port)).map_err(|e| e.to_string())?;
+    println!("SOLVER: serving on http://{}:{}", options.host, options.port);
+    let sessions: Sessions = Arc::new(Mutex::new(HashMap::new()));
+    let options = Arc::new(options);
+    for request in server.incoming_requests() {
+        let (sessions, options, file_access) = (sessions.clone(), options.clone(), file_access.clone());
+        thread::spawn(move || handle_request(request, &sessions, &options, &file_access));
+    }
+    Ok(())
+}
+
+fn header(field: &str, value: &str) -> Header {
+    Header::from_bytes(field.as_bytes(), value.as_bytes()).unwrap()
+}
+
+// requests from other origins are refused before a response is made, so the origin is the allowed one
+fn respond(request: Request, status: u16, body: &Value) {
+    let mut response = Response::from_string(body.to_string())
+        .with_status_code(StatusCode(status))
+        .with_header(header("Content-Type", "application/json"));
+    if let Some(origin) = get_header(&request, "Origin") {
+        response = response.with_header(header("Access-Control-Allow-Origin", &origin)).with_header(header("Vary", "Origin"));
+    }
+    // the client may have gone away
+    let _ = request.respond(response);
+}
+
+fn respond_error(request: Request, status: u16, code: &str, message: &str) {
+    respond(request, status, &json!({ "ok": false, "error": { "code": code, "message": message } }));
+}
+
+fn get_header(request: &Request, field: &str) -> Option<String> {
+    request.headers().iter().find(|x| x.field.as_str().as_str().eq_ignore_ascii_case(field)).map(|x| x.value.to_string())
+}
+
+fn authorized(request: &Request, token: &str) -> bool {
+    let query_token = request.url().split_once('?').is_some_and(|(_, query)| query.split('&').any(|x| x == format!("token={}", token)));
+    get_header(request, "Authorization").is_some_and(|x| x == format!("Bearer {}", token)) || query_token
+}
+
+fn handle_request(mut request: Request, sessions: &Sessions, options: &ServeOptions, file_access: &FileAccess) {
+    let url = request.url().split('?').next().unwrap().to_string();
+    let path: Vec<&str> = url.trim_matches('/').split('/').collect();
+    let method = request.method().clone();
+
+    // pages of other sites can't use the server, not even with requests that skip the preflight
+    if let Some(origin) = get_header(&request, "Origin") {
+        if options.origin.as_ref() != Some(&origin) {
+            let body = json!({ "ok": false, "error": { "code": "forbidden", "message": format!("origin {} not allowed", origin) } });
+            let _ = request.respond(Response::from_string(body.to_string()).with_status_code(StatusCode(403)));
+            return;
+        }
+    }
+    if let (Some(token), false) = (&options.token, method == Method::Options) {
+        if !authorized(&request, token) {
+            respond_error(request, 401, "unauthorized", "missing or wrong token");
+            return;
+        }
+    }
+
+    match (&method, path.as_slice()) {
+        (Method::Options, _) => {
+            let mut response = Response::empty(204)
+                .with_header(header("Access-Control-Allow-Methods", "GET, POST, DELETE, OPTIONS"))
+                .with_header(header("Access-Control-Allow-Headers", "Content-Type, Authorization"));
+            if let Some(origin) = get_header(&request, "Origin") {
+                response = response.with_header(header("Access-Control-Allow-Origin", &origin)).with_header(header("Vary", "Origin"));
+            }
+            let _ = request.respond(response);
+        },
+        (Method::Get, ["sessions"]) => {
+            let mut names: Vec<String> = sessions.lock().unwrap().keys().cloned().collect();
+            names.sort();
+            respond(request, 200, &json!({ "ok": true, "result": names }));
+        },
+        (Method::Post, ["sessions", name]) => {
+            let mut body = String::new();
+            if let Err(e) = request.as_reader().read_to_string(&mut body) {
+                respond_error(request, 400, "parse_error", &e.to_string());
+                return;
+            }
+            let session = sessions.lock().unwrap().entry(name.to_string()).or_insert_with(|| Arc::new(Session::new(file_access.clone()))).clone();
+            let response = run_command(&session, &body);
+            respond(request, 200, &response);
+        },
+        (Method::Post, ["sessions", name, "stop"]) => {
+            match sessions.lock().unwrap().get(*name) {
+                Some(session) => session.cancel.store(true, Ordering::Relaxed),
+                None => {
+                    respond_error(request, 404, "unknown_session", name);
+                    return;
+                },
+            };
+            respond(request, 200, &json!({ "ok": true, "result": null }));
+        },
+        (Method::Delete, ["sessions", name]) => {
+            match sessions.lock().unwrap().remove(*name) {
+                Some(session) => {
+                    session.cancel.store(true, Ordering::Relaxed);
+                    // ends the event streams
+                    session.subscribers.lock().unwrap().clear();
+                },
+                None => {
+                    respond_error(request, 404, "unknown_session", name);
+                    return;
+                },
+            };
+            respond(request, 200, &json!({ "ok": true, "result": null }));
+        },
+        (Method::Get, ["sessions", name, "events"]) => {
+            let session = match sessions.lock().unwrap().get(*name) {
+                Some(session) => session.clone(),
+                None => {
+                    respond_error(request, 404, "unknown_session", name);
+                    return;
+                },
+            };
+            stream_events(request, &session);
+        },
+        _ => respond_error(request, 404, "not_found", &url),
+    };
+}
+
+// a command that panicked may have left the session half updated, it starts over
+fn reset_session(cli_session: &mut CliSession) {
+    let file_access = cli_session.file_access.clone();
+    *cli_session = CliSession::new();
+    cli_session.set_file_access(file_access);
+}
+
+// runs one JSON command in the session, returns its response
+fn run_command(session: &Session, body: &str) -> Value {
+    let mut cli_session = session.cli_session.lock().unwrap_or_else(|poisoned| {
+        let mut cli_session = poisoned.into_inner();
+        reset_session(&mut cli_session);
+        session.cli_session.clear_poison();
+        cli_session
+    });
+    // a stop sent while no go was running doesn't apply to the next one
+    session.cancel.store(false, Ordering::Relaxed);
+    let mut response = Value::Null;
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        cli_session.handle_json(body, Some(&session.cancel), &mut |value| {
+            session.broadcast(&value.to_string());
+            if value.get("event").is_none() {
+                response = value.clone();
+            }
+        });
+    }));
+    if result.is_err() {
+        reset_session(&mut cli_session);
+        let request: Value = serde_json::from_str(body).unwrap_or(Value::Null);
+        response = json!({
+            "id": request.get("id").cloned().unwrap_or(Value::Null),
+            "cmd": request.get("cmd").cloned().unwrap_or_else(|| json!("")),
+            "ok": false,
+            "error": { "code": "internal_error", "message": "the command failed unexpectedly, the session was reset" },
+        });
+        session.broadcast(&response.to_string());
+    }
+    response
+}
+
+fn stream_events(request: Request, session: &Session) {
+    let key = match get_header(&request, "Sec-WebSocket-Key") {
+        Some(key) if get_header(&request, "Upgrade").is_some_and(|x| x.eq_ignore_ascii_case("websocket")) => key,
+        _ => {
+            respond_error(request, 400, "invalid_argument", "expected a websocket upgrade");
+            return;
+        },
+    };
+    let response = Response::new_empty(StatusCode(101))
+        .with_header(header("Upgrade", "websocket"))
+        .with_header(header("Connection", "Upgrade"))
+        .with_header(header("Sec-WebSocket-Accept", &derive_accept_key(key.as_bytes())));
+    let stream = request.upgrade("websocket", response);
+    let mut websocket = WebSocket::from_raw_socket(stream, Role::Server, None);
+
+    let (sender, receiver) = channel();
+    session.subscribers.lock().unwrap().push(sender);
+    for message in receiver {
+        if websocket.send(Message::text(message)).is_err() {
+            break;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_run_command() {
+        let session = Session::new(FileAccess::Denied);
+        let response = run_command(&session, r#"{"id": 1, "cmd": "set_pot", "pot": 50}"#);
+        assert_eq!(response["ok"], json!(true));
+        assert_eq!(response["id"], json!(1));
+        let response = run_command(&session, r#"{"cmd": "save_solution", "file": "solution.bin"}"#);
+        assert_eq!(response["error"]["code"], json!("forbidden"));
+
+        // a panic while holding the session poisons it, the next command gets a fresh session
+        let _ = thread::scope(|scope| scope.spawn(|| {
+            let _cli_session = session.cli_session.lock().unwrap();
+            panic!("command panicked");
+        }).join());
+        let response = run_command(&session, r#"{"cmd": "is_ready"}"#);
+        assert_eq!(response["ok"], json!(true));
+        let cli_session = session.cli_session.lock().unwrap();
+        assert_eq!(cli_session.tree_information.pot, None);
+        assert_eq!(cli_session.file_access, FileAccess::Denied);
+    }
+}
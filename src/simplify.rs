@@ -0,0 +1,188 @@
+use crate::api::*;
+use crate::best_response::*;
+use crate::postfloptree::*;
+use crate::trainer::*;
+use serde::Serialize;
+
+/// How a hand's action frequencies are simplified
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Simplification {
+    /// to multiples of the percentage, e.g. 50 for 0/50/100%, 100 plays only the most frequent action
+    Round(f64),
+    /// actions below the percentage are dropped and the rest scaled up
+    Drop(f64),
+    /// all bets and raises go to the size the range uses most at the node
+    OneBetSize,
+}
+
+/// EVs of both players with the current strategies and what best responding would gain
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct StrategyValue {
+    pub oop_ev: f64,
+    pub ip_ev: f64,
+    pub oop_mes: f64,
+    pub ip_mes: f64,
+    pub exploitability: f64,
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct SimplificationResult {
+    pub nodes: usize,
+    pub before: StrategyValue,
+    pub after: StrategyValue,
+}
+
+pub fn get_strategy_value(trainer: &Trainer) -> Result<StrategyValue, String> {
+    let mut best_response = BestResponse::new(&trainer.range_manager);
+    best_response.set_relative_probablities(true);
+    best_response.set_relative_probablities(false);
+    let (oop_mes, ip_mes, exploitability) = best_response.get_exploitability(&trainer.root);
+    let oop_ev = get_ev(trainer, true, "r:0")?.total;
+    let ip_ev = get_ev(trainer, false, "r:0")?.total;
+    Ok(StrategyValue { oop_ev, ip_ev, oop_mes, ip_mes, exploitability })
+}
+
+// largest remainder rounding to units of 1/units, so the frequencies still sum to 1
+fn round_frequencies(frequencies: &mut [f64], units: usize) {
+    let mut rounded: Vec<usize> = frequencies.iter().map(|x| (x * units as f64).floor() as usize).collect();
+    let mut order: Vec<usize> = (0..frequencies.len()).collect();
+    order.sort_by(|&a, &b| {
+        let remainder_a = frequencies[a] * units as f64 - rounded[a] as f64;
+        let remainder_b = frequencies[b] * units as f64 - rounded[b] as f64;
+        remainder_b.partial_cmp(&remainder_a).unwrap()
+    });
+    let missing = units.saturating_sub(rounded.iter().sum());
+    for &i in order.iter().take(missing) {
+        rounded[i] += 1;
+    }
+    for (frequency, units_i) in frequencies.iter_mut().zip(rounded.iter()) {
+        *frequency = *units_i as f64 / units as f64;
+    }
+}
+
+fn drop_frequencies(frequencies: &mut [f64], threshold: f64) {
+    let max = frequencies.iter().cloned().fold(0.0, f64::max);
+    // the most frequent action is always kept
+    for frequency in frequencies.iter_mut() {
+        if *frequency < threshold && *frequency < max {
+            *frequency = 0.0;
+        }
+    }
+    let total: f64 = frequencies.iter().sum();
+    for frequency in frequencies.iter_mut() {
+        *frequency /= total;
+    }
+}
+
+/// Simplifies the frequencies of one hand, bet_target is the action bets move to with OneBetSize
+pub fn simplify_frequencies(frequencies: &mut [f64], simplification: Simplification, bets: &[bool], bet_target: Option<usize>) {
+    match simplification {
+        Simplification::Round(percent) => round_frequencies(frequencies, ((100.0 / percent).round() as usize).max(1)),
+        Simplification::Drop(percent) => drop_frequencies(frequencies, percent / 100.0),
+        Simplification::OneBetSize => {
+            if let Some(target) = bet_target {
+                for j in 0..frequencies.len() {
+                    if bets[j] && j != target {
+                        frequencies[target] += frequencies[j];
+                        frequencies[j] = 0.0;
+                    }
+                }
+            }
+        },
+    };
+}
+
+fn simplify_node(node_info: &mut ActionNodeInfo, simplification: Simplification) {
+    let mut strategy = node_info.get_average_strategy();
+    let actions_num = node_info.actions_num;
+    let hands_num = strategy.len() / actions_num;
+    let bets: Vec<bool> = node_info.actions.iter().map(|action| matches!(action, ActionType::Bet(_) | ActionType::Raise{..})).collect();
+    // strategy sums are reach weighted, so their totals are how often the range takes each action
+    let bet_target = (0..actions_num).filter(|&j| bets[j])
+        .max_by(|&a, &b| {
+            let total_a: f64 = node_info.strategy_sum[a*hands_num..(a+1)*hands_num].iter().sum();
+            let total_b: f64 = node_info.strategy_sum[b*hands_num..(b+1)*hands_num].iter().sum();
+            total_a.partial_cmp(&total_b).unwrap()
+        });
+
+    let mut frequencies = vec![0.0; actions_num];
+    for i in 0..hands_num {
+        for j in 0..actions_num {
+            frequencies[j] = strategy[j*hands_num + i];
+        }
+        simplify_frequencies(&mut frequencies, simplification, &bets, bet_target);
+        for j in 0..actions_num {
+            strategy[j*hands_num + i] = frequencies[j];
+        }
+    }
+    node_info.locked_strategy = Some(strategy);
+}
+
+// applies f to the action node and with subtree to every action node below it, returns the number of nodes
+fn for_each_action_node(node: &mut Node, subtree: bool, f: &mut dyn FnMut(&mut ActionNodeInfo)) -> usize {
+    let mut count = 0;
+    if let NodeType::ActionNode(node_info) = &mut node.node_type {
+        f(node_info);
+        count += 1;
+    }
+    if subtree {
+        for child in node.children.iter_mut() {
+            count += for_each_action_node(child, true, f);
+        }
+    }
+    count
+}
+
+/// Locks the simplified average strategy at the line's node, and with subtree at every node below it.
+/// Further solving only adapts the other nodes
+pub fn simplify_strategy(trainer: &mut Trainer, line: &str, simplification: Simplification, subtree: bool) -> Result<SimplificationResult, String> {
+    check_line(trainer, line)?;
+    let node = trainer.root.find_node_mut(&line.to_string(), &trainer.range_manager);
+    if !subtree && !matches!(node.node_type, NodeType::ActionNode(_)) {
+        return Err(format!("{} is not an action node", line));
+    }
+
+    let before = get_strategy_value(trainer)?;
+    let node = trainer.root.find_node_mut(&line.to_string(), &trainer.range_manager);
+    let nodes = for_each_action_node(node, subtree, &mut |node_info| simplify_node(node_info, simplification));
+    let after = get_strategy_value(trainer)?;
+    Ok(SimplificationResult { nodes, before, after })
+}
+
+/// Unlocks the line's node, and with subtree every node below it, returns the number of unlocked nodes
+pub fn unlock_strategy(trainer: &mut Trainer, line: &str, subtree: bool) -> Result<usize, String> {
+    check_line(trainer, line)?;
+    let node = trainer.root.find_node_mut(&line.to_string(), &trainer.range_manager);
+    let mut unlocked = 0;
+    for_each_action_node(node, subtree, &mut |node_info| {
+        if node_info.locked_strategy.take().is_some() {
+            unlocked += 1;
+        }
+    });
+    Ok(unlocked)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_simplify_frequencies() {
+        let bets = [false, true, true];
+        let mut frequencies = [0.2, 0.35, 0.45];
+        simplify_frequencies(&mut frequencies, Simplification::Round(50.0), &bets, None);
+        assert_eq!(frequencies, [0.0, 0.5, 0.5]);
+
+        let mut frequencies = [0.2, 0.35, 0.45];
+        simplify_frequencies(&mut frequencies, Simplification::Round(100.0), &bets, None);
+        assert_eq!(frequencies, [0.0, 0.0, 1.0]);
+
+        let mut frequencies = [0.05, 0.5, 0.45];
+        simplify_frequencies(&mut frequencies, Simplification::Drop(10.0), &bets, None);
+        assert!((frequencies[0]).abs() < 1e-12 && (frequencies[1] - 0.5 / 0.95).abs() < 1e-12);
+
+        let mut frequencies = [0.2, 0.35, 0.45];
+        simplify_frequencies(&mut frequencies, Simplification::OneBetSize, &bets, Some(1));
+        assert_eq!(frequencies, [0.2, 0.8, 0.0]);
+    }
+}
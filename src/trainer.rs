@@ -2,13 +2,28 @@ use crate::postfloptree::*;
 use crate::range::*;
 use crate::cfr::*;
 use crate::best_response::*;
+use crate::checkpoint::*;
+use crate::resolve::*;
 use std::time::Instant;
+use std::sync::atomic::{AtomicBool, Ordering};
 use rust_poker::hand_range::{get_card_mask};
-use std::collections::HashMap;
+use serde::Serialize;
 
 pub struct Trainer {
     pub range_manager: RangeManager,
     pub root: Node,
+    pub iteration: u64,
+    pub checkpoint: Option<CheckpointConfig>,
+    pub algorithm: CfrAlgorithm,
+    pub sampling: ChanceSampling,
+    // sampling switches to full traversal once exploitability drops below this fraction (% of pot)
+    pub sampling_polish: f64,
+    // regret-based pruning, every n-th iteration is a full pass without pruning
+    pub pruning_interval: Option<u64>,
+    // set when safely re-solving a subgame
+    pub gadget: Option<ResolveGadget>,
+    // iterations between exploitability checks
+    pub info_freq: u64,
 }
 
 pub enum Accuracy {
@@ -16,6 +31,38 @@ pub enum Accuracy {
     Fraction(f64),
 }
 
+/// Exploitability check during solving, MES is the maximally exploitative strategy's value of a player
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct Progress {
+    pub iteration: u64,
+    pub time_elapsed: f64,
+    pub oop_mes: f64,
+    pub ip_mes: f64,
+    pub exploitability: f64,
+    /// exploitability of the gadget game when safely re-solving a subgame
+    pub gadget_exploitability: Option<f64>,
+}
+
+impl Progress {
+    /// exploitability that has to reach the accuracy goal
+    pub fn target_exploitability(&self) -> f64 {
+        self.gadget_exploitability.unwrap_or(self.exploitability)
+    }
+    
+    pub fn print(&self, pot: u32) {
+        info!("SOLVER:");
+        info!("running time: {}", self.time_elapsed);
+        info!("OOP's MES: {}", self.oop_mes);
+        info!("IP's MES: {}", self.ip_mes);
+        info!("Exploitable for: {} ({}%)", self.exploitability, self.exploitability / (pot as f64) * 100.0);
+        info!("END \n");
+        if let Some(gadget_exploitability) = self.gadget_exploitability {
+            info!("SOLVER: gadget exploitable for: {} ({}%)", gadget_exploitability, gadget_exploitability / (pot as f64) * 100.0);
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
 pub enum TrainFinish {
     Seconds(u64),
     Iterations(u64),
@@ -26,23 +73,49 @@ impl Trainer {
     pub fn new(mut range_manager: RangeManager, lines: Vec<Vec<u32>>, eff_stack: u32, pot_size: u32) -> Self {
         let sizing_mapping = get_sizings(lines);
         range_manager.initialize_ranges();
+        range_manager.initialize_leaf_payoffs();
         let oop_num_hands = range_manager.get_num_hands(true, get_card_mask(&range_manager.initial_board), None);
         let ip_num_hands = range_manager.get_num_hands(false, get_card_mask(&range_manager.initial_board), None);
         let mut root = Node::new_root(eff_stack, pot_size, oop_num_hands, ip_num_hands);
         
         recursive_build(None, &sizing_mapping, &"".to_string(), &mut root, &range_manager, &range_manager.initial_board);
+        mark_card_splits(&mut root);
+        let mut allin_boards = vec![];
+        collect_allin_boards(&root, (get_card_mask(&range_manager.initial_board), None), &mut allin_boards);
+        range_manager.initialize_allin_runouts(&allin_boards);
         
-        Trainer { range_manager, root }
+        Trainer { range_manager, root, iteration: 0, checkpoint: None, algorithm: CfrAlgorithm::default(), sampling: ChanceSampling::Full, sampling_polish: 0.0, pruning_interval: None, gadget: None, info_freq: 25 }
     }
     
     
     
-    pub fn train(&mut self, accuracy: &Accuracy, train_finish: TrainFinish) {
+    fn check_exploitability(&self, best_response: &mut BestResponse, time_elapsed: f64, on_progress: &mut dyn FnMut(&Progress)) -> f64 {
+        let progress = self.get_progress(best_response, time_elapsed);
+        on_progress(&progress);
+        progress.target_exploitability()
+    }
+    
+    fn get_progress(&self, best_response: &mut BestResponse, time_elapsed: f64) -> Progress {
+        let (oop_mes, ip_mes, exploitability) = best_response.get_exploitability(&self.root);
+        let gadget_exploitability = self.gadget.as_ref().map(|gadget| get_gadget_exploitability(gadget, &self.range_manager, &self.root));
+        Progress { iteration: self.iteration, time_elapsed, oop_mes, ip_mes, exploitability, gadget_exploitability }
+    }
+    
+    // solves printing the exploitability checks like the UPI protocol expects
+    pub fn train(&mut self, accuracy: &Accuracy, train_finish: TrainFinish) -> f64 {
+        let pot = self.root.pot_size;
+        self.solve(accuracy, train_finish, None, &mut |progress| progress.print(pot))
+    }
+    
+    /// Runs CFR until the accuracy goal, the finish condition or cancel is reached. on_progress is called with every
+    /// exploitability check, with a re-solving gadget the gadget game exploitability is what has to converge
+    pub fn solve(&mut self, accuracy: &Accuracy, train_finish: TrainFinish, cancel: Option<&AtomicBool>, on_progress: &mut dyn FnMut(&Progress)) -> f64 {
         let mut best_response = BestResponse::new(&self.range_manager);
         best_response.set_relative_probablities(true);
         best_response.set_relative_probablities(false);
         let now = Instant::now();
-        let mut i = 0;
+        let start_iteration = self.iteration;
+        let mut last_checkpoint = (self.iteration, Instant::now());
         let exploitability_goal = match accuracy {
             Accuracy::Chips(val) => *val,
             Accuracy::Fraction(val) => {
@@ -50,45 +123,93 @@ impl Trainer {
             },
         };
         let mut time_elapsed = now.elapsed().as_secs_f64();
+        let mut exploitability = f64::MAX;
+        let mut settings = CfrSettings { algorithm: self.algorithm, sampling: self.sampling, pruning: false, parallel_actions: true };
         loop {
             time_elapsed = now.elapsed().as_secs_f64();
             match train_finish {
                 TrainFinish::Seconds(val) => {
                     if time_elapsed as u64 >= val {
-                        best_response.print_exploitability(&self.root, time_elapsed);
+                        exploitability = self.check_exploitability(&mut best_response, time_elapsed, on_progress);
                         break;
                     }
                 },
                 TrainFinish::Iterations(val) => {
-                    if i >= val {
-                        best_response.print_exploitability(&self.root, time_elapsed);
+                    if self.iteration - start_iteration >= val {
+                        exploitability = self.check_exploitability(&mut best_response, time_elapsed, on_progress);
                         break;
                     }
                 },
                 TrainFinish::Indefinite => (),
             };
+            if cancel.is_some_and(|cancel| cancel.load(Ordering::Relaxed)) {
+                exploitability = self.check_exploitability(&mut best_response, time_elapsed, on_progress);
+                break;
+            }
             
-            cfr_aux(true, &mut self.root, i, &self.range_manager);
-            cfr_aux(false, &mut self.root, i, &self.range_manager);
-            if i % 25 == 0 {
-                let exploitability = best_response.print_exploitability(&self.root, time_elapsed);
+            let i = self.iteration;
+            settings.pruning = match self.pruning_interval {
+                Some(interval) => !i.is_multiple_of(interval),
+                None => false,
+            };
+            cfr_aux(true, &mut self.root, i, &self.range_manager, settings, &mut self.gadget);
+            cfr_aux(false, &mut self.root, i, &self.range_manager, settings, &mut self.gadget);
+            self.iteration += 1;
+            
+            if let Some(checkpoint) = &self.checkpoint {
+                let due = match checkpoint.frequency {
+                    CheckpointFrequency::Iterations(val) => self.iteration - last_checkpoint.0 >= val,
+                    CheckpointFrequency::Seconds(val) => last_checkpoint.1.elapsed().as_secs() >= val,
+                };
+                if due {
+                    if let Err(e) = save_checkpoint(self, &checkpoint.path) {
+                        output!("ERROR: couldn't write checkpoint {}: {}", checkpoint.path, e);
+                    }
+                    last_checkpoint = (self.iteration, Instant::now());
+                }
+            }
+            
+            if i.is_multiple_of(self.info_freq) {
+                exploitability = self.check_exploitability(&mut best_response, time_elapsed, on_progress);
                 if exploitability <= exploitability_goal {
                     break;
                 }
+                if exploitability <= self.sampling_polish * (self.root.pot_size as f64) / 100.0 {
+                    settings.sampling = ChanceSampling::Full;
+                }
+            }
+        }
+
+        if let Some(checkpoint) = &self.checkpoint {
+            if let Err(e) = save_checkpoint(self, &checkpoint.path) {
+                output!("ERROR: couldn't write checkpoint {}: {}", checkpoint.path, e);
             }
-            i += 1;
         }
 
         //println!("Elapsed: {} seconds", time_elapsed);
+        exploitability
     }
 }
 
-fn cfr_aux(pos: bool, root: &mut Node, n_iteration: u64, range_manager: &RangeManager) {
+fn cfr_aux(pos: bool, root: &mut Node, n_iteration: u64, range_manager: &RangeManager, settings: CfrSettings, gadget: &mut Option<ResolveGadget>) {
     let villain_pos = pos ^ true;
-    let villain_reach_probs = range_manager.get_initial_reach_probs(villain_pos);
+    let mut villain_reach_probs = range_manager.get_initial_reach_probs(villain_pos);
+    if let Some(gadget) = gadget {
+        if gadget.oop == villain_pos {
+            for (reach_prob, follow_prob) in villain_reach_probs.iter_mut().zip(gadget.get_follow_probs()) {
+                *reach_prob *= follow_prob;
+            }
+        }
+    }
     let board_mask = get_card_mask(&range_manager.initial_board);
     
     let mut results = vec![];
-    let mut cfr_start = CfrState::new(range_manager, &mut results, root, pos, &villain_reach_probs, (board_mask, None), n_iteration);
+    let mut cfr_start = CfrState::new(range_manager, &mut results, root, pos, &villain_reach_probs, (board_mask, None), n_iteration, settings);
     cfr_start.run();
+    
+    if let Some(gadget) = gadget {
+        if gadget.oop == pos {
+            gadget.update(&results, n_iteration);
+        }
+    }
 }
\ No newline at end of file
@@ -1,29 +1,75 @@
 use std::io;
 use std::io::Write;
 use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
 
 use crate::hand_range::*;
 use crate::trainer::*;
-use crate::range::*;
+use crate::equity::*;
+use crate::batch::*;
+use crate::isomorphism::*;
+use crate::checkpoint::*;
+use crate::postfloptree::CfrAlgorithm;
+use crate::cfr::ChanceSampling;
+use crate::api::*;
+use crate::warmstart::*;
+use crate::resolve::*;
+use crate::export::*;
+use crate::report::*;
+use crate::simplify::*;
+use crate::compare::*;
+use crate::practice::*;
+use crate::hand_history::*;
+use crate::output::{log_input, count_error, error_count};
+use crate::json_protocol::FileAccess;
+use rust_poker::hand_range::{get_card_mask};
 #[derive(Debug)]
-struct TreeInformation {
-    eff_stack: Option<u32>,
-    pot: Option<u32>,
-    oop_range: Option<HandRange>,
-    ip_range: Option<HandRange>,
-    lines: Option<Vec<Vec<u32>>>,
-    board: Option<String>,
+pub(crate) struct TreeInformation {
+    pub(crate) eff_stack: Option<u32>,
+    pub(crate) pot: Option<u32>,
+    pub(crate) oop_range: Option<HandRange>,
+    pub(crate) ip_range: Option<HandRange>,
+    pub(crate) lines: Option<Vec<Vec<u32>>>,
+    pub(crate) board: Option<String>,
+    // board length the tree stops at and equity realization of OOP and IP
+    pub(crate) depth_limit: Option<(usize, f64, f64)>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Protocol {
+    Upi,
+    // one JSON object per line in both directions, see json_protocol
+    Json,
 }
 
 pub struct CliSession {
-    tree_information: TreeInformation,
-    end_string: String,
-    accuracy: Accuracy,
-    hand_order: Vec<String>,
-    hand_order_map: HashMap<String, usize>,
-    trainer: Option<Trainer>,
+    pub(crate) protocol: Protocol,
+    pub(crate) tree_information: TreeInformation,
+    pub(crate) end_string: String,
+    pub(crate) accuracy: Accuracy,
+    pub(crate) hand_order: Vec<String>,
+    pub(crate) hand_order_map: HashMap<String, usize>,
+    pub(crate) trainer: Option<Trainer>,
+    pub(crate) checkpoint: Option<CheckpointConfig>,
+    pub(crate) algorithm: CfrAlgorithm,
+    pub(crate) sampling: (ChanceSampling, f64),
+    pub(crate) pruning_interval: Option<u64>,
+    pub(crate) info_freq: u64,
+    // solved tree a subgame was re-solved from
+    pub(crate) parent_trainer: Option<Trainer>,
+    // hand played against the solution by practice_hand and practice_action
+    pub(crate) practice: Option<PracticeHand>,
+    // files JSON commands may use
+    pub(crate) file_access: FileAccess,
+    // scripts being run by load_script, innermost last
+    script_stack: Vec<PathBuf>,
+    // scripts run on after a command failed
+    keep_going: bool,
 }
 
+const MAX_SCRIPT_DEPTH: usize = 16;
+
 fn trim_newline(s: &mut String) {
     if s.ends_with('\n') {
         s.pop();
@@ -35,88 +81,218 @@ fn trim_newline(s: &mut String) {
 
 impl CliSession {
     pub fn new() -> Self {
-        let mut tree_information = TreeInformation { eff_stack: None, pot: None, oop_range: None, ip_range: None, lines: None, board: None };
-        let mut hand_order = vec!["2d2c".to_string(), "2h2c".to_string(), "2h2d".to_string(), "2s2c".to_string(), "2s2d".to_string(), "2s2h".to_string(), "3c2c".to_string(), "3c2d".to_string(), "3c2h".to_string(), "3c2s".to_string(), "3d2c".to_string(), "3d2d".to_string(), "3d2h".to_string(), "3d2s".to_string(), "3d3c".to_string(), "3h2c".to_string(), "3h2d".to_string(), "3h2h".to_string(), "3h2s".to_string(), "3h3c".to_string(), "3h3d".to_string(), "3s2c".to_string(), "3s2d".to_string(), "3s2h".to_string(), "3s2s".to_string(), "3s3c".to_string(), "3s3d".to_string(), "3s3h".to_string(), "4c2c".to_string(), "4c2d".to_string(), "4c2h".to_string(), "4c2s".to_string(), "4c3c".to_string(), "4c3d".to_string(), "4c3h".to_string(), "4c3s".to_string(), "4d2c".to_string(), "4d2d".to_string(), "4d2h".to_string(), "4d2s".to_string(), "4d3c".to_string(), "4d3d".to_string(), "4d3h".to_string(), "4d3s".to_string(), "4d4c".to_string(), "4h2c".to_string(), "4h2d".to_string(), "4h2h".to_string(), "4h2s".to_string(), "4h3c".to_string(), "4h3d".to_string(), "4h3h".to_string(), "4h3s".to_string(), "4h4c".to_string(), "4h4d".to_string(), "4s2c".to_string(), "4s2d".to_string(), "4s2h".to_string(), "4s2s".to_string(), "4s3c".to_string(), "4s3d".to_string(), "4s3h".to_string(), "4s3s".to_string(), "4s4c".to_string(), "4s4d".to_string(), "4s4h".to_string(), "5c2c".to_string(), "5c2d".to_string(), "5c2h".to_string(), "5c2s".to_string(), "5c3c".to_string(), "5c3d".to_string(), "5c3h".to_string(), "5c3s".to_string(), "5c4c".to_string(), "5c4d".to_string(), "5c4h".to_string(), "5c4s".to_string(), "5d2c".to_string(), "5d2d".to_string(), "5d2h".to_string(), "5d2s".to_string(), "5d3c".to_string(), "5d3d".to_string(), "5d3h".to_string(), "5d3s".to_string(), "5d4c".to_string(), "5d4d".to_string(), "5d4h".to_string(), "5d4s".to_string(), "5d5c".to_string(), "5h2c".to_string(), "5h2d".to_string(), "5h2h".to_string(), "5h2s".to_string(), "5h3c".to_string(), "5h3d".to_string(), "5h3h".to_string(), "5h3s".to_string(), "5h4c".to_string(), "5h4d".to_string(), "5h4h".to_string(), "5h4s".to_string(), "5h5c".to_string(), "5h5d".to_string(), "5s2c".to_string(), "5s2d".to_string(), "5s2h".to_string(), "5s2s".to_string(), "5s3c".to_string(), "5s3d".to_string(), "5s3h".to_string(), "5s3s".to_string(), "5s4c".to_string(), "5s4d".to_string(), "5s4h".to_string(), "5s4s".to_string(), "5s5c".to_string(), "5s5d".to_string(), "5s5h".to_string(), "6c2c".to_string(), "6c2d".to_string(), "6c2h".to_string(), "6c2s".to_string(), "6c3c".to_string(), "6c3d".to_string(), "6c3h".to_string(), "6c3s".to_string(), "6c4c".to_string(), "6c4d".to_string(), "6c4h".to_string(), "6c4s".to_string(), "6c5c".to_string(), "6c5d".to_string(), "6c5h".to_string(), "6c5s".to_string(), "6d2c".to_string(), "6d2d".to_string(), "6d2h".to_string(), "6d2s".to_string(), "6d3c".to_string(), "6d3d".to_string(), "6d3h".to_string(), "6d3s".to_string(), "6d4c".to_string(), "6d4d".to_string(), "6d4h".to_string(), "6d4s".to_string(), "6d5c".to_string(), "6d5d".to_string(), "6d5h".to_string(), "6d5s".to_string(), "6d6c".to_string(), "6h2c".to_string(), "6h2d".to_string(), "6h2h".to_string(), "6h2s".to_string(), "6h3c".to_string(), "6h3d".to_string(), "6h3h".to_string(), "6h3s".to_string(), "6h4c".to_string(), "6h4d".to_string(), "6h4h".to_string(), "6h4s".to_string(), "6h5c".to_string(), "6h5d".to_string(), "6h5h".to_string(), "6h5s".to_string(), "6h6c".to_string(), "6h6d".to_string(), "6s2c".to_string(), "6s2d".to_string(), "6s2h".to_string(), "6s2s".to_string(), "6s3c".to_string(), "6s3d".to_string(), "6s3h".to_string(), "6s3s".to_string(), "6s4c".to_string(), "6s4d".to_string(), "6s4h".to_string(), "6s4s".to_string(), "6s5c".to_string(), "6s5d".to_string(), "6s5h".to_string(), "6s5s".to_string(), "6s6c".to_string(), "6s6d".to_string(), "6s6h".to_string(), "7c2c".to_string(), "7c2d".to_string(), "7c2h".to_string(), "7c2s".to_string(), "7c3c".to_string(), "7c3d".to_string(), "7c3h".to_string(), "7c3s".to_string(), "7c4c".to_string(), "7c4d".to_string(), "7c4h".to_string(), "7c4s".to_string(), "7c5c".to_string(), "7c5d".to_string(), "7c5h".to_string(), "7c5s".to_string(), "7c6c".to_string(), "7c6d".to_string(), "7c6h".to_string(), "7c6s".to_string(), "7d2c".to_string(), "7d2d".to_string(), "7d2h".to_string(), "7d2s".to_string(), "7d3c".to_string(), "7d3d".to_string(), "7d3h".to_string(), "7d3s".to_string(), "7d4c".to_string(), "7d4d".to_string(), "7d4h".to_string(), "7d4s".to_string(), "7d5c".to_string(), "7d5d".to_string(), "7d5h".to_string(), "7d5s".to_string(), "7d6c".to_string(), "7d6d".to_string(), "7d6h".to_string(), "7d6s".to_string(), "7d7c".to_string(), "7h2c".to_string(), "7h2d".to_string(), "7h2h".to_string(), "7h2s".to_string(), "7h3c".to_string(), "7h3d".to_string(), "7h3h".to_string(), "7h3s".to_string(), "7h4c".to_string(), "7h4d".to_string(), "7h4h".to_string(), "7h4s".to_string(), "7h5c".to_string(), "7h5d".to_string(), "7h5h".to_string(), "7h5s".to_string(), "7h6c".to_string(), "7h6d".to_string(), "7h6h".to_string(), "7h6s".to_string(), "7h7c".to_string(), "7h7d".to_string(), "7s2c".to_string(), "7s2d".to_string(), "7s2h".to_string(), "7s2s".to_string(), "7s3c".to_string(), "7s3d".to_string(), "7s3h".to_string(), "7s3s".to_string(), "7s4c".to_string(), "7s4d".to_string(), "7s4h".to_string(), "7s4s".to_string(), "7s5c".to_string(), "7s5d".to_string(), "7s5h".to_string(), "7s5s".to_string(), "7s6c".to_string(), "7s6d".to_string(), "7s6h".to_string(), "7s6s".to_string(), "7s7c".to_string(), "7s7d".to_string(), "7s7h".to_string(), "8c2c".to_string(), "8c2d".to_string(), "8c2h".to_string(), "8c2s".to_string(), "8c3c".to_string(), "8c3d".to_string(), "8c3h".to_string(), "8c3s".to_string(), "8c4c".to_string(), "8c4d".to_string(), "8c4h".to_string(), "8c4s".to_string(), "8c5c".to_string(), "8c5d".to_string(), "8c5h".to_string(), "8c5s".to_string(), "8c6c".to_string(), "8c6d".to_string(), "8c6h".to_string(), "8c6s".to_string(), "8c7c".to_string(), "8c7d".to_string(), "8c7h".to_string(), "8c7s".to_string(), "8d2c".to_string(), "8d2d".to_string(), "8d2h".to_string(), "8d2s".to_string(), "8d3c".to_string(), "8d3d".to_string(), "8d3h".to_string(), "8d3s".to_string(), "8d4c".to_string(), "8d4d".to_string(), "8d4h".to_string(), "8d4s".to_string(), "8d5c".to_string(), "8d5d".to_string(), "8d5h".to_string(), "8d5s".to_string(), "8d6c".to_string(), "8d6d".to_string(), "8d6h".to_string(), "8d6s".to_string(), "8d7c".to_string(), "8d7d".to_string(), "8d7h".to_string(), "8d7s".to_string(), "8d8c".to_string(), "8h2c".to_string(), "8h2d".to_string(), "8h2h".to_string(), "8h2s".to_string(), "8h3c".to_string(), "8h3d".to_string(), "8h3h".to_string(), "8h3s".to_string(), "8h4c".to_string(), "8h4d".to_string(), "8h4h".to_string(), "8h4s".to_string(), "8h5c".to_string(), "8h5d".to_string(), "8h5h".to_string(), "8h5s".to_string(), "8h6c".to_string(), "8h6d".to_string(), "8h6h".to_string(), "8h6s".to_string(), "8h7c".to_string(), "8h7d".to_string(), "8h7h".to_string(), "8h7s".to_string(), "8h8c".to_string(), "8h8d".to_string(), "8s2c".to_string(), "8s2d".to_string(), "8s2h".to_string(), "8s2s".to_string(), "8s3c".to_string(), "8s3d".to_string(), "8s3h".to_string(), "8s3s".to_string(), "8s4c".to_string(), "8s4d".to_string(), "8s4h".to_string(), "8s4s".to_string(), "8s5c".to_string(), "8s5d".to_string(), "8s5h".to_string(), "8s5s".to_string(), "8s6c".to_string(), "8s6d".to_string(), "8s6h".to_string(), "8s6s".to_string(), "8s7c".to_string(), "8s7d".to_string(), "8s7h".to_string(), "8s7s".to_string(), "8s8c".to_string(), "8s8d".to_string(), "8s8h".to_string(), "9c2c".to_string(), "9c2d".to_string(), "9c2h".to_string(), "9c2s".to_string(), "9c3c".to_string(), "9c3d".to_string(), "9c3h".to_string(), "9c3s".to_string(), "9c4c".to_string(), "9c4d".to_string(), "9c4h".to_string(), "9c4s".to_string(), "9c5c".to_string(), "9c5d".to_string(), "9c5h".to_string(), "9c5s".to_string(), "9c6c".to_string(), "9c6d".to_string(), "9c6h".to_string(), "9c6s".to_string(), "9c7c".to_string(), "9c7d".to_string(), "9c7h".to_string(), "9c7s".to_string(), "9c8c".to_string(), "9c8d".to_string(), "9c8h".to_string(), "9c8s".to_string(), "9d2c".to_string(), "9d2d".to_string(), "9d2h".to_string(), "9d2s".to_string(), "9d3c".to_string(), "9d3d".to_string(), "9d3h".to_string(), "9d3s".to_string(), "9d4c".to_string(), "9d4d".to_string(), "9d4h".to_string(), "9d4s".to_string(), "9d5c".to_string(), "9d5d".to_string(), "9d5h".to_string(), "9d5s".to_string(), "9d6c".to_string(), "9d6d".to_string(), "9d6h".to_string(), "9d6s".to_string(), "9d7c".to_string(), "9d7d".to_string(), "9d7h".to_string(), "9d7s".to_string(), "9d8c".to_string(), "9d8d".to_string(), "9d8h".to_string(), "9d8s".to_string(), "9d9c".to_string(), "9h2c".to_string(), "9h2d".to_string(), "9h2h".to_string(), "9h2s".to_string(), "9h3c".to_string(), "9h3d".to_string(), "9h3h".to_string(), "9h3s".to_string(), "9h4c".to_string(), "9h4d".to_string(), "9h4h".to_string(), "9h4s".to_string(), "9h5c".to_string(), "9h5d".to_string(), "9h5h".to_string(), "9h5s".to_string(), "9h6c".to_string(), "9h6d".to_string(), "9h6h".to_string(), "9h6s".to_string(), "9h7c".to_string(), "9h7d".to_string(), "9h7h".to_string(), "9h7s".to_string(), "9h8c".to_string(), "9h8d".to_string(), "9h8h".to_string(), "9h8s".to_string(), "9h9c".to_string(), "9h9d".to_string(), "9s2c".to_string(), "9s2d".to_string(), "9s2h".to_string(), "9s2s".to_string(), "9s3c".to_string(), "9s3d".to_string(), "9s3h".to_string(), "9s3s".to_string(), "9s4c".to_string(), "9s4d".to_string(), "9s4h".to_string(), "9s4s".to_string(), "9s5c".to_string(), "9s5d".to_string(), "9s5h".to_string(), "9s5s".to_string(), "9s6c".to_string(), "9s6d".to_string(), "9s6h".to_string(), "9s6s".to_string(), "9s7c".to_string(), "9s7d".to_string(), "9s7h".to_string(), "9s7s".to_string(), "9s8c".to_string(), "9s8d".to_string(), "9s8h".to_string(), "9s8s".to_string(), "9s9c".to_string(), "9s9d".to_string(), "9s9h".to_string(), "Tc2c".to_string(), "Tc2d".to_string(), "Tc2h".to_string(), "Tc2s".to_string(), "Tc3c".to_string(), "Tc3d".to_string(), "Tc3h".to_string(), "Tc3s".to_string(), "Tc4c".to_string(), "Tc4d".to_string(), "Tc4h".to_string(), "Tc4s".to_string(), "Tc5c".to_string(), "Tc5d".to_string(), "Tc5h".to_string(), "Tc5s".to_string(), "Tc6c".to_string(), "Tc6d".to_string(), "Tc6h".to_string(), "Tc6s".to_string(), "Tc7c".to_string(), "Tc7d".to_string(), "Tc7h".to_string(), "Tc7s".to_string(), "Tc8c".to_string(), "Tc8d".to_string(), "Tc8h".to_string(), "Tc8s".to_string(), "Tc9c".to_string(), "Tc9d".to_string(), "Tc9h".to_string(), "Tc9s".to_string(), "Td2c".to_string(), "Td2d".to_string(), "Td2h".to_string(), "Td2s".to_string(), "Td3c".to_string(), "Td3d".to_string(), "Td3h".to_string(), "Td3s".to_string(), "Td4c".to_string(), "Td4d".to_string(), "Td4h".to_string(), "Td4s".to_string(), "Td5c".to_string(), "Td5d".to_string(), "Td5h".to_string(), "Td5s".to_string(), "Td6c".to_string(), "Td6d".to_string(), "Td6h".to_string(), "Td6s".to_string(), "Td7c".to_string(), "Td7d".to_string(), "Td7h".to_string(), "Td7s".to_string(), "Td8c".to_string(), "Td8d".to_string(), "Td8h".to_string(), "Td8s".to_string(), "Td9c".to_string(), "Td9d".to_string(), "Td9h".to_string(), "Td9s".to_string(), "TdTc".to_string(), "Th2c".to_string(), "Th2d".to_string(), "Th2h".to_string(), "Th2s".to_string(), "Th3c".to_string(), "Th3d".to_string(), "Th3h".to_string(), "Th3s".to_string(), "Th4c".to_string(), "Th4d".to_string(), "Th4h".to_string(), "Th4s".to_string(), "Th5c".to_string(), "Th5d".to_string(), "Th5h".to_string(), "Th5s".to_string(), "Th6c".to_string(), "Th6d".to_string(), "Th6h".to_string(), "Th6s".to_string(), "Th7c".to_string(), "Th7d".to_string(), "Th7h".to_string(), "Th7s".to_string(), "Th8c".to_string(), "Th8d".to_string(), "Th8h".to_string(), "Th8s".to_string(), "Th9c".to_string(), "Th9d".to_string(), "Th9h".to_string(), "Th9s".to_string(), "ThTc".to_string(), "ThTd".to_string(), "Ts2c".to_string(), "Ts2d".to_string(), "Ts2h".to_string(), "Ts2s".to_string(), "Ts3c".to_string(), "Ts3d".to_string(), "Ts3h".to_string(), "Ts3s".to_string(), "Ts4c".to_string(), "Ts4d".to_string(), "Ts4h".to_string(), "Ts4s".to_string(), "Ts5c".to_string(), "Ts5d".to_string(), "Ts5h".to_string(), "Ts5s".to_string(), "Ts6c".to_string(), "Ts6d".to_string(), "Ts6h".to_string(), "Ts6s".to_string(), "Ts7c".to_string(), "Ts7d".to_string(), "Ts7h".to_string(), "Ts7s".to_string(), "Ts8c".to_string(), "Ts8d".to_string(), "Ts8h".to_string(), "Ts8s".to_string(), "Ts9c".to_string(), "Ts9d".to_string(), "Ts9h".to_string(), "Ts9s".to_string(), "TsTc".to_string(), "TsTd".to_string(), "TsTh".to_string(), "Jc2c".to_string(), "Jc2d".to_string(), "Jc2h".to_string(), "Jc2s".to_string(), "Jc3c".to_string(), "Jc3d".to_string(), "Jc3h".to_string(), "Jc3s".to_string(), "Jc4c".to_string(), "Jc4d".to_string(), "Jc4h".to_string(), "Jc4s".to_string(), "Jc5c".to_string(), "Jc5d".to_string(), "Jc5h".to_string(), "Jc5s".to_string(), "Jc6c".to_string(), "Jc6d".to_string(), "Jc6h".to_string(), "Jc6s".to_string(), "Jc7c".to_string(), "Jc7d".to_string(), "Jc7h".to_string(), "Jc7s".to_string(), "Jc8c".to_string(), "Jc8d".to_string(), "Jc8h".to_string(), "Jc8s".to_string(), "Jc9c".to_string(), "Jc9d".to_string(), "Jc9h".to_string(), "Jc9s".to_string(), "JcTc".to_string(), "JcTd".to_string(), "JcTh".to_string(), "JcTs".to_string(), "Jd2c".to_string(), "Jd2d".to_string(), "Jd2h".to_string(), "Jd2s".to_string(), "Jd3c".to_string(), "Jd3d".to_string(), "Jd3h".to_string(), "Jd3s".to_string(), "Jd4c".to_string(), "Jd4d".to_string(), "Jd4h".to_string(), "Jd4s".to_string(), "Jd5c".to_string(), "Jd5d".to_string(), "Jd5h".to_string(), "Jd5s".to_string(), "Jd6c".to_string(), "Jd6d".to_string(), "Jd6h".to_string(), "Jd6s".to_string(), "Jd7c".to_string(), "Jd7d".to_string(), "Jd7h".to_string(), "Jd7s".to_string(), "Jd8c".to_string(), "Jd8d".to_string(), "Jd8h".to_string(), "Jd8s".to_string(), "Jd9c".to_string(), "Jd9d".to_string(), "Jd9h".to_string(), "Jd9s".to_string(), "JdTc".to_string(), "JdTd".to_string(), "JdTh".to_string(), "JdTs".to_string(), "JdJc".to_string(), "Jh2c".to_string(), "Jh2d".to_string(), "Jh2h".to_string(), "Jh2s".to_string(), "Jh3c".to_string(), "Jh3d".to_string(), "Jh3h".to_string(), "Jh3s".to_string(), "Jh4c".to_string(), "Jh4d".to_string(), "Jh4h".to_string(), "Jh4s".to_string(), "Jh5c".to_string(), "Jh5d".to_string(), "Jh5h".to_string(), "Jh5s".to_string(), "Jh6c".to_string(), "Jh6d".to_string(), "Jh6h".to_string(), "Jh6s".to_string(), "Jh7c".to_string(), "Jh7d".to_string(), "Jh7h".to_string(), "Jh7s".to_string(), "Jh8c".to_string(), "Jh8d".to_string(), "Jh8h".to_string(), "Jh8s".to_string(), "Jh9c".to_string(), "Jh9d".to_string(), "Jh9h".to_string(), "Jh9s".to_string(), "JhTc".to_string(), "JhTd".to_string(), "JhTh".to_string(), "JhTs".to_string(), "JhJc".to_string(), "JhJd".to_string(), "Js2c".to_string(), "Js2d".to_string(), "Js2h".to_string(), "Js2s".to_string(), "Js3c".to_string(), "Js3d".to_string(), "Js3h".to_string(), "Js3s".to_string(), "Js4c".to_string(), "Js4d".to_string(), "Js4h".to_string(), "Js4s".to_string(), "Js5c".to_string(), "Js5d".to_string(), "Js5h".to_string(), "Js5s".to_string(), "Js6c".to_string(), "Js6d".to_string(), "Js6h".to_string(), "Js6s".to_string(), "Js7c".to_string(), "Js7d".to_string(), "Js7h".to_string(), "Js7s".to_string(), "Js8c".to_string(), "Js8d".to_string(), "Js8h".to_string(), "Js8s".to_string(), "Js9c".to_string(), "Js9d".to_string(), "Js9h".to_string(), "Js9s".to_string(), "JsTc".to_string(), "JsTd".to_string(), "JsTh".to_string(), "JsTs".to_string(), "JsJc".to_string(), "JsJd".to_string(), "JsJh".to_string(), "Qc2c".to_string(), "Qc2d".to_string(), "Qc2h".to_string(), "Qc2s".to_string(), "Qc3c".to_string(), "Qc3d".to_string(), "Qc3h".to_string(), "Qc3s".to_string(), "Qc4c".to_string(), "Qc4d".to_string(), "Qc4h".to_string(), "Qc4s".to_string(), "Qc5c".to_string(), "Qc5d".to_string(), "Qc5h".to_string(), "Qc5s".to_string(), "Qc6c".to_string(), "Qc6d".to_string(), "Qc6h".to_string(), "Qc6s".to_string(), "Qc7c".to_string(), "Qc7d".to_string(), "Qc7h".to_string(), "Qc7s".to_string(), "Qc8c".to_string(), "Qc8d".to_string(), "Qc8h".to_string(), "Qc8s".to_string(), "Qc9c".to_string(), "Qc9d".to_string(), "Qc9h".to_string(), "Qc9s".to_string(), "QcTc".to_string(), "QcTd".to_string(), "QcTh".to_string(), "QcTs".to_string(), "QcJc".to_string(), "QcJd".to_string(), "QcJh".to_string(), "QcJs".to_string(), "Qd2c".to_string(), "Qd2d".to_string(), "Qd2h".to_string(), "Qd2s".to_string(), "Qd3c".to_string(), "Qd3d".to_string(), "Qd3h".to_string(), "Qd3s".to_string(), "Qd4c".to_string(), "Qd4d".to_string(), "Qd4h".to_string(), "Qd4s".to_string(), "Qd5c".to_string(), "Qd5d".to_string(), "Qd5h".to_string(), "Qd5s".to_string(), "Qd6c".to_string(), "Qd6d".to_string(), "Qd6h".to_string(), "Qd6s".to_string(), "Qd7c".to_string(), "Qd7d".to_string(), "Qd7h".to_string(), "Qd7s".to_string(), "Qd8c".to_string(), "Qd8d".to_string(), "Qd8h".to_string(), "Qd8s".to_string(), "Qd9c".to_string(), "Qd9d".to_string(), "Qd9h".to_string(), "Qd9s".to_string(), "QdTc".to_string(), "QdTd".to_string(), "QdTh".to_string(), "QdTs".to_string(), "QdJc".to_string(), "QdJd".to_string(), "QdJh".to_string(), "QdJs".to_string(), "QdQc".to_string(), "Qh2c".to_string(), "Qh2d".to_string(), "Qh2h".to_string(), "Qh2s".to_string(), "Qh3c".to_string(), "Qh3d".to_string(), "Qh3h".to_string(), "Qh3s".to_string(), "Qh4c".to_string(), "Qh4d".to_string(), "Qh4h".to_string(), "Qh4s".to_string(), "Qh5c".to_string(), "Qh5d".to_string(), "Qh5h".to_string(), "Qh5s".to_string(), "Qh6c".to_string(), "Qh6d".to_string(), "Qh6h".to_string(), "Qh6s".to_string(), "Qh7c".to_string(), "Qh7d".to_string(), "Qh7h".to_string(), "Qh7s".to_string(), "Qh8c".to_string(), "Qh8d".to_string(), "Qh8h".to_string(), "Qh8s".to_string(), "Qh9c".to_string(), "Qh9d".to_string(), "Qh9h".to_string(), "Qh9s".to_string(), "QhTc".to_string(), "QhTd".to_string(), "QhTh".to_string(), "QhTs".to_string(), "QhJc".to_string(), "QhJd".to_string(), "QhJh".to_string(), "QhJs".to_string(), "QhQc".to_string(), "QhQd".to_string(), "Qs2c".to_string(), "Qs2d".to_string(), "Qs2h".to_string(), "Qs2s".to_string(), "Qs3c".to_string(), "Qs3d".to_string(), "Qs3h".to_string(), "Qs3s".to_string(), "Qs4c".to_string(), "Qs4d".to_string(), "Qs4h".to_string(), "Qs4s".to_string(), "Qs5c".to_string(), "Qs5d".to_string(), "Qs5h".to_string(), "Qs5s".to_string(), "Qs6c".to_string(), "Qs6d".to_string(), "Qs6h".to_string(), "Qs6s".to_string(), "Qs7c".to_string(), "Qs7d".to_string(), "Qs7h".to_string(), "Qs7s".to_string(), "Qs8c".to_string(), "Qs8d".to_string(), "Qs8h".to_string(), "Qs8s".to_string(), "Qs9c".to_string(), "Qs9d".to_string(), "Qs9h".to_string(), "Qs9s".to_string(), "QsTc".to_string(), "QsTd".to_string(), "QsTh".to_string(), "QsTs".to_string(), "QsJc".to_string(), "QsJd".to_string(), "QsJh".to_string(), "QsJs".to_string(), "QsQc".to_string(), "QsQd".to_string(), "QsQh".to_string(), "Kc2c".to_string(), "Kc2d".to_string(), "Kc2h".to_string(), "Kc2s".to_string(), "Kc3c".to_string(), "Kc3d".to_string(), "Kc3h".to_string(), "Kc3s".to_string(), "Kc4c".to_string(), "Kc4d".to_string(), "Kc4h".to_string(), "Kc4s".to_string(), "Kc5c".to_string(), "Kc5d".to_string(), "Kc5h".to_string(), "Kc5s".to_string(), "Kc6c".to_string(), "Kc6d".to_string(), "Kc6h".to_string(), "Kc6s".to_string(), "Kc7c".to_string(), "Kc7d".to_string(), "Kc7h".to_string(), "Kc7s".to_string(), "Kc8c".to_string(), "Kc8d".to_string(), "Kc8h".to_string(), "Kc8s".to_string(), "Kc9c".to_string(), "Kc9d".to_string(), "Kc9h".to_string(), "Kc9s".to_string(), "KcTc".to_string(), "KcTd".to_string(), "KcTh".to_string(), "KcTs".to_string(), "KcJc".to_string(), "KcJd".to_string(), "KcJh".to_string(), "KcJs".to_string(), "KcQc".to_string(), "KcQd".to_string(), "KcQh".to_string(), "KcQs".to_string(), "Kd2c".to_string(), "Kd2d".to_string(), "Kd2h".to_string(), "Kd2s".to_string(), "Kd3c".to_string(), "Kd3d".to_string(), "Kd3h".to_string(), "Kd3s".to_string(), "Kd4c".to_string(), "Kd4d".to_string(), "Kd4h".to_string(), "Kd4s".to_string(), "Kd5c".to_string(), "Kd5d".to_string(), "Kd5h".to_string(), "Kd5s".to_string(), "Kd6c".to_string(), "Kd6d".to_string(), "Kd6h".to_string(), "Kd6s".to_string(), "Kd7c".to_string(), "Kd7d".to_string(), "Kd7h".to_string(), "Kd7s".to_string(), "Kd8c".to_string(), "Kd8d".to_string(), "Kd8h".to_string(), "Kd8s".to_string(), "Kd9c".to_string(), "Kd9d".to_string(), "Kd9h".to_string(), "Kd9s".to_string(), "KdTc".to_string(), "KdTd".to_string(), "KdTh".to_string(), "KdTs".to_string(), "KdJc".to_string(), "KdJd".to_string(), "KdJh".to_string(), "KdJs".to_string(), "KdQc".to_string(), "KdQd".to_string(), "KdQh".to_string(), "KdQs".to_string(), "KdKc".to_string(), "Kh2c".to_string(), "Kh2d".to_string(), "Kh2h".to_string(), "Kh2s".to_string(), "Kh3c".to_string(), "Kh3d".to_string(), "Kh3h".to_string(), "Kh3s".to_string(), "Kh4c".to_string(), "Kh4d".to_string(), "Kh4h".to_string(), "Kh4s".to_string(), "Kh5c".to_string(), "Kh5d".to_string(), "Kh5h".to_string(), "Kh5s".to_string(), "Kh6c".to_string(), "Kh6d".to_string(), "Kh6h".to_string(), "Kh6s".to_string(), "Kh7c".to_string(), "Kh7d".to_string(), "Kh7h".to_string(), "Kh7s".to_string(), "Kh8c".to_string(), "Kh8d".to_string(), "Kh8h".to_string(), "Kh8s".to_string(), "Kh9c".to_string(), "Kh9d".to_string(), "Kh9h".to_string(), "Kh9s".to_string(), "KhTc".to_string(), "KhTd".to_string(), "KhTh".to_string(), "KhTs".to_string(), "KhJc".to_string(), "KhJd".to_string(), "KhJh".to_string(), "KhJs".to_string(), "KhQc".to_string(), "KhQd".to_string(), "KhQh".to_string(), "KhQs".to_string(), "KhKc".to_string(), "KhKd".to_string(), "Ks2c".to_string(), "Ks2d".to_string(), "Ks2h".to_string(), "Ks2s".to_string(), "Ks3c".to_string(), "Ks3d".to_string(), "Ks3h".to_string(), "Ks3s".to_string(), "Ks4c".to_string(), "Ks4d".to_string(), "Ks4h".to_string(), "Ks4s".to_string(), "Ks5c".to_string(), "Ks5d".to_string(), "Ks5h".to_string(), "Ks5s".to_string(), "Ks6c".to_string(), "Ks6d".to_string(), "Ks6h".to_string(), "Ks6s".to_string(), "Ks7c".to_string(), "Ks7d".to_string(), "Ks7h".to_string(), "Ks7s".to_string(), "Ks8c".to_string(), "Ks8d".to_string(), "Ks8h".to_string(), "Ks8s".to_string(), "Ks9c".to_string(), "Ks9d".to_string(), "Ks9h".to_string(), "Ks9s".to_string(), "KsTc".to_string(), "KsTd".to_string(), "KsTh".to_string(), "KsTs".to_string(), "KsJc".to_string(), "KsJd".to_string(), "KsJh".to_string(), "KsJs".to_string(), "KsQc".to_string(), "KsQd".to_string(), "KsQh".to_string(), "KsQs".to_string(), "KsKc".to_string(), "KsKd".to_string(), "KsKh".to_string(), "Ac2c".to_string(), "Ac2d".to_string(), "Ac2h".to_string(), "Ac2s".to_string(), "Ac3c".to_string(), "Ac3d".to_string(), "Ac3h".to_string(), "Ac3s".to_string(), "Ac4c".to_string(), "Ac4d".to_string(), "Ac4h".to_string(), "Ac4s".to_string(), "Ac5c".to_string(), "Ac5d".to_string(), "Ac5h".to_string(), "Ac5s".to_string(), "Ac6c".to_string(), "Ac6d".to_string(), "Ac6h".to_string(), "Ac6s".to_string(), "Ac7c".to_string(), "Ac7d".to_string(), "Ac7h".to_string(), "Ac7s".to_string(), "Ac8c".to_string(), "Ac8d".to_string(), "Ac8h".to_string(), "Ac8s".to_string(), "Ac9c".to_string(), "Ac9d".to_string(), "Ac9h".to_string(), "Ac9s".to_string(), "AcTc".to_string(), "AcTd".to_string(), "AcTh".to_string(), "AcTs".to_string(), "AcJc".to_string(), "AcJd".to_string(), "AcJh".to_string(), "AcJs".to_string(), "AcQc".to_string(), "AcQd".to_string(), "AcQh".to_string(), "AcQs".to_string(), "AcKc".to_string(), "AcKd".to_string(), "AcKh".to_string(), "AcKs".to_string(), "Ad2c".to_string(), "Ad2d".to_string(), "Ad2h".to_string(), "Ad2s".to_string(), "Ad3c".to_string(), "Ad3d".to_string(), "Ad3h".to_string(), "Ad3s".to_string(), "Ad4c".to_string(), "Ad4d".to_string(), "Ad4h".to_string(), "Ad4s".to_string(), "Ad5c".to_string(), "Ad5d".to_string(), "Ad5h".to_string(), "Ad5s".to_string(), "Ad6c".to_string(), "Ad6d".to_string(), "Ad6h".to_string(), "Ad6s".to_string(), "Ad7c".to_string(), "Ad7d".to_string(), "Ad7h".to_string(), "Ad7s".to_string(), "Ad8c".to_string(), "Ad8d".to_string(), "Ad8h".to_string(), "Ad8s".to_string(), "Ad9c".to_string(), "Ad9d".to_string(), "Ad9h".to_string(), "Ad9s".to_string(), "AdTc".to_string(), "AdTd".to_string(), "AdTh".to_string(), "AdTs".to_string(), "AdJc".to_string(), "AdJd".to_string(), "AdJh".to_string(), "AdJs".to_string(), "AdQc".to_string(), "AdQd".to_string(), "AdQh".to_string(), "AdQs".to_string(), "AdKc".to_string(), "AdKd".to_string(), "AdKh".to_string(), "AdKs".to_string(), "AdAc".to_string(), "Ah2c".to_string(), "Ah2d".to_string(), "Ah2h".to_string(), "Ah2s".to_string(), "Ah3c".to_string(), "Ah3d".to_string(), "Ah3h".to_string(), "Ah3s".to_string(), "Ah4c".to_string(), "Ah4d".to_string(), "Ah4h".to_string(), "Ah4s".to_string(), "Ah5c".to_string(), "Ah5d".to_string(), "Ah5h".to_string(), "Ah5s".to_string(), "Ah6c".to_string(), "Ah6d".to_string(), "Ah6h".to_string(), "Ah6s".to_string(), "Ah7c".to_string(), "Ah7d".to_string(), "Ah7h".to_string(), "Ah7s".to_string(), "Ah8c".to_string(), "Ah8d".to_string(), "Ah8h".to_string(), "Ah8s".to_string(), "Ah9c".to_string(), "Ah9d".to_string(), "Ah9h".to_string(), "Ah9s".to_string(), "AhTc".to_string(), "AhTd".to_string(), "AhTh".to_string(), "AhTs".to_string(), "AhJc".to_string(), "AhJd".to_string(), "AhJh".to_string(), "AhJs".to_string(), "AhQc".to_string(), "AhQd".to_string(), "AhQh".to_string(), "AhQs".to_string(), "AhKc".to_string(), "AhKd".to_string(), "AhKh".to_string(), "AhKs".to_string(), "AhAc".to_string(), "AhAd".to_string(), "As2c".to_string(), "As2d".to_string(), "As2h".to_string(), "As2s".to_string(), "As3c".to_string(), "As3d".to_string(), "As3h".to_string(), "As3s".to_string(), "As4c".to_string(), "As4d".to_string(), "As4h".to_string(), "As4s".to_string(), "As5c".to_string(), "As5d".to_string(), "As5h".to_string(), "As5s".to_string(), "As6c".to_string(), "As6d".to_string(), "As6h".to_string(), "As6s".to_string(), "As7c".to_string(), "As7d".to_string(), "As7h".to_string(), "As7s".to_string(), "As8c".to_string(), "As8d".to_string(), "As8h".to_string(), "As8s".to_string(), "As9c".to_string(), "As9d".to_string(), "As9h".to_string(), "As9s".to_string(), "AsTc".to_string(), "AsTd".to_string(), "AsTh".to_string(), "AsTs".to_string(), "AsJc".to_string(), "AsJd".to_string(), "AsJh".to_string(), "AsJs".to_string(), "AsQc".to_string(), "AsQd".to_string(), "AsQh".to_string(), "AsQs".to_string(), "AsKc".to_string(), "AsKd".to_string(), "AsKh".to_string(), "AsKs".to_string(), "AsAc".to_string(), "AsAd".to_string(), "AsAh".to_string()];
+        let mut tree_information = TreeInformation { eff_stack: None, pot: None, oop_range: None, ip_range: None, lines: None, board: None, depth_limit: None };
+        let hand_order = hand_order();
         let mut hand_order_map = HashMap::new();
         
         for (i,hand) in hand_order.iter().enumerate() {
             hand_order_map.insert(hand.clone(), i);
         }
         
-        CliSession { tree_information, end_string: "".to_string(), accuracy: Accuracy::Chips(0.0), hand_order, hand_order_map, trainer: None }
+        CliSession { protocol: Protocol::Upi, tree_information, end_string: "".to_string(), accuracy: Accuracy::Chips(0.0), hand_order, hand_order_map, trainer: None, checkpoint: None, algorithm: CfrAlgorithm::default(), sampling: (ChanceSampling::Full, 0.0), pruning_interval: None, info_freq: 25, parent_trainer: None, practice: None, file_access: FileAccess::Any, script_stack: vec![], keep_going: false }
+    }
+    
+    pub fn set_protocol(&mut self, protocol: Protocol) {
+        self.protocol = protocol;
+    }
+    
+    pub fn set_file_access(&mut self, file_access: FileAccess) {
+        self.file_access = file_access;
+    }
+    
+    pub fn set_keep_going(&mut self, keep_going: bool) {
+        self.keep_going = keep_going;
     }
     
     pub fn start(&mut self) {
         let mut user_input = String::new();
-        println!("OpenSolver free (piosolver) 0.0.1 (Jul 29 2022, 17:48)");
-        println!("(C) John Doe");
+        if self.protocol == Protocol::Upi {
+            info!("OpenSolver free (piosolver) 0.0.1 (Jul 29 2022, 17:48)");
+            info!("(C) John Doe");
+        }
         while true {
             user_input.clear();
             io::stdout().flush().expect("Cannot flush stdout");
-            io::stdin()
+            let read = io::stdin()
                 .read_line(&mut user_input)
                 .expect("Cannot read user input");
+            // end of input
+            if read == 0 {
+                break;
+            }
             trim_newline(&mut user_input);
-            
-            if user_input.len() != 0 && user_input.chars().nth(0).unwrap() != '#' {
-                let mut split = user_input.as_str().split(" ");
-                let input_params = split.collect::<Vec<&str>>();
-                match input_params[0] {
-                    "set_end_string" => set_end_string(&input_params, &mut self.end_string),
-                    "set_accuracy" => set_accuracy(&input_params, &mut self.accuracy),
-                    "set_eff_stack" => set_eff_stack(&input_params, &mut self.tree_information),
-                    "set_pot" => set_pot(&input_params, &mut self.tree_information),
-                    "set_board" => set_board(&input_params, &mut self.tree_information),
-                    "show_effective_stack" => {
-                        if let Some(x) = self.tree_information.eff_stack {
-                            println!("{}",x);
-                        } else {
-                            println!("ERROR: {} missing/incorrect tree", input_params[0])
-                        }
-                    },
-                    "show_children" => show_children(&input_params, &self.trainer),
-                    "show_range" => show_range(&input_params, &self.trainer, &self.hand_order_map),
-                    "show_strategy" => show_strategy(&input_params, &self.trainer, &self.hand_order_map),
-                    "calc_line_freq" => calc_line_freq(&input_params, &self.trainer, &self.hand_order_map),
-                    "calc_eq_node" => calc_eq_node(&input_params, &self.trainer),
-                    "calc_ev" => calc_ev(&input_params, &self.trainer),
-                    "show_node" => show_node(&input_params, &self.trainer),
-                    "add_line" => add_line(&input_params, &mut self.tree_information),
-                    "clear_lines" => clear_lines(&mut self.tree_information),
-                    "build_tree" => build_tree(&mut self.tree_information, &mut self.trainer),
-                    "is_ready" => println!("{} ok!", input_params[0]),
-                    "set_isomorphism" => println!("{} ok!", input_params[0]), // TODO: fix this
-                    "set_threads" => println!("{} ok!", input_params[0]), // TODO: fix this
-                    "set_recalc_accuracy" => println!("{} ok!", input_params[0]), // TODO: fix this
-                    "show_hand_order" => println!("{:?}",self.hand_order),
-                    "set_range" => set_range(&input_params, &mut self.tree_information,&self.hand_order),
-                    "go" => go(&input_params, &mut self.trainer, &self.accuracy, &self.end_string),
-                    "exit" => break,
-                    _ => println!("ERROR: Command {} not recognized", input_params[0]),
-                };
-                if self.end_string.len() > 0 && input_params[0] != "go" {
-                    println!("{}",self.end_string);
+            if self.run_line(&user_input) == false {
+                break;
+            }
+        }
+    }
+    
+    /// Runs the commands of a script file like typed input, returns false if the script exits the session.
+    /// Relative paths of nested scripts are relative to the script loading them. The script stops at the first
+    /// command that fails unless keep_going is set
+    pub fn run_script(&mut self, path: &str) -> Result<bool, String> {
+        if self.script_stack.len() >= MAX_SCRIPT_DEPTH {
+            return Err("scripts nested too deep".to_string());
+        }
+        let path = match self.script_stack.last() {
+            Some(parent) => parent.parent().unwrap_or(Path::new("")).join(path),
+            None => PathBuf::from(path),
+        };
+        let script = fs::read_to_string(&path).map_err(|e| format!("couldn't read {}: {}", path.display(), e))?;
+        
+        self.script_stack.push(path);
+        let mut result = Ok(true);
+        for (i, line) in script.lines().enumerate() {
+            let line = line.trim_end_matches('\r');
+            let errors = error_count();
+            if self.run_line(line) == false {
+                result = Ok(false);
+                break;
+            }
+            if error_count() > errors && !self.keep_going {
+                result = Err(format!("{} stopped at line {}: {}", self.script_stack.last().unwrap().display(), i + 1, line));
+                break;
+            }
+        }
+        self.script_stack.pop();
+        result
+    }
+    
+    // load_script <file> runs the commands of the file, returns false if the script exits the session
+    fn load_script(&mut self, input_params: &Vec<&str>) -> bool {
+        if input_params.len() == 1 || input_params[1] == "" {
+            output!("ERROR: {} incorrect or missing argument", input_params[0]);
+            return true;
+        }
+        match self.run_script(input_params[1]) {
+            Ok(true) => {
+                info!("{} ok!", input_params[0]);
+                true
+            },
+            Ok(false) => false,
+            Err(e) => {
+                output!("ERROR: {} {}", input_params[0], e);
+                true
+            },
+        }
+    }
+    
+    /// Runs one command, returns false on exit
+    pub fn run_line(&mut self, user_input: &str) -> bool {
+        log_input(user_input);
+        if self.protocol == Protocol::Json {
+            return user_input.trim().len() == 0 || self.handle_json(user_input, None, &mut |value| {
+                if value["ok"] == false {
+                    count_error();
                 }
+                output!("{}", value);
+            });
+        }
+        if user_input.len() != 0 && user_input.chars().nth(0).unwrap() != '#' {
+            let input_params = user_input.split(" ").collect::<Vec<&str>>();
+            match input_params[0] {
+                "set_end_string" => set_end_string(&input_params, &mut self.end_string),
+                "set_accuracy" => set_accuracy(&input_params, &mut self.accuracy),
+                "set_eff_stack" => set_eff_stack(&input_params, &mut self.tree_information),
+                "set_pot" => set_pot(&input_params, &mut self.tree_information),
+                "set_board" => set_board(&input_params, &mut self.tree_information),
+                "show_effective_stack" => {
+                    if let Some(x) = self.tree_information.eff_stack {
+                        output!("{}",x);
+                    } else {
+                        output!("ERROR: {} missing/incorrect tree", input_params[0])
+                    }
+                },
+                "show_children" => show_children(&input_params, &self.trainer),
+                "show_range" => show_range(&input_params, &self.trainer),
+                "show_strategy" => show_strategy(&input_params, &self.trainer),
+                "calc_line_freq" => calc_line_freq(&input_params, &self.trainer),
+                "calc_eq_node" => calc_eq_node(&input_params, &self.trainer),
+                "calc_ev" => calc_ev(&input_params, &self.trainer),
+                "calc_range_eq" => calc_range_eq(&input_params, &self.tree_information),
+                "show_node" => show_node(&input_params, &self.trainer),
+                "calc_exploitability" => calc_exploitability(&input_params, &self.trainer),
+                "set_depth_limit" => set_depth_limit(&input_params, &mut self.tree_information),
+                "add_line" => add_line(&input_params, &mut self.tree_information),
+                "clear_lines" => clear_lines(&mut self.tree_information),
+                "build_tree" => build_tree(&input_params, &mut self.tree_information, &mut self.trainer),
+                "save_solution" => save_solution(&input_params, &self.trainer),
+                "export_strategy" => export_strategy_cmd(&input_params, &self.trainer),
+                "report" => report(&input_params, &self.trainer),
+                "simplify_strategy" => simplify_strategy_cmd(&input_params, &mut self.trainer),
+                "unlock_strategy" => unlock_strategy_cmd(&input_params, &mut self.trainer),
+                "compare_solutions" => compare_solutions_cmd(&input_params, &self.tree_information, &self.algorithm),
+                "practice_hand" => practice_hand(&input_params, &self.trainer, &mut self.practice),
+                "practice_action" => practice_action(&input_params, &self.trainer, &mut self.practice),
+                "lookup_hand" => lookup_hand_cmd(&input_params, &self.trainer),
+                "warm_start" => warm_start_cmd(&input_params, &mut self.trainer),
+                "is_ready" => info!("{} ok!", input_params[0]),
+                "set_protocol" => set_protocol(&input_params, &mut self.protocol),
+                "set_isomorphism" => info!("{} ok!", input_params[0]), // TODO: fix this
+                "set_threads" => info!("{} ok!", input_params[0]), // TODO: fix this
+                "set_recalc_accuracy" => info!("{} ok!", input_params[0]), // TODO: fix this
+                "show_hand_order" => output!("{:?}",self.hand_order),
+                "set_range" => set_range(&input_params, &mut self.tree_information,&self.hand_order),
+                "go" => go(&input_params, &mut self.trainer, &self.accuracy, &self.end_string, &self.checkpoint, &self.algorithm, &self.sampling, &self.pruning_interval, self.info_freq),
+                "set_pruning" => set_pruning(&input_params, &mut self.pruning_interval),
+                "set_info_freq" => set_info_freq(&input_params, &mut self.info_freq),
+                "set_sampling" => set_sampling(&input_params, &mut self.sampling),
+                "set_algorithm" => set_algorithm(&input_params, &mut self.algorithm),
+                "show_algorithm" => output!("{}", self.algorithm),
+                "set_checkpoint" => set_checkpoint(&input_params, &mut self.checkpoint),
+                "save_checkpoint" => save_checkpoint_cmd(&input_params, &self.trainer),
+                "load_checkpoint" => load_checkpoint_cmd(&input_params, &mut self.trainer, &self.algorithm),
+                "list_flops" => list_flops(&input_params),
+                "solve_batch" => solve_batch_cmd(&input_params, &self.tree_information, &self.accuracy, &self.algorithm, &self.sampling, &self.pruning_interval, self.info_freq),
+                "resolve_subgame" => resolve_subgame(&input_params, &self.tree_information, &mut self.trainer, &mut self.parent_trainer, &self.hand_order, &self.hand_order_map),
+                "load_script" => {
+                    if self.load_script(&input_params) == false {
+                        return false;
+                    }
+                },
+                "exit_subgame" => exit_subgame(&input_params, &mut self.trainer, &mut self.parent_trainer),
+                "exit" => return false,
+                _ => output!("ERROR: Command {} not recognized", input_params[0]),
+            };
+            if self.end_string.len() > 0 && input_params[0] != "go" {
+                output!("{}",self.end_string);
             }
         }
+        true
+    }
+}
+
+// set_protocol json switches to the JSON lines protocol for the rest of the session
+fn set_protocol(input_params: &Vec<&str>, protocol: &mut Protocol) {
+    if input_params.len() == 1 {
+        output!("ERROR: {} incorrect or missing argument", input_params[0]);
+        return;
     }
+    match input_params[1] {
+        "upi" => info!("{} ok!", input_params[0]),
+        "json" => {
+            *protocol = Protocol::Json;
+            info!("{} ok!", input_params[0]);
+        },
+        _ => output!("ERROR: {} incorrect or missing argument", input_params[0]),
+    };
 }
 
 fn set_end_string(input_params: &Vec<&str>, end_string: &mut String) {
     if input_params.len() == 1 {
-        println!("ERROR: {} incorrect or missing argument", input_params[0]);
+        output!("ERROR: {} incorrect or missing argument", input_params[0]);
     } else {
         *end_string = input_params[1].to_string();
-        println!("{} ok!", input_params[0]);
+        info!("{} ok!", input_params[0]);
     }
 }
 
 fn set_accuracy(input_params: &Vec<&str>, accuracy: &mut Accuracy) {
     // TODO: add optional argument chips or fraction
     if input_params.len() == 1 {
-        println!("ERROR: {} incorrect or missing argument", input_params[0]);
+        output!("ERROR: {} incorrect or missing argument", input_params[0]);
     } else if input_params[1].parse::<f64>().is_ok() == false || input_params[1].parse::<f64>().unwrap() < 0.0 {
-        println!("ERROR: Invalid value");
+        output!("ERROR: Invalid value");
     } else {
         let mut accuracy_type = if input_params.len() > 2 {
             match input_params[2] {
@@ -127,209 +303,228 @@ fn set_accuracy(input_params: &Vec<&str>, accuracy: &mut Accuracy) {
             Accuracy::Chips(input_params[1].parse::<f64>().unwrap())
         };
         *accuracy = accuracy_type;
-        println!("{} ok!", input_params[0]);
+        info!("{} ok!", input_params[0]);
     }
 }
 
 fn set_eff_stack(input_params: &Vec<&str>, tree_information: &mut TreeInformation) {
     if input_params.len() == 1 {
-        println!("ERROR: {} incorrect or missing argument", input_params[0]);
+        output!("ERROR: {} incorrect or missing argument", input_params[0]);
     } else if input_params[1].parse::<u32>().is_ok() == false {
-        println!("ERROR: Invalid value");
+        output!("ERROR: Invalid value");
     } else {
         tree_information.eff_stack = Some(input_params[1].parse::<u32>().unwrap());
-        println!("{} ok!", input_params[0]);
+        info!("{} ok!", input_params[0]);
     }
 }
 
 fn set_pot(input_params: &Vec<&str>, tree_information: &mut TreeInformation) {
     if input_params.len() < 4 {
-        println!("ERROR: {} incorrect or missing argument", input_params[0]);
+        output!("ERROR: {} incorrect or missing argument", input_params[0]);
     } else if input_params[3].parse::<u32>().is_ok() == false {
-        println!("ERROR: Invalid value");
+        output!("ERROR: Invalid value");
     } else {
         tree_information.pot = Some(input_params[3].parse::<u32>().unwrap());
-        println!("{} ok!", input_params[0]);
+        info!("{} ok!", input_params[0]);
     }
 }
 
 fn set_board(input_params: &Vec<&str>, tree_information: &mut TreeInformation) {
     //TODO: error handling for invalid boards
     if input_params.len() == 1 {
-        println!("ERROR: {} incorrect or missing argument", input_params[0]);
+        output!("ERROR: {} incorrect or missing argument", input_params[0]);
     } else {
         tree_information.board = Some(input_params[1].to_string());
-        println!("{} ok!", input_params[0]);
+        info!("{} ok!", input_params[0]);
     }
 }
 
 fn set_range(input_params: &Vec<&str>, tree_information: &mut TreeInformation, hand_order: &Vec<String>) {
     if input_params.len() < 1328 {
-        println!("ERROR: {} incorrect or missing argument", input_params[0]);
+        output!("ERROR: {} incorrect or missing argument", input_params[0]);
     } else if (input_params[1] != "OOP" && input_params[1] != "IP") {
-        println!("ERROR: {} incorrect player", input_params[0]);
+        output!("ERROR: {} incorrect player", input_params[0]);
     } else {
-        let mut hand_range_string = String::new();
-        for i in 0..1326 {
-            let hand_weight = if input_params[i+2].parse::<f64>().is_ok() == false || input_params[i+2].parse::<f64>().unwrap() < 0.0 {
-                0.0
-            } else if input_params[i+2].parse::<f64>().unwrap() > 1.0 {
-                1.0
-            } else {
-                input_params[i+2].parse::<f64>().unwrap()
-            };
-            
-            if hand_weight > 0.0 {
-                let new_hand = hand_order[i].clone();
-                let new_weight = (hand_weight*100.0) as u8;
-                hand_range_string = format!("{}{}@{},",hand_range_string,new_hand,new_weight);
-            }
-        }
-        hand_range_string.pop();
-        let range = HandRange::from_string(hand_range_string);
+        let weights: Vec<f64> = input_params[2..1328].iter().map(|x| x.parse::<f64>().unwrap_or(0.0)).collect();
+        let range = range_from_weights(&weights, hand_order);
         if input_params[1] == "OOP" {
             tree_information.oop_range = Some(range);
         } else {
             tree_information.ip_range = Some(range);
         }
-        println!("{} ok!", input_params[0]);
+        info!("{} ok!", input_params[0]);
     }
 }
 
+// weights in hand order, clamped to [0, 1]
+pub(crate) fn range_from_weights(weights: &[f64], hand_order: &Vec<String>) -> HandRange {
+    let mut hand_range_string = String::new();
+    for (i, weight) in weights.iter().enumerate() {
+        let hand_weight = if !(*weight > 0.0) {
+            0.0
+        } else if *weight > 1.0 {
+            1.0
+        } else {
+            *weight
+        };
+        
+        if hand_weight > 0.0 {
+            let new_hand = hand_order[i].clone();
+            let new_weight = (hand_weight*100.0) as u8;
+            hand_range_string = format!("{}{}@{},",hand_range_string,new_hand,new_weight);
+        }
+    }
+    hand_range_string.pop();
+    HandRange::from_string(hand_range_string)
+}
+
 fn show_children(input_params: &Vec<&str>, trainer_option: &Option<Trainer>) {
     match trainer_option {
         Some(trainer) => {
             if input_params.len() == 1 {
-                println!("ERROR: {} incorrect or missing argument", input_params[0]);
+                output!("ERROR: {} incorrect or missing argument", input_params[0]);
             } else {
                 let children_info = trainer.root.get_children(input_params[1].to_string(), &trainer.range_manager);
                 for (i,info) in children_info.iter().enumerate() {
-                    println!("child {}:", i);
-                    println!("{}",info);
+                    output!("child {}:", i);
+                    output!("{}",info);
                 }
             }
         },
-        None => println!("ERROR: Built tree not found"),
+        None => output!("ERROR: Built tree not found"),
     };
 }
 
-fn show_range(input_params: &Vec<&str>, trainer_option: &Option<Trainer>, hand_order_map: &HashMap<String, usize>) {
+fn parse_player(param: &str) -> Option<bool> {
+    match param {
+        "oop" | "OOP" => Some(true),
+        "ip" | "IP" => Some(false),
+        _ => None,
+    }
+}
+
+fn show_range(input_params: &Vec<&str>, trainer_option: &Option<Trainer>) {
     match trainer_option {
         Some(trainer) => {
-            if input_params.len() < 3 {
-                println!("ERROR: {} incorrect or missing argument", input_params[0]);
+            if input_params.len() < 3 || parse_player(input_params[1]).is_none() {
+                output!("ERROR: {} incorrect or missing argument", input_params[0]);
             } else {
-                let oop = if input_params[1] == "oop" || input_params[1] == "OOP" {
-                    true
-                } else if input_params[1] == "ip" || input_params[1] == "IP" {
-                    false
-                } else {
-                    println!("ERROR: {} incorrect or missing argument", input_params[0]);
-                    return;
+                match get_range(trainer, parse_player(input_params[1]).unwrap(), input_params[2]) {
+                    Ok(range) => {
+                        output!("{}", join_values(&range));
+                    },
+                    Err(e) => output!("ERROR: {} {}", input_params[0], e),
                 };
-                let range = trainer.root.get_range(oop, input_params[2].to_string(), &trainer.range_manager, hand_order_map);
-                for el in &range {
-                     print!("{} ", el);
-                }
-                println!("");
             }
         },
-        None => println!("ERROR: Built tree not found"),
+        None => output!("ERROR: Built tree not found"),
     };
 }
 
-fn show_strategy(input_params: &Vec<&str>, trainer_option: &Option<Trainer>, hand_order_map: &HashMap<String, usize>) {
+fn show_strategy(input_params: &Vec<&str>, trainer_option: &Option<Trainer>) {
     match trainer_option {
         Some(trainer) => {
             if input_params.len() < 2 {
-                println!("ERROR: {} incorrect or missing argument", input_params[0]);
+                output!("ERROR: {} incorrect or missing argument", input_params[0]);
             } else {
-                let strategy = trainer.root.get_strategy(input_params[1].to_string(), &trainer.range_manager, hand_order_map);
-                for action_strategy in &strategy {
-                    for el in action_strategy {
-                        print!("{} ", el);
-                    }
-                    println!("");
-                }
+                match get_strategy(trainer, input_params[1]) {
+                    Ok(strategy) => {
+                        for action_strategy in &strategy.frequencies {
+                            output!("{}", join_values(action_strategy));
+                        }
+                    },
+                    Err(e) => output!("ERROR: {} {}", input_params[0], e),
+                };
             }
         },
-        None => println!("ERROR: Built tree not found"),
+        None => output!("ERROR: Built tree not found"),
     };
 }
 
-fn calc_line_freq(input_params: &Vec<&str>, trainer_option: &Option<Trainer>, hand_order_map: &HashMap<String, usize>) {
+fn calc_line_freq(input_params: &Vec<&str>, trainer_option: &Option<Trainer>) {
     match trainer_option {
         Some(trainer) => {
             if input_params.len() < 2 {
-                println!("ERROR: {} incorrect or missing argument", input_params[0]);
+                output!("ERROR: {} incorrect or missing argument", input_params[0]);
             } else {
-                let freq = trainer.root.get_line_freq(input_params[1].to_string(), &trainer.range_manager, hand_order_map);
-                println!("{}",freq);
+                match get_line_freq(trainer, input_params[1]) {
+                    Ok(freq) => output!("{}", freq),
+                    Err(e) => output!("ERROR: {} {}", input_params[0], e),
+                };
             }
         },
-        None => println!("ERROR: Built tree not found"),
+        None => output!("ERROR: Built tree not found"),
     };
 }
 
+// values separated by spaces
+fn join_values(values: &[f64]) -> String {
+    values.iter().map(|x| format!("{} ", x)).collect()
+}
+
+fn print_hand_values(hand_values: &HandValues) {
+    output!("{}", join_values(&hand_values.values));
+    output!("{}", join_values(&hand_values.matchups));
+    output!("{}", hand_values.total);
+}
+
+// calc_eq_node OOP|IP <line> prints equity and matchups of every hand and the range equity
 fn calc_eq_node(input_params: &Vec<&str>, trainer_option: &Option<Trainer>) {
     match trainer_option {
         Some(trainer) => {
-            if input_params.len() < 3 {
-                println!("ERROR: {} incorrect or missing argument", input_params[0]);
+            if input_params.len() < 3 || parse_player(input_params[1]).is_none() {
+                output!("ERROR: {} incorrect or missing argument", input_params[0]);
             } else {
-                let oop = if input_params[1] == "oop" || input_params[1] == "OOP" {
-                    true
-                } else if input_params[1] == "ip" || input_params[1] == "IP" {
-                    false
-                } else {
-                    println!("ERROR: {} incorrect or missing argument", input_params[0]);
-                    return;
+                match get_equity(trainer, parse_player(input_params[1]).unwrap(), input_params[2]) {
+                    Ok(equities) => print_hand_values(&equities),
+                    Err(e) => output!("ERROR: {} {}", input_params[0], e),
                 };
-                let equities = vec![0.0; 1326];
-                let matchups = vec![0.0; 1326];
-                let total = 0.0;
-                for el in &equities {
-                     print!("{} ", el);
-                }
-                println!("");
-                for el in &matchups {
-                     print!("{} ", el);
-                }
-                println!("");
-                println!("{}",total);
             }
         },
-        None => println!("ERROR: Built tree not found"),
+        None => output!("ERROR: Built tree not found"),
     };
 }
 
+// calc_ev OOP|IP <line> prints EV and matchups of every hand and the range EV
 fn calc_ev(input_params: &Vec<&str>, trainer_option: &Option<Trainer>) {
     match trainer_option {
         Some(trainer) => {
-            if input_params.len() < 3 {
-                println!("ERROR: {} incorrect or missing argument", input_params[0]);
+            if input_params.len() < 3 || parse_player(input_params[1]).is_none() {
+                output!("ERROR: {} incorrect or missing argument", input_params[0]);
             } else {
-                let oop = if input_params[1] == "oop" || input_params[1] == "OOP" {
-                    true
-                } else if input_params[1] == "ip" || input_params[1] == "IP" {
-                    false
-                } else {
-                    println!("ERROR: {} incorrect or missing argument", input_params[0]);
-                    return;
+                match get_ev(trainer, parse_player(input_params[1]).unwrap(), input_params[2]) {
+                    Ok(evs) => print_hand_values(&evs),
+                    Err(e) => output!("ERROR: {} {}", input_params[0], e),
                 };
-                let equities = vec![0.0; 1326];
-                let matchups = vec![0.0; 1326];
-                for el in &equities {
-                     print!("{} ", el);
-                }
-                println!("");
-                for el in &matchups {
-                     print!("{} ", el);
-                }
-                println!("");
             }
         },
-        None => println!("ERROR: Built tree not found"),
+        None => output!("ERROR: Built tree not found"),
+    };
+}
+
+fn calc_range_eq(input_params: &Vec<&str>, tree_information: &TreeInformation) {
+    // optional argument overrides the board set with set_board
+    let board = if input_params.len() > 1 && input_params[1] != "" {
+        Some(input_params[1].to_string())
+    } else {
+        tree_information.board.clone()
+    };
+    
+    match (&tree_information.oop_range, &tree_information.ip_range, board) {
+        (Some(oop_range), Some(ip_range), Some(board)) => {
+            if board.len() < 6 || board.len() > 10 || board.len() % 2 != 0 || get_card_mask(&board).count_ones() as usize != board.len() / 2 {
+                output!("ERROR: {} invalid board", input_params[0]);
+                return;
+            }
+            let range_equity = calc_range_equity(oop_range, ip_range, &board);
+            output!("{} {}", range_equity.oop_equity, range_equity.ip_equity);
+            for &oop in [true, false].iter() {
+                let distribution = range_equity.get_distribution(oop);
+                output!("{}", join_values(&distribution.iter().map(|x| x.0).collect::<Vec<f64>>()));
+                output!("{}", join_values(&distribution.iter().map(|x| x.1).collect::<Vec<f64>>()));
+            }
+        },
+        _ => output!("ERROR: {} missing ranges or board", input_params[0]),
     };
 }
 
@@ -337,19 +532,86 @@ fn show_node(input_params: &Vec<&str>, trainer_option: &Option<Trainer>) {
     match trainer_option {
         Some(trainer) => {
             if input_params.len() == 1 {
-                println!("ERROR: {} incorrect or missing argument", input_params[0]);
+                output!("ERROR: {} incorrect or missing argument", input_params[0]);
             } else {
                 let node_info = trainer.root.get_node(input_params[1].to_string(), &trainer.range_manager);
-                println!("{}",node_info);
+                output!("{}",node_info);
+            }
+        },
+        None => output!("ERROR: Built tree not found"),
+    };
+}
+
+// calc_exploitability <line> prints how much each player gains by best responding from the node on,
+// per street, in chips per matchup reaching the node
+fn calc_exploitability(input_params: &Vec<&str>, trainer_option: &Option<Trainer>) {
+    match trainer_option {
+        Some(trainer) => {
+            if input_params.len() == 1 {
+                output!("ERROR: {} incorrect or missing argument", input_params[0]);
+                return;
+            }
+            let node_exploitability = match get_node_exploitability(trainer, input_params[1]) {
+                Ok(x) => x,
+                Err(e) => {
+                    output!("ERROR: {} {}", input_params[0], e);
+                    return;
+                },
+            };
+            
+            let streets = ["flop", "turn", "river"];
+            let first_street = node_exploitability.board.len() / 2 - 3;
+            for (name, gains) in [("OOP", &node_exploitability.oop_gains), ("IP", &node_exploitability.ip_gains)].iter() {
+                for (i, gain) in gains.iter().enumerate() {
+                    output!("{} {}: {}", name, streets[first_street + i], gain);
+                }
+                output!("{} total: {}", name, gains.iter().sum::<f64>());
             }
+            let exploitability = node_exploitability.exploitability();
+            output!("Exploitable for: {} ({}%)", exploitability, exploitability / (node_exploitability.pot as f64) * 100.0);
         },
-        None => println!("ERROR: Built tree not found"),
+        None => output!("ERROR: Built tree not found"),
+    };
+}
+
+// set_depth_limit flop|turn [oop_realization ip_realization] ends the tree after that street,
+// realization 1 1 values the leaves by raw equity
+fn set_depth_limit(input_params: &Vec<&str>, tree_information: &mut TreeInformation) {
+    if input_params.len() < 2 {
+        output!("ERROR: {} incorrect or missing argument", input_params[0]);
+        return;
+    }
+    let limit = match input_params[1] {
+        "off" => {
+            tree_information.depth_limit = None;
+            info!("{} ok!", input_params[0]);
+            return;
+        },
+        "flop" => 6,
+        "turn" => 8,
+        _ => {
+            output!("ERROR: {} incorrect or missing argument", input_params[0]);
+            return;
+        },
+    };
+    let realization = if input_params.len() == 4 {
+        match (input_params[2].parse::<f64>(), input_params[3].parse::<f64>()) {
+            (Ok(oop), Ok(ip)) if oop >= 0.0 && ip >= 0.0 => (oop, ip),
+            _ => {
+                output!("ERROR: {} incorrect or missing argument", input_params[0]);
+                return;
+            },
+        }
+    } else {
+        (1.0, 1.0)
     };
+    tree_information.depth_limit = Some((limit, realization.0, realization.1));
+    info!("{} ok!", input_params[0]);
 }
 
 fn add_line(input_params: &Vec<&str>, tree_information: &mut TreeInformation) {
     if input_params.len() == 1 {
-        println!("ERROR: {} incorrect or missing argument", input_params[0]);
+        output!("ERROR: {} incorrect or missing argument", input_params[0]);
     } else {
         let mut line = vec![];
         for i in 1..input_params.len() {
@@ -357,7 +619,7 @@ fn add_line(input_params: &Vec<&str>, tree_information: &mut TreeInformation) {
                 continue;
             }
             if input_params[i].parse::<u32>().is_ok() == false {
-                println!("ERROR: Invalid value");
+                output!("ERROR: Invalid value");
                 break;
             }
             line.push(input_params[i].parse::<u32>().unwrap());
@@ -367,27 +629,618 @@ fn add_line(input_params: &Vec<&str>, tree_information: &mut TreeInformation) {
         } else {
             tree_information.lines = Some(vec![line]);
         }
-        println!("{} ok!", input_params[0]);
+        info!("{} ok!", input_params[0]);
     }
 }
 
 fn clear_lines(tree_information: &mut TreeInformation) {
     tree_information.lines = None;
-    println!("clear_lines ok!");
+    info!("clear_lines ok!");
 }
 
-fn build_tree(tree_information: &mut TreeInformation, trainer: &mut Option<Trainer>) {
+// build_tree warm initializes the new tree from the solution of the current one
+fn build_tree(input_params: &Vec<&str>, tree_information: &mut TreeInformation, trainer: &mut Option<Trainer>) {
+    let warm = input_params.len() > 1 && input_params[1] == "warm";
+    match rebuild_trainer(tree_information, trainer, warm) {
+        Ok(matched) => {
+            if let Some(matched) = matched {
+                info!("SOLVER: warm started {} nodes", matched);
+            }
+            info!("build_tree ok!");
+        },
+        Err(e) => output!("ERROR: build_tree {}", e),
+    };
+}
+
+pub(crate) fn get_game_config(tree_information: &TreeInformation) -> Result<GameConfig, String> {
     if tree_information.eff_stack.is_none() || tree_information.pot.is_none() || tree_information.oop_range.is_none() || tree_information.ip_range.is_none() || tree_information.lines.is_none() || tree_information.board.is_none() {
-        println!("ERROR: build_tree missing/incorrect tree");
+        return Err("missing/incorrect tree".to_string());
+    }
+    Ok(GameConfig {
+        board: tree_information.board.as_ref().unwrap().clone(),
+        oop_range: tree_information.oop_range.as_ref().unwrap().clone(),
+        ip_range: tree_information.ip_range.as_ref().unwrap().clone(),
+        pot: tree_information.pot.unwrap(),
+        eff_stack: tree_information.eff_stack.unwrap(),
+        lines: tree_information.lines.as_ref().unwrap().clone(),
+        depth_limit: tree_information.depth_limit,
+    })
+}
+
+// replaces the trainer with a new tree, returns the number of warm started nodes
+pub(crate) fn rebuild_trainer(tree_information: &TreeInformation, trainer: &mut Option<Trainer>, warm: bool) -> Result<Option<usize>, String> {
+    let config = get_game_config(tree_information)?;
+    let snapshot = match (warm, trainer.as_ref()) {
+        (true, Some(old_trainer)) => Some(take_snapshot(old_trainer)),
+        (true, None) => return Err("no solution to warm start from".to_string()),
+        _ => None,
+    };
+    // free the old tree before building the new one
+    *trainer = None;
+    let mut new_trainer = config.build()?;
+    let matched = snapshot.map(|snapshot| warm_start(&mut new_trainer, &snapshot));
+    *trainer = Some(new_trainer);
+    Ok(matched)
+}
+
+fn save_solution(input_params: &Vec<&str>, trainer_option: &Option<Trainer>) {
+    match trainer_option {
+        Some(trainer) => {
+            if input_params.len() == 1 {
+                output!("ERROR: {} incorrect or missing argument", input_params[0]);
+            } else {
+                match save_snapshot(&take_snapshot(trainer), input_params[1]) {
+                    Ok(_) => info!("{} ok!", input_params[0]),
+                    Err(e) => output!("ERROR: {} {}", input_params[0], e),
+                };
+            }
+        },
+        None => output!("ERROR: Built tree not found"),
+    };
+}
+
+// export_strategy <line> <file> [depth] writes every decision node under the line, JSON for .json files and CSV otherwise
+fn export_strategy_cmd(input_params: &Vec<&str>, trainer_option: &Option<Trainer>) {
+    match trainer_option {
+        Some(trainer) => {
+            if input_params.len() < 3 || (input_params.len() > 3 && input_params[3].parse::<usize>().is_err()) {
+                output!("ERROR: {} incorrect or missing argument", input_params[0]);
+                return;
+            }
+            let depth = input_params.get(3).map(|x| x.parse::<usize>().unwrap());
+            match export_strategy(trainer, input_params[1], input_params[2], depth) {
+                Ok(count) => {
+                    info!("SOLVER: exported {} nodes", count);
+                    info!("{} ok!", input_params[0]);
+                },
+                Err(e) => output!("ERROR: {} {}", input_params[0], e),
+            };
+        },
+        None => output!("ERROR: Built tree not found"),
+    };
+}
+
+// report <line> <out.html> [depth] writes an HTML page with the strategy grids of the line and the nodes
+// up to depth actions and dealt cards below it, 1 by default
+fn report(input_params: &Vec<&str>, trainer_option: &Option<Trainer>) {
+    match trainer_option {
+        Some(trainer) => {
+            if input_params.len() < 3 || (input_params.len() > 3 && input_params[3].parse::<usize>().is_err()) {
+                output!("ERROR: {} incorrect or missing argument", input_params[0]);
+                return;
+            }
+            let depth = input_params.get(3).map_or(1, |x| x.parse::<usize>().unwrap());
+            match write_report(trainer, input_params[1], input_params[2], Some(depth)) {
+                Ok(count) => {
+                    info!("SOLVER: reported {} nodes", count);
+                    info!("{} ok!", input_params[0]);
+                },
+                Err(e) => output!("ERROR: {} {}", input_params[0], e),
+            };
+        },
+        None => output!("ERROR: Built tree not found"),
+    };
+}
+
+// simplify_strategy <line> round <percent>|drop <percent>|pure|one_size [subtree] locks the simplified strategy
+// of the node, or of every node below it with subtree, and prints the EVs and MES before and after
+fn simplify_strategy_cmd(input_params: &Vec<&str>, trainer_option: &mut Option<Trainer>) {
+    match trainer_option {
+        Some(trainer) => {
+            if input_params.len() < 3 {
+                output!("ERROR: {} incorrect or missing argument", input_params[0]);
+                return;
+            }
+            let percent = input_params.get(3).and_then(|x| x.parse::<f64>().ok()).filter(|x| *x > 0.0 && *x <= 100.0);
+            let (simplification, used_params) = match (input_params[2], percent) {
+                ("round", Some(percent)) => (Simplification::Round(percent), 4),
+                ("drop", Some(percent)) => (Simplification::Drop(percent), 4),
+                ("pure", _) => (Simplification::Round(100.0), 3),
+                ("one_size", _) => (Simplification::OneBetSize, 3),
+                _ => {
+                    output!("ERROR: {} incorrect or missing argument", input_params[0]);
+                    return;
+                },
+            };
+            let subtree = input_params.get(used_params) == Some(&"subtree");
+            
+            match simplify_strategy(trainer, input_params[1], simplification, subtree) {
+                Ok(result) => {
+                    let (before, after) = (result.before, result.after);
+                    info!("SOLVER: simplified {} nodes", result.nodes);
+                    output!("OOP's EV: {} -> {} ({})", before.oop_ev, after.oop_ev, after.oop_ev - before.oop_ev);
+                    output!("IP's EV: {} -> {} ({})", before.ip_ev, after.ip_ev, after.ip_ev - before.ip_ev);
+                    output!("OOP's MES: {} -> {}", before.oop_mes, after.oop_mes);
+                    output!("IP's MES: {} -> {}", before.ip_mes, after.ip_mes);
+                    output!("Exploitable for: {} -> {} ({}%)", before.exploitability, after.exploitability, after.exploitability / (trainer.root.pot_size as f64) * 100.0);
+                    info!("{} ok!", input_params[0]);
+                },
+                Err(e) => output!("ERROR: {} {}", input_params[0], e),
+            };
+        },
+        None => output!("ERROR: Built tree not found"),
+    };
+}
+
+// unlock_strategy <line> [subtree]
+fn unlock_strategy_cmd(input_params: &Vec<&str>, trainer_option: &mut Option<Trainer>) {
+    match trainer_option {
+        Some(trainer) => {
+            if input_params.len() < 2 {
+                output!("ERROR: {} incorrect or missing argument", input_params[0]);
+                return;
+            }
+            let subtree = input_params.get(2) == Some(&"subtree");
+            match unlock_strategy(trainer, input_params[1], subtree) {
+                Ok(count) => {
+                    info!("SOLVER: unlocked {} nodes", count);
+                    info!("{} ok!", input_params[0]);
+                },
+                Err(e) => output!("ERROR: {} {}", input_params[0], e),
+            };
+        },
+        None => output!("ERROR: Built tree not found"),
+    };
+}
+
+// compare_solutions <first> <second> [freq|range|ev] [count] compares two strategies exported to JSON with
+// export_strategy, checkpoints or solutions saved with save_solution and prints the count nodes differing most,
+// 20 by default. Checkpoints and saved solutions are loaded onto the tree set in the session
+fn compare_solutions_cmd(input_params: &Vec<&str>, tree_information: &TreeInformation, algorithm: &CfrAlgorithm) {
+    let compare_by = match input_params.get(3) {
+        None | Some(&"freq") => Some(CompareBy::Frequency),
+        Some(&"range") => Some(CompareBy::Range),
+        Some(&"ev") => Some(CompareBy::Ev),
+        _ => None,
+    };
+    let count = input_params.get(4).map_or(Some(20), |x| x.parse::<usize>().ok());
+    let (compare_by, count) = match (compare_by, count) {
+        (Some(compare_by), Some(count)) if input_params.len() >= 3 => (compare_by, count),
+        _ => {
+            output!("ERROR: {} incorrect or missing argument", input_params[0]);
+            return;
+        },
+    };
+    let config = get_game_config(tree_information).ok();
+    let (first, second) = match (load_solution(input_params[1], config.as_ref(), *algorithm), load_solution(input_params[2], config.as_ref(), *algorithm)) {
+        (Ok(first), Ok(second)) => (first, second),
+        (Err(e), _) | (_, Err(e)) => {
+            output!("ERROR: {} {}", input_params[0], e);
+            return;
+        },
+    };
+
+    let comparison = compare_solutions(&first, &second, compare_by);
+    info!("SOLVER: {} common nodes, {} only in the first solution, {} only in the second", comparison.nodes.len(), comparison.only_first, comparison.only_second);
+    for node in comparison.nodes.iter().take(count) {
+        let frequencies: Vec<String> = node.actions.iter().enumerate().map(|(j, action)| {
+            format!("{} {:.1}% -> {:.1}%", action, node.frequencies[j] * 100.0, node.other_frequencies[j] * 100.0)
+        }).collect();
+        output!("{} -> {} {}: frequency {:.1}% range {:.1}% EV {} -> {} | {}", node.line, node.other_line, node.player,
+            node.frequency_difference * 100.0, node.range_difference * 100.0, node.ev, node.other_ev, frequencies.join(", "));
+    }
+    info!("{} ok!", input_params[0]);
+}
+
+// practice_hand OOP|IP [seed] deals a combo of the player's range to play against the solution with practice_action
+fn practice_hand(input_params: &Vec<&str>, trainer_option: &Option<Trainer>, practice: &mut Option<PracticeHand>) {
+    match trainer_option {
+        Some(trainer) => {
+            let seed = input_params.get(2).map(|x| x.parse::<u64>().ok());
+            let (oop, seed) = match (input_params.get(1).and_then(|x| parse_player(x)), seed) {
+                (Some(oop), None) => (oop, None),
+                (Some(oop), Some(Some(seed))) => (oop, Some(seed)),
+                _ => {
+                    output!("ERROR: {} incorrect or missing argument", input_params[0]);
+                    return;
+                },
+            };
+            match PracticeHand::deal(trainer, oop, seed) {
+                Ok(practice_hand) => {
+                    let state = practice_hand.state();
+                    output!("{} holds {} on {}", state.player, state.hand, state.board);
+                    print_practice_state(&state);
+                    *practice = Some(practice_hand);
+                    info!("{} ok!", input_params[0]);
+                },
+                Err(e) => output!("ERROR: {} {}", input_params[0], e),
+            };
+        },
+        None => output!("ERROR: Built tree not found"),
+    };
+}
+
+fn print_practice_state(state: &PracticeState) {
+    for event in &state.events {
+        output!("{}", event);
+    }
+    if state.finished {
+        output!("Hand over at {}, villain held {}", state.line, state.villain_hand.as_ref().unwrap());
+        output!("EV loss: {} in {} decisions", state.ev_loss, state.decisions);
+    } else {
+        output!("{} to act at {} on {}: {}", state.player, state.line, state.board, state.actions.join(" "));
+    }
+}
+
+// practice_action <action> plays the action, prints the solution's frequencies and EVs of the hand and plays on
+fn practice_action(input_params: &Vec<&str>, trainer_option: &Option<Trainer>, practice: &mut Option<PracticeHand>) {
+    match (trainer_option, practice.as_mut()) {
+        (Some(trainer), Some(practice_hand)) => {
+            if input_params.len() < 2 {
+                output!("ERROR: {} incorrect or missing argument", input_params[0]);
+                return;
+            }
+            match practice_hand.play(trainer, input_params[1]) {
+                Ok(decision) => {
+                    for (j, action) in decision.actions.iter().enumerate() {
+                        output!("{}: {:.1}% EV {}", action, decision.frequencies[j] * 100.0, decision.evs[j]);
+                    }
+                    output!("Played {} ({:.1}%), best {}, EV loss: {}", decision.action, decision.frequency * 100.0, decision.best_action, decision.ev_loss);
+                    print_practice_state(&practice_hand.state());
+                    info!("{} ok!", input_params[0]);
+                },
+                Err(e) => output!("ERROR: {} {}", input_params[0], e),
+            };
+        },
+        (None, _) => output!("ERROR: Built tree not found"),
+        (_, None) => output!("ERROR: {} no hand dealt, use practice_hand", input_params[0]),
+    };
+}
+
+// lookup_hand OOP|IP <hand> <board> [actions] prints the solution for hero's hand at each of hero's decisions of
+// a hand history. Actions are separated by spaces or colons, e.g. x:b30:c for check, bet 30 on the street, call
+fn lookup_hand_cmd(input_params: &Vec<&str>, trainer_option: &Option<Trainer>) {
+    match trainer_option {
+        Some(trainer) => {
+            let oop = match input_params.get(1).and_then(|x| parse_player(x)) {
+                Some(oop) if input_params.len() >= 4 => oop,
+                _ => {
+                    output!("ERROR: {} incorrect or missing argument", input_params[0]);
+                    return;
+                },
+            };
+            let actions: Vec<&str> = input_params[4..].iter().flat_map(|x| x.split(':')).filter(|x| x.len() > 0).collect();
+            match lookup_hand(trainer, oop, input_params[2], input_params[3], &actions) {
+                Ok(lookup) => {
+                    for (action, tree_action) in &lookup.translations {
+                        info!("SOLVER: {} mapped to {}", action, tree_action);
+                    }
+                    for decision in &lookup.decisions {
+                        let actions: Vec<String> = decision.actions.iter().enumerate().map(|(j, action)| {
+                            format!("{} {:.1}% EV {}", action, decision.frequencies[j] * 100.0, decision.evs[j])
+                        }).collect();
+                        let played = match &decision.played {
+                            Some((action, tree_action)) => format!(" | played {} as {}", action, tree_action),
+                            None => "".to_string(),
+                        };
+                        output!("{} on {}, EV {}: {}{}", decision.line, decision.board, decision.ev, actions.join(", "), played);
+                    }
+                    output!("Line: {}", lookup.line);
+                    info!("{} ok!", input_params[0]);
+                },
+                Err(e) => output!("ERROR: {} {}", input_params[0], e),
+            };
+        },
+        None => output!("ERROR: Built tree not found"),
+    };
+}
+
+fn warm_start_cmd(input_params: &Vec<&str>, trainer_option: &mut Option<Trainer>) {
+    match trainer_option {
+        Some(trainer) => {
+            if input_params.len() == 1 {
+                output!("ERROR: {} incorrect or missing argument", input_params[0]);
+            } else {
+                match load_snapshot(input_params[1]) {
+                    Ok(snapshot) => {
+                        let matched = warm_start(trainer, &snapshot);
+                        info!("SOLVER: warm started {} nodes", matched);
+                        info!("{} ok!", input_params[0]);
+                    },
+                    Err(e) => output!("ERROR: {} {}", input_params[0], e),
+                };
+            }
+        },
+        None => output!("ERROR: Built tree not found"),
+    };
+}
+
+// resolve_subgame <line> [safe OOP|IP] re-solves the turn or river subgame at line with the current lines,
+// safe keeps the parent counterfactual values of the opponent of the given player as a lower bound
+fn resolve_subgame(input_params: &Vec<&str>, tree_information: &TreeInformation, trainer_option: &mut Option<Trainer>, parent_trainer: &mut Option<Trainer>, hand_order: &Vec<String>, hand_order_map: &HashMap<String, usize>) {
+    if input_params.len() < 2 || (input_params.len() > 2 && (input_params.len() != 4 || input_params[2] != "safe")) {
+        output!("ERROR: {} incorrect or missing argument", input_params[0]);
+        return;
+    }
+    let safe = if input_params.len() == 4 {
+        match input_params[3] {
+            "OOP" | "oop" => Some(false),
+            "IP" | "ip" => Some(true),
+            _ => {
+                output!("ERROR: {} incorrect player", input_params[0]);
+                return;
+            },
+        }
+    } else {
+        None
+    };
+    let lines = match &tree_information.lines {
+        Some(x) => x.clone(),
+        None => {
+            output!("ERROR: {} missing lines", input_params[0]);
+            return;
+        },
+    };
+    
+    match trainer_option {
+        Some(trainer) => {
+            match get_subgame(trainer, &input_params[1].to_string(), hand_order, hand_order_map) {
+                Ok(subgame) => {
+                    let subgame_trainer = build_subgame(&subgame, lines, safe);
+                    info!("SOLVER: subgame {} pot {} effective stack {}", subgame.board, subgame.pot, subgame.eff_stack);
+                    *parent_trainer = trainer_option.take();
+                    *trainer_option = Some(subgame_trainer);
+                    info!("{} ok!", input_params[0]);
+                },
+                Err(e) => output!("ERROR: {} {}", input_params[0], e),
+            };
+        },
+        None => output!("ERROR: Built tree not found"),
+    };
+}
+
+fn exit_subgame(input_params: &Vec<&str>, trainer_option: &mut Option<Trainer>, parent_trainer: &mut Option<Trainer>) {
+    match parent_trainer.take() {
+        Some(parent) => {
+            *trainer_option = Some(parent);
+            info!("{} ok!", input_params[0]);
+        },
+        None => output!("ERROR: {} not in a subgame", input_params[0]),
+    };
+}
+
+fn list_flops(input_params: &Vec<&str>) {
+    let name = if input_params.len() > 1 && input_params[1] != "" {
+        input_params[1]
+    } else {
+        "all"
+    };
+    
+    match get_named_flop_subset(name) {
+        Some(flops) => {
+            for (flop, weight) in &flops {
+                output!("{} {}", flop, weight);
+            }
+        },
+        None => output!("ERROR: {} unknown subset {}", input_params[0], name),
+    };
+}
+
+pub(crate) fn get_tree_template(tree_information: &TreeInformation, algorithm: &CfrAlgorithm, sampling: &(ChanceSampling, f64), pruning_interval: &Option<u64>, info_freq: u64) -> Option<TreeTemplate> {
+    match (&tree_information.oop_range, &tree_information.ip_range, tree_information.eff_stack, tree_information.pot, &tree_information.lines) {
+        (Some(oop_range), Some(ip_range), Some(eff_stack), Some(pot), Some(lines)) => {
+            Some(TreeTemplate { oop_range: oop_range.clone(), ip_range: ip_range.clone(), eff_stack, pot, lines: lines.clone(), depth_limit: tree_information.depth_limit, algorithm: *algorithm, sampling: *sampling, pruning_interval: *pruning_interval, info_freq })
+        },
+        _ => None,
+    }
+}
+
+fn solve_batch_cmd(input_params: &Vec<&str>, tree_information: &TreeInformation, accuracy: &Accuracy, algorithm: &CfrAlgorithm, sampling: &(ChanceSampling, f64), pruning_interval: &Option<u64>, info_freq: u64) {
+    if input_params.len() < 3 {
+        output!("ERROR: {} incorrect or missing argument", input_params[0]);
+        return;
+    }
+    let template = match get_tree_template(tree_information, algorithm, sampling, pruning_interval, info_freq) {
+        Some(x) => x,
+        None => {
+            output!("ERROR: {} missing/incorrect tree", input_params[0]);
+            return;
+        },
+    };
+    
+    // either a named subset or comma separated flops
+    let flops = match parse_flops(input_params[2]) {
+        Ok(x) => x,
+        Err(e) => {
+            output!("ERROR: {} {}", input_params[0], e);
+            return;
+        },
+    };
+    
+    let train_finish = if input_params.len() > 4 && input_params[3].parse::<u64>().is_ok() == true && (input_params[4] == "seconds" || input_params[4] == "steps") {
+        if input_params[4] == "seconds" {
+            TrainFinish::Seconds(input_params[3].parse::<u64>().unwrap())
+        } else {
+            TrainFinish::Iterations(input_params[3].parse::<u64>().unwrap())
+        }
     } else {
-        let mut range_manager = RangeManager::new(tree_information.oop_range.as_ref().unwrap().clone(), tree_information.ip_range.as_ref().unwrap().clone(), tree_information.board.as_ref().unwrap().clone());
-        let mut new_trainer = Trainer::new(range_manager, tree_information.lines.as_ref().unwrap().clone(), tree_information.eff_stack.unwrap(), tree_information.pot.unwrap());
-        *trainer = Some(new_trainer);
-        println!("build_tree ok!");
+        TrainFinish::Indefinite
+    };
+    
+    info!("{} ok!", input_params[0]);
+    let mut current_flop = None;
+    let results = solve_batch(&template, &flops, accuracy, train_finish, None, &mut |i, progress| {
+        if current_flop != Some(i) {
+            info!("SOLVER: flop {} ({}/{})", flops[i].0, i+1, flops.len());
+            current_flop = Some(i);
+        }
+        progress.print(template.pot);
+    });
+    match write_batch_report(&results, input_params[1]) {
+        Ok(_) => info!("SOLVER: batch finished, report written to {}", input_params[1]),
+        Err(e) => output!("ERROR: {} couldn't write report: {}", input_params[0], e),
+    };
+}
+
+fn set_algorithm(input_params: &Vec<&str>, algorithm: &mut CfrAlgorithm) {
+    if input_params.len() == 1 {
+        output!("ERROR: {} incorrect or missing argument", input_params[0]);
+        return;
     }
+    
+    let new_algorithm = match input_params[1] {
+        "dcfr" => {
+            if input_params.len() == 2 {
+                Some(CfrAlgorithm::default())
+            } else if input_params.len() < 5 {
+                None
+            } else {
+                match (input_params[2].parse::<f64>(), input_params[3].parse::<f64>(), input_params[4].parse::<f64>()) {
+                    (Ok(alpha), Ok(beta), Ok(gamma)) => Some(CfrAlgorithm::Dcfr { alpha, beta, gamma }),
+                    _ => None,
+                }
+            }
+        },
+        "cfr+" => Some(CfrAlgorithm::CfrPlus),
+        "linear" => Some(CfrAlgorithm::LinearCfr),
+        "vanilla" => Some(CfrAlgorithm::VanillaCfr),
+        _ => None,
+    };
+    
+    match new_algorithm {
+        Some(x) => {
+            *algorithm = x;
+            info!("{} ok!", input_params[0]);
+        },
+        None => output!("ERROR: {} incorrect or missing argument", input_params[0]),
+    };
 }
 
-fn go(input_params: &Vec<&str>, trainer_option: &mut Option<Trainer>, accuracy: &Accuracy, end_string: &String) {
+// set_sampling full | set_sampling <cards per chance node> [<exploitability % of pot to switch to full traversal>]
+fn set_sampling(input_params: &Vec<&str>, sampling: &mut (ChanceSampling, f64)) {
+    if input_params.len() == 1 {
+        output!("ERROR: {} incorrect or missing argument", input_params[0]);
+    } else if input_params[1] == "full" {
+        *sampling = (ChanceSampling::Full, 0.0);
+        info!("{} ok!", input_params[0]);
+    } else if input_params[1].parse::<usize>().is_ok() == false || input_params[1].parse::<usize>().unwrap() == 0 {
+        output!("ERROR: Invalid value");
+    } else {
+        let polish = if input_params.len() > 2 {
+            match input_params[2].parse::<f64>() {
+                Ok(x) if x >= 0.0 => x,
+                _ => {
+                    output!("ERROR: Invalid value");
+                    return;
+                },
+            }
+        } else {
+            0.0
+        };
+        *sampling = (ChanceSampling::Sampled(input_params[1].parse::<usize>().unwrap()), polish);
+        info!("{} ok!", input_params[0]);
+    }
+}
+
+// set_pruning off | set_pruning <full pass every n iterations>
+fn set_pruning(input_params: &Vec<&str>, pruning_interval: &mut Option<u64>) {
+    if input_params.len() == 1 {
+        output!("ERROR: {} incorrect or missing argument", input_params[0]);
+    } else if input_params[1] == "off" {
+        *pruning_interval = None;
+        info!("{} ok!", input_params[0]);
+    } else if input_params[1].parse::<u64>().is_ok() == false || input_params[1].parse::<u64>().unwrap() == 0 {
+        output!("ERROR: Invalid value");
+    } else {
+        *pruning_interval = Some(input_params[1].parse::<u64>().unwrap());
+        info!("{} ok!", input_params[0]);
+    }
+}
+
+// set_info_freq <iterations between exploitability checks>
+fn set_info_freq(input_params: &Vec<&str>, info_freq: &mut u64) {
+    if input_params.len() == 1 {
+        output!("ERROR: {} incorrect or missing argument", input_params[0]);
+    } else if input_params[1].parse::<u64>().is_ok() == false || input_params[1].parse::<u64>().unwrap() == 0 {
+        output!("ERROR: Invalid value");
+    } else {
+        *info_freq = input_params[1].parse::<u64>().unwrap();
+        info!("{} ok!", input_params[0]);
+    }
+}
+
+fn set_checkpoint(input_params: &Vec<&str>, checkpoint: &mut Option<CheckpointConfig>) {
+    if input_params.len() == 2 && input_params[1] == "off" {
+        *checkpoint = None;
+        info!("{} ok!", input_params[0]);
+    } else if input_params.len() < 4 {
+        output!("ERROR: {} incorrect or missing argument", input_params[0]);
+    } else if input_params[2].parse::<u64>().is_ok() == false || input_params[2].parse::<u64>().unwrap() == 0 {
+        output!("ERROR: Invalid value");
+    } else {
+        let value = input_params[2].parse::<u64>().unwrap();
+        let frequency = match input_params[3] {
+            "steps" => CheckpointFrequency::Iterations(value),
+            "seconds" => CheckpointFrequency::Seconds(value),
+            "minutes" => CheckpointFrequency::Seconds(value * 60),
+            _ => {
+                output!("ERROR: {} incorrect or missing argument", input_params[0]);
+                return;
+            },
+        };
+        *checkpoint = Some(CheckpointConfig { path: input_params[1].to_string(), frequency });
+        info!("{} ok!", input_params[0]);
+    }
+}
+
+fn save_checkpoint_cmd(input_params: &Vec<&str>, trainer_option: &Option<Trainer>) {
+    match trainer_option {
+        Some(trainer) => {
+            if input_params.len() == 1 {
+                output!("ERROR: {} incorrect or missing argument", input_params[0]);
+            } else {
+                match save_checkpoint(trainer, input_params[1]) {
+                    Ok(_) => info!("{} ok!", input_params[0]),
+                    Err(e) => output!("ERROR: {} {}", input_params[0], e),
+                };
+            }
+        },
+        None => output!("ERROR: Built tree not found"),
+    };
+}
+
+// the tree must be built with the same settings and algorithm as the one the checkpoint was saved from
+fn load_checkpoint_cmd(input_params: &Vec<&str>, trainer_option: &mut Option<Trainer>, algorithm: &CfrAlgorithm) {
+    match trainer_option {
+        Some(trainer) => {
+            if input_params.len() == 1 {
+                output!("ERROR: {} incorrect or missing argument", input_params[0]);
+            } else {
+                trainer.algorithm = *algorithm;
+                match load_checkpoint(trainer, input_params[1]) {
+                    Ok(iteration) => {
+                        trainer.iteration = iteration;
+                        info!("{} ok!", input_params[0]);
+                    },
+                    Err(e) => output!("ERROR: {} {}", input_params[0], e),
+                };
+            }
+        },
+        None => output!("ERROR: Built tree not found"),
+    };
+}
+
+fn go(input_params: &Vec<&str>, trainer_option: &mut Option<Trainer>, accuracy: &Accuracy, end_string: &String, checkpoint: &Option<CheckpointConfig>, algorithm: &CfrAlgorithm, sampling: &(ChanceSampling, f64), pruning_interval: &Option<u64>, info_freq: u64) {
     match trainer_option {
         Some(trainer) => {
             let train_finish = if input_params.len() == 1 || (input_params.len() == 2 && input_params[1] == "") {
@@ -402,21 +1255,31 @@ fn go(input_params: &Vec<&str>, trainer_option: &mut Option<Trainer>, accuracy:
                 None
             };
             if let Some(x) = train_finish {
-                println!("SOLVER: started");
-                println!("{} ok!", input_params[0]);
+                info!("SOLVER: started");
+                info!("{} ok!", input_params[0]);
                 if end_string.len() > 0 {
-                    println!("{}",end_string);
+                    output!("{}",end_string);
                 }
+                apply_solver_settings(trainer, checkpoint, algorithm, sampling, pruning_interval, info_freq);
                 trainer.train(accuracy, x);
-                println!("SOLVER: stopped (required accuracy reached)");
+                info!("SOLVER: stopped (required accuracy reached)");
             }
         },
         None => {
-            println!("ERROR: Built tree not found");
+            output!("ERROR: Built tree not found");
             if end_string.len() > 0 {
-                println!("{}",end_string);
+                output!("{}",end_string);
             }
         },
         
     };
-}
\ No newline at end of file
+}
+
+pub(crate) fn apply_solver_settings(trainer: &mut Trainer, checkpoint: &Option<CheckpointConfig>, algorithm: &CfrAlgorithm, sampling: &(ChanceSampling, f64), pruning_interval: &Option<u64>, info_freq: u64) {
+    trainer.checkpoint = checkpoint.clone();
+    trainer.algorithm = *algorithm;
+    trainer.sampling = sampling.0;
+    trainer.sampling_polish = sampling.1;
+    trainer.pruning_interval = *pruning_interval;
+    trainer.info_freq = info_freq;
+}
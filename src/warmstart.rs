@@ -0,0 +1,288 @@
+use crate::postfloptree::*;
+use crate::range::*;
+use crate::trainer::*;
+use crate::checkpoint::{write_u64, read_u64, write_f64_slice, read_f64_vec, invalid_data};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use rust_poker::hand_range::{get_card_mask, mask_to_string};
+
+pub(crate) const SOLUTION_MAGIC: &[u8; 4] = b"OSWS";
+const SOLUTION_VERSION: u32 = 3;
+
+#[derive(Debug, Clone)]
+pub struct NodeSnapshot {
+    pub actions: Vec<ActionType>,
+    pub combos: Vec<(u8, u8)>,
+    pub regret_sum: Vec<f64>,
+    pub strategy_sum: Vec<f64>,
+    pub locked_strategy: Option<Vec<f64>>,
+}
+
+// Solver state keyed by action line and combo, so it can be mapped onto a tree built with
+// slightly different sizings or ranges. Lines use street relative sizings and dealt cards, e.g. r:x:b50:c:Kd
+#[derive(Debug)]
+pub struct SolutionSnapshot {
+    pub iteration: u64,
+    pub nodes: HashMap<String, NodeSnapshot>,
+}
+
+fn child_line(line: &String, board_masks: (u64, Option<u64>), child: &Node) -> String {
+    match child.node_type {
+        NodeType::ChanceNodeCard((new, _)) if new != board_masks.0 => format!("{}:{}", line, mask_to_string(new & !board_masks.0)),
+        _ => line.clone(),
+    }
+}
+
+fn collect_snapshots(node: &Node, line: String, board_masks: (u64, Option<u64>), range_manager: &RangeManager, nodes: &mut HashMap<String, NodeSnapshot>) {
+    match &node.node_type {
+        NodeType::ActionNode(node_info) => {
+            let combos = range_manager.get_range(node_info.oop, board_masks.0, board_masks.1).hands.iter().map(|c| (c.0, c.1)).collect();
+            nodes.insert(line.clone(), NodeSnapshot { actions: node_info.actions.clone(), combos, regret_sum: node_info.get_regret_sum().to_vec(), strategy_sum: node_info.strategy_sum.clone(), locked_strategy: node_info.locked_strategy.clone() });
+            for (i, child) in node.children.iter().enumerate() {
+                collect_snapshots(child, format!("{}:{}", line, node_info.actions[i]), board_masks, range_manager, nodes);
+            }
+        },
+        NodeType::ChanceNode(_) => {
+            for child in &node.children {
+                collect_snapshots(child, child_line(&line, board_masks, child), board_masks, range_manager, nodes);
+            }
+        },
+        NodeType::ChanceNodeCard(new_masks) => {
+            collect_snapshots(&node.children[0], line, *new_masks, range_manager, nodes);
+        },
+        NodeType::TerminalNode(_) => (),
+    }
+}
+
+pub fn take_snapshot(trainer: &Trainer) -> SolutionSnapshot {
+    let mut nodes = HashMap::new();
+    let board_masks = (get_card_mask(&trainer.range_manager.initial_board), None);
+    collect_snapshots(&trainer.root, "r".to_string(), board_masks, &trainer.range_manager, &mut nodes);
+    SolutionSnapshot { iteration: trainer.iteration, nodes }
+}
+
+fn apply_node_snapshot(node_info: &mut ActionNodeInfo, combos: &[(u8, u8)], snapshot: &NodeSnapshot) {
+    let hands_num = combos.len();
+    let old_hands_num = snapshot.combos.len();
+    let action_mapping: Vec<Option<usize>> = node_info.actions.iter().map(|a| snapshot.actions.iter().position(|x| x == a)).collect();
+    let mut combo_mapping = HashMap::new();
+    for (i, combo) in snapshot.combos.iter().enumerate() {
+        combo_mapping.insert(*combo, i);
+    }
+
+    let mut regret_sum = vec![0.0; hands_num * node_info.actions_num];
+    let mut strategy_sum = vec![0.0; hands_num * node_info.actions_num];
+    let mut locked_strategy = snapshot.locked_strategy.as_ref().map(|_| vec![0.0; hands_num * node_info.actions_num]);
+    for (i, combo) in combos.iter().enumerate() {
+        if let Some(old_i) = combo_mapping.get(combo) {
+            for (j, old_j) in action_mapping.iter().enumerate() {
+                if let Some(old_j) = old_j {
+                    regret_sum[j*hands_num + i] = snapshot.regret_sum[old_j*old_hands_num + old_i];
+                    strategy_sum[j*hands_num + i] = snapshot.strategy_sum[old_j*old_hands_num + old_i];
+                    if let (Some(locked), Some(old_locked)) = (&mut locked_strategy, &snapshot.locked_strategy) {
+                        locked[j*hands_num + i] = old_locked[old_j*old_hands_num + old_i];
+                    }
+                }
+            }
+        }
+    }
+
+    // locked hands renormalize over the actions kept from the snapshot, hands that weren't in it play uniformly
+    if let Some(locked) = &mut locked_strategy {
+        normalize_strategy(locked, hands_num, node_info.actions_num);
+    }
+    node_info.set_sums(regret_sum, strategy_sum);
+    node_info.locked_strategy = locked_strategy;
+}
+
+fn apply_snapshots(node: &mut Node, line: String, board_masks: (u64, Option<u64>), range_manager: &RangeManager, snapshot: &SolutionSnapshot) -> usize {
+    let mut matched = 0;
+    match &mut node.node_type {
+        NodeType::ActionNode(node_info) => {
+            if let Some(node_snapshot) = snapshot.nodes.get(&line) {
+                let combos: Vec<(u8, u8)> = range_manager.get_range(node_info.oop, board_masks.0, board_masks.1).hands.iter().map(|c| (c.0, c.1)).collect();
+                apply_node_snapshot(node_info, &combos, node_snapshot);
+                matched += 1;
+            }
+            let actions = node_info.actions.clone();
+            for (i, child) in node.children.iter_mut().enumerate() {
+                matched += apply_snapshots(child, format!("{}:{}", line, actions[i]), board_masks, range_manager, snapshot);
+            }
+        },
+        NodeType::ChanceNode(_) => {
+            for child in node.children.iter_mut() {
+                let new_line = child_line(&line, board_masks, child);
+                matched += apply_snapshots(child, new_line, board_masks, range_manager, snapshot);
+            }
+        },
+        NodeType::ChanceNodeCard(new_masks) => {
+            let new_masks = *new_masks;
+            matched += apply_snapshots(&mut node.children[0], line, new_masks, range_manager, snapshot);
+        },
+        NodeType::TerminalNode(_) => (),
+    }
+    matched
+}
+
+// Initializes regrets and strategy sums of a freshly built tree from a solution and continues
+// the DCFR iteration count, returns the number of matched action nodes
+pub fn warm_start(trainer: &mut Trainer, snapshot: &SolutionSnapshot) -> usize {
+    let board_masks = (get_card_mask(&trainer.range_manager.initial_board), None);
+    let matched = apply_snapshots(&mut trainer.root, "r".to_string(), board_masks, &trainer.range_manager, snapshot);
+    trainer.iteration = snapshot.iteration;
+    matched
+}
+
+fn write_action(writer: &mut impl Write, action: &ActionType) -> io::Result<()> {
+    let (tag, sizing) = match action {
+        ActionType::Fold => (0u8, 0),
+        ActionType::Check => (1u8, 0),
+        ActionType::Call => (2u8, 0),
+        ActionType::Bet(sizing) => (3u8, *sizing),
+        ActionType::Raise{sizing} => (4u8, *sizing),
+    };
+    writer.write_all(&[tag])?;
+    writer.write_all(&sizing.to_le_bytes())
+}
+
+fn read_action(reader: &mut impl Read) -> io::Result<ActionType> {
+    let mut tag = [0u8; 1];
+    let mut sizing = [0u8; 4];
+    reader.read_exact(&mut tag)?;
+    reader.read_exact(&mut sizing)?;
+    let sizing = u32::from_le_bytes(sizing);
+    match tag[0] {
+        0 => Ok(ActionType::Fold),
+        1 => Ok(ActionType::Check),
+        2 => Ok(ActionType::Call),
+        3 => Ok(ActionType::Bet(sizing)),
+        4 => Ok(ActionType::Raise{sizing}),
+        _ => Err(invalid_data("invalid action in solution file")),
+    }
+}
+
+pub fn save_snapshot(snapshot: &SolutionSnapshot, path: &str) -> io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    writer.write_all(SOLUTION_MAGIC)?;
+    writer.write_all(&SOLUTION_VERSION.to_le_bytes())?;
+    write_u64(&mut writer, snapshot.iteration)?;
+    write_u64(&mut writer, snapshot.nodes.len() as u64)?;
+
+    for (line, node) in &snapshot.nodes {
+        write_u64(&mut writer, line.len() as u64)?;
+        writer.write_all(line.as_bytes())?;
+        write_u64(&mut writer, node.actions.len() as u64)?;
+        for action in &node.actions {
+            write_action(&mut writer, action)?;
+        }
+        write_u64(&mut writer, node.combos.len() as u64)?;
+        for combo in &node.combos {
+            writer.write_all(&[combo.0, combo.1])?;
+        }
+        write_f64_slice(&mut writer, &node.regret_sum)?;
+        write_f64_slice(&mut writer, &node.strategy_sum)?;
+        match &node.locked_strategy {
+            Some(locked_strategy) => {
+                write_u64(&mut writer, 1)?;
+                write_f64_slice(&mut writer, locked_strategy)?;
+            },
+            None => write_u64(&mut writer, 0)?,
+        };
+    }
+    writer.flush()
+}
+
+// reads a length field of items of item_size bytes, lengths that couldn't fit in the file are rejected
+// before anything is allocated for them
+fn read_len(reader: &mut impl Read, item_size: u64, file_len: u64) -> io::Result<usize> {
+    let len = read_u64(reader)?;
+    match len.checked_mul(item_size) {
+        Some(size) if size <= file_len => Ok(len as usize),
+        _ => Err(invalid_data("corrupt solution file")),
+    }
+}
+
+pub fn load_snapshot(path: &str) -> io::Result<SolutionSnapshot> {
+    let file = File::open(path)?;
+    let file_len = file.metadata()?.len();
+    let mut reader = BufReader::new(file);
+
+    let mut magic = [0u8; 4];
+    reader.read_exact(&mut magic)?;
+    let mut version = [0u8; 4];
+    reader.read_exact(&mut version)?;
+    if &magic != SOLUTION_MAGIC || u32::from_le_bytes(version) != SOLUTION_VERSION {
+        return Err(invalid_data("not a solution file"));
+    }
+
+    let iteration = read_u64(&mut reader)?;
+    let nodes_num = read_len(&mut reader, 1, file_len)?;
+    let mut nodes = HashMap::new();
+
+    for _ in 0..nodes_num {
+        let mut line = vec![0u8; read_len(&mut reader, 1, file_len)?];
+        reader.read_exact(&mut line)?;
+        let line = String::from_utf8(line).map_err(|_| invalid_data("invalid line in solution file"))?;
+
+        let mut actions = vec![];
+        for _ in 0..read_len(&mut reader, 5, file_len)? {
+            actions.push(read_action(&mut reader)?);
+        }
+        let mut combos = vec![];
+        for _ in 0..read_len(&mut reader, 2, file_len)? {
+            let mut combo = [0u8; 2];
+            reader.read_exact(&mut combo)?;
+            combos.push((combo[0], combo[1]));
+        }
+        let len = match actions.len().checked_mul(combos.len()) {
+            Some(len) if len as u64 * 8 <= file_len => len,
+            _ => return Err(invalid_data("corrupt solution file")),
+        };
+        let regret_sum = read_f64_vec(&mut reader, len)?;
+        let strategy_sum = read_f64_vec(&mut reader, len)?;
+        let locked_strategy = match read_u64(&mut reader)? {
+            0 => None,
+            _ => Some(read_f64_vec(&mut reader, len)?),
+        };
+
+        nodes.insert(line, NodeSnapshot { actions, combos, regret_sum, strategy_sum, locked_strategy });
+    }
+
+    Ok(SolutionSnapshot { iteration, nodes })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::*;
+
+    #[test]
+    fn test_snapshot_round_trip() {
+        let path = std::env::temp_dir().join(format!("opensolver_snapshot_{}.bin", std::process::id()));
+        let path = path.to_str().unwrap();
+
+        let mut trainer = test_config("2c7d9hTsJs").build().unwrap();
+        trainer.solve(&Accuracy::Chips(0.0), TrainFinish::Iterations(10), None, &mut |_| ());
+        let locked_line = "r:0:c".to_string();
+        if let NodeType::ActionNode(node_info) = &mut trainer.root.find_node_mut(&locked_line, &trainer.range_manager).node_type {
+            node_info.locked_strategy = Some(node_info.get_average_strategy());
+        }
+        let snapshot = take_snapshot(&trainer);
+        save_snapshot(&snapshot, path).unwrap();
+
+        let mut warm = test_config("2c7d9hTsJs").build().unwrap();
+        assert_eq!(warm_start(&mut warm, &load_snapshot(path).unwrap()), snapshot.nodes.len());
+        assert_eq!(warm.iteration, 10);
+        assert_eq!(get_strategy(&warm, "r:0").unwrap().frequencies, get_strategy(&trainer, "r:0").unwrap().frequencies);
+        assert_eq!(get_strategy(&warm, &locked_line).unwrap().frequencies, get_strategy(&trainer, &locked_line).unwrap().frequencies);
+        assert!(matches!(&warm.root.find_node_mut(&locked_line, &warm.range_manager).node_type, NodeType::ActionNode(node_info) if node_info.locked_strategy.is_some()));
+
+        // a length field past the end of the file, right after the header
+        let mut bytes = std::fs::read(path).unwrap();
+        bytes[24..32].copy_from_slice(&u64::MAX.to_le_bytes());
+        std::fs::write(path, bytes).unwrap();
+        assert_eq!(load_snapshot(path).unwrap_err().kind(), io::ErrorKind::InvalidData);
+        let _ = std::fs::remove_file(path);
+    }
+}